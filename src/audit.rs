@@ -0,0 +1,199 @@
+// 审计日志：以 JSONL 追加记录每次切换、保存、重命名、删除和自动同步
+
+use crate::csv;
+use crate::t;
+use chrono::{DateTime, Local, Utc};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::Path;
+
+pub const AUDIT_LOG_NAME: &str = "audit_log.jsonl";
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum AuditAction {
+    SetAsDefault,
+    SaveCurrentFile,
+    RenameSelectedFile,
+    DeleteSelectedFile,
+    AutomaticSync,
+}
+
+impl AuditAction {
+    pub const ALL: [AuditAction; 5] = [
+        AuditAction::SetAsDefault,
+        AuditAction::SaveCurrentFile,
+        AuditAction::RenameSelectedFile,
+        AuditAction::DeleteSelectedFile,
+        AuditAction::AutomaticSync,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            AuditAction::SetAsDefault => t!("audit.action.set_as_default"),
+            AuditAction::SaveCurrentFile => t!("audit.action.save"),
+            AuditAction::RenameSelectedFile => t!("audit.action.rename"),
+            AuditAction::DeleteSelectedFile => t!("audit.action.delete"),
+            AuditAction::AutomaticSync => t!("audit.action.automatic_sync"),
+        }
+    }
+
+    pub fn key(self) -> &'static str {
+        match self {
+            AuditAction::SetAsDefault => "set_as_default",
+            AuditAction::SaveCurrentFile => "save_current_file",
+            AuditAction::RenameSelectedFile => "rename_selected_file",
+            AuditAction::DeleteSelectedFile => "delete_selected_file",
+            AuditAction::AutomaticSync => "automatic_sync",
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum AuditResult {
+    Success,
+    Failure,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct AuditEntry {
+    pub timestamp: String,
+    pub action: AuditAction,
+    pub profile: String,
+    // 写入（或匹配到的）Claude 活动配置内容的 SHA-256，未涉及活动配置时为空
+    pub active_settings_hash: Option<String>,
+    pub result: AuditResult,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub detail: String,
+}
+
+impl AuditEntry {
+    pub fn new(action: AuditAction, profile: impl Into<String>, active_content: Option<&str>, result: Result<(), String>) -> Self {
+        let (result, detail) = match result {
+            Ok(()) => (AuditResult::Success, String::new()),
+            Err(message) => (AuditResult::Failure, message),
+        };
+        Self {
+            timestamp: Local::now().to_rfc3339(),
+            action,
+            profile: profile.into(),
+            active_settings_hash: active_content.map(content_hash),
+            result,
+            detail,
+        }
+    }
+
+    // 附加说明；已有失败信息时放在信息前面
+    pub fn with_detail(mut self, detail: impl Into<String>) -> Self {
+        let detail = detail.into();
+        self.detail = if self.detail.is_empty() { detail } else { format!("{}: {}", detail, self.detail) };
+        self
+    }
+
+    pub fn time(&self) -> Option<DateTime<Utc>> {
+        DateTime::parse_from_rfc3339(&self.timestamp).ok().map(|t| t.with_timezone(&Utc))
+    }
+}
+
+pub fn content_hash(content: &str) -> String {
+    format!("{:x}", Sha256::digest(content.as_bytes()))
+}
+
+// 只追加，不改写已有记录
+pub fn append_entry(path: &Path, entry: &AuditEntry) -> std::io::Result<()> {
+    let line = serde_json::to_string(entry)?;
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    writeln!(file, "{}", line)
+}
+
+// 按写入顺序读取全部记录
+pub fn read_entries(path: &Path) -> Vec<AuditEntry> {
+    let content = fs::read_to_string(path).unwrap_or_default();
+    content.lines().filter_map(|line| serde_json::from_str(line).ok()).collect()
+}
+
+pub fn to_csv(entries: &[AuditEntry]) -> String {
+    let mut csv = String::from("timestamp,action,profile,active_settings_hash,result,detail\n");
+    for entry in entries {
+        csv.push_str(&format!(
+            "{},{},{},{},{},{}\n",
+            csv::field(&entry.timestamp),
+            entry.action.key(),
+            csv::field(&entry.profile),
+            entry.active_settings_hash.as_deref().unwrap_or_default(),
+            if entry.result == AuditResult::Success { "success" } else { "failure" },
+            csv::field(&entry.detail),
+        ));
+    }
+    csv
+}
+
+// 由审计日志得到的活动配置时间线，用于回答“某个时间点使用的是哪个配置”
+pub struct SwitchHistory {
+    records: Vec<(DateTime<Utc>, String)>,
+}
+
+impl SwitchHistory {
+    pub fn from_entries(entries: &[AuditEntry]) -> Self {
+        let mut records: Vec<(DateTime<Utc>, String)> = entries
+            .iter()
+            .filter(|e| e.result == AuditResult::Success)
+            .filter(|e| matches!(e.action, AuditAction::SetAsDefault | AuditAction::AutomaticSync))
+            .filter_map(|e| e.time().map(|t| (t, e.profile.clone())))
+            .collect();
+        records.sort_by_key(|(t, _)| *t);
+        Self { records }
+    }
+
+    pub fn load(path: &Path) -> Self {
+        Self::from_entries(&read_entries(path))
+    }
+
+    // 返回指定时间点生效的配置
+    pub fn profile_at(&self, time: DateTime<Utc>) -> Option<&str> {
+        let index = self.records.partition_point(|(t, _)| *t <= time);
+        index.checked_sub(1).map(|i| self.records[i].1.as_str())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn switch_history_uses_successful_switches() {
+        let at = |time: &str| DateTime::parse_from_rfc3339(time).unwrap().with_timezone(&Utc);
+        let entry = |time: &str, action, profile: &str, result: Result<(), String>| AuditEntry {
+            timestamp: time.to_string(),
+            ..AuditEntry::new(action, profile, None, result)
+        };
+        let history = SwitchHistory::from_entries(&[
+            entry("2026-01-02T10:00:00+00:00", AuditAction::AutomaticSync, "b", Ok(())),
+            entry("2026-01-01T10:00:00+00:00", AuditAction::SetAsDefault, "a", Ok(())),
+            entry("2026-01-01T11:00:00+00:00", AuditAction::SetAsDefault, "c", Err("failed".to_string())),
+            entry("2026-01-01T12:00:00+00:00", AuditAction::SaveCurrentFile, "d", Ok(())),
+        ]);
+        assert_eq!(history.profile_at(at("2026-01-01T09:00:00+00:00")), None);
+        assert_eq!(history.profile_at(at("2026-01-01T12:30:00+00:00")), Some("a"));
+        assert_eq!(history.profile_at(at("2026-01-02T10:00:00+00:00")), Some("b"));
+    }
+
+    #[test]
+    fn detail_prefixes_failure_message() {
+        let failed = AuditEntry::new(AuditAction::SaveCurrentFile, "a", None, Err("disk full".to_string())).with_detail("autosave");
+        assert_eq!(failed.detail, "autosave: disk full");
+        let saved = AuditEntry::new(AuditAction::SaveCurrentFile, "a", None, Ok(())).with_detail("autosave");
+        assert_eq!(saved.detail, "autosave");
+    }
+
+    #[test]
+    fn csv_escapes_fields() {
+        let mut entry = AuditEntry::new(AuditAction::SaveCurrentFile, "a,b", None, Err("say \"hi\"".to_string()));
+        entry.timestamp = "t".to_string();
+        let csv = to_csv(&[entry]);
+        assert_eq!(csv.lines().nth(1), Some("t,save_current_file,\"a,b\",,failure,\"say \"\"hi\"\"\""));
+    }
+}
@@ -0,0 +1,144 @@
+// 命令面板：Ctrl+K / Ctrl+P 打开，模糊搜索配置和操作后用键盘执行
+
+use claude_code_switcher::t;
+use std::path::{Path, PathBuf};
+
+// 侧边栏按钮和命令面板共用的操作，统一由 ConfigManagerApp::perform_action 执行
+#[derive(Clone, PartialEq)]
+pub enum Action {
+    Apply(PathBuf),
+    Open(PathBuf),
+    Rename(PathBuf),
+    Delete(PathBuf),
+    Format(PathBuf),
+    TestConnection(PathBuf),
+    ToggleTheme,
+    OpenSettings,
+}
+
+impl Action {
+    pub fn label(&self) -> String {
+        let name = |path: &Path| path.file_name().unwrap_or_default().to_string_lossy().to_string();
+        match self {
+            Action::Apply(path) => t!("palette.apply", name = name(path)),
+            Action::Open(path) => t!("palette.open", name = name(path)),
+            Action::Rename(path) => t!("palette.rename", name = name(path)),
+            Action::Delete(path) => t!("palette.delete", name = name(path)),
+            Action::Format(path) => t!("palette.format", name = name(path)),
+            Action::TestConnection(path) => t!("palette.test_connection", name = name(path)),
+            Action::ToggleTheme => t!("palette.toggle_theme").to_string(),
+            Action::OpenSettings => t!("palette.open_settings").to_string(),
+        }
+    }
+
+    // 英文关键字，界面为中文时也可以输入 apply、open 等搜索
+    fn keyword(&self) -> &'static str {
+        match self {
+            Action::Apply(_) => "apply default",
+            Action::Open(_) => "open",
+            Action::Rename(_) => "rename",
+            Action::Delete(_) => "delete",
+            Action::Format(_) => "format",
+            Action::TestConnection(_) => "test connection",
+            Action::ToggleTheme => "toggle theme",
+            Action::OpenSettings => "settings",
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct CommandPalette {
+    pub open: bool,
+    pub query: String,
+    pub selected: usize,
+}
+
+impl CommandPalette {
+    pub fn toggle(&mut self) {
+        self.open = !self.open;
+        self.query.clear();
+        self.selected = 0;
+    }
+
+    pub fn close(&mut self) {
+        self.open = false;
+    }
+
+    // 按匹配程度排序的候选操作；分数相同时保持原有顺序
+    pub fn matches(&self, profiles: &[PathBuf]) -> Vec<Action> {
+        let mut scored: Vec<(i32, Action)> = candidates(profiles)
+            .into_iter()
+            .filter_map(|action| {
+                let text = format!("{} {}", action.keyword(), action.label());
+                fuzzy_score(&self.query, &text).map(|score| (score, action))
+            })
+            .collect();
+        scored.sort_by_key(|(score, _)| std::cmp::Reverse(*score));
+        scored.into_iter().map(|(_, action)| action).collect()
+    }
+}
+
+fn candidates(profiles: &[PathBuf]) -> Vec<Action> {
+    let mut actions = vec![Action::ToggleTheme, Action::OpenSettings];
+    for path in profiles {
+        actions.extend([
+            Action::Apply(path.clone()),
+            Action::Open(path.clone()),
+            Action::Rename(path.clone()),
+            Action::Delete(path.clone()),
+            Action::Format(path.clone()),
+            Action::TestConnection(path.clone()),
+        ]);
+    }
+    actions
+}
+
+// 查询中的字符按顺序出现在文本中即为匹配（忽略大小写和空格），
+// 连续命中和单词开头命中得分更高
+pub fn fuzzy_score(query: &str, text: &str) -> Option<i32> {
+    let text: Vec<char> = text.chars().flat_map(char::to_lowercase).collect();
+    let mut score = 0;
+    let mut position = 0;
+    let mut previous: Option<usize> = None;
+    for q in query.chars().filter(|c| !c.is_whitespace()).flat_map(char::to_lowercase) {
+        let index = position + text[position..].iter().position(|&c| c == q)?;
+        score += 1;
+        if previous.is_some_and(|p| p + 1 == index) {
+            score += 5;
+        }
+        if index == 0 || !text[index - 1].is_alphanumeric() {
+            score += 3;
+        }
+        previous = Some(index);
+        position = index + 1;
+    }
+    Some(score)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn prefix_match_ranks_above_scattered_match() {
+        let prefix = fuzzy_score("set", "Settings").unwrap();
+        let scattered = fuzzy_score("set", "Save current file").unwrap();
+        assert!(prefix > scattered, "{} <= {}", prefix, scattered);
+        // 单词开头命中也优先于单词中间命中
+        assert!(fuzzy_score("fi", "open file").unwrap() > fuzzy_score("fi", "profile").unwrap());
+    }
+
+    #[test]
+    fn matching_ignores_case_and_spaces() {
+        assert_eq!(fuzzy_score("SETTINGS", "settings"), fuzzy_score("settings", "Settings"));
+        assert_eq!(fuzzy_score("open file", "OpenFile"), fuzzy_score("openfile", "OpenFile"));
+        assert_eq!(fuzzy_score("", "anything"), Some(0));
+    }
+
+    #[test]
+    fn missing_or_out_of_order_characters_do_not_match() {
+        assert_eq!(fuzzy_score("xyz", "Settings"), None);
+        assert_eq!(fuzzy_score("tes", "set"), None);
+        assert_eq!(fuzzy_score("settingss", "Settings"), None);
+    }
+}
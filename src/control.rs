@@ -0,0 +1,325 @@
+// 本地控制接口：通过 Unix 域套接字提供按行分隔的 JSON-RPC 2.0 协议
+//
+// 支持的方法：list、current、apply、validate、subscribe。
+// 除 subscribe 和未知方法外，所有请求都转发到主线程，由与界面按钮相同的方法处理。
+// 每个连接的回复和通知由同一个写线程写出。
+
+use crate::t;
+use serde_json::{json, Value};
+use std::sync::mpsc;
+use std::time::Duration;
+
+pub const CONTROL_SOCKET_NAME: &str = "control.sock";
+
+// JSON-RPC 错误码
+pub const INVALID_PARAMS: i64 = -32602;
+pub const METHOD_NOT_FOUND: i64 = -32601;
+pub const APPLICATION_ERROR: i64 = -32000;
+const PARSE_ERROR: i64 = -32700;
+const INVALID_REQUEST: i64 = -32600;
+
+// 转发到主线程处理的方法
+pub const METHODS: [&str; 4] = ["list", "current", "apply", "validate"];
+
+pub type RpcResult = Result<Value, (i64, String)>;
+
+// 等待主线程处理的请求
+pub struct ControlRequest {
+    pub method: String,
+    pub params: Value,
+    reply: mpsc::Sender<RpcResult>,
+}
+
+impl ControlRequest {
+    pub fn respond(self, result: RpcResult) {
+        let _ = self.reply.send(result);
+    }
+
+    // 读取字符串参数，支持 {"name": value} 和按位置传入的 [value] 两种形式
+    pub fn string_param(&self, name: &str) -> Option<String> {
+        match &self.params {
+            Value::Object(map) => map.get(name).and_then(Value::as_str).map(str::to_string),
+            Value::Array(items) => items.first().and_then(Value::as_str).map(str::to_string),
+            _ => None,
+        }
+    }
+}
+
+fn response(id: Value, result: RpcResult) -> Value {
+    match result {
+        Ok(value) => json!({ "jsonrpc": "2.0", "id": id, "result": value }),
+        Err((code, message)) => json!({ "jsonrpc": "2.0", "id": id, "error": { "code": code, "message": message } }),
+    }
+}
+
+#[cfg(unix)]
+pub use unix::ControlServer;
+
+#[cfg(unix)]
+mod unix {
+    use super::*;
+    use std::fs;
+    use std::io::{BufRead, BufReader, Write};
+    use std::os::unix::fs::{DirBuilderExt, PermissionsExt};
+    use std::os::unix::io::AsRawFd;
+    use std::os::unix::net::{UnixListener, UnixStream};
+    use std::path::{Path, PathBuf};
+    use std::sync::{Arc, Mutex};
+
+    // 每个连接待写出的行数上限；订阅者积压到上限时取消订阅，不影响主线程
+    const OUTGOING_CAPACITY: usize = 64;
+
+    type Subscribers = Arc<Mutex<Vec<mpsc::SyncSender<String>>>>;
+
+    // 各连接线程共享的状态
+    #[derive(Clone)]
+    struct Shared {
+        sender: mpsc::Sender<ControlRequest>,
+        subscribers: Subscribers,
+        waker: Arc<dyn Fn() + Send + Sync>,
+    }
+
+    pub struct ControlServer {
+        requests: mpsc::Receiver<ControlRequest>,
+        subscribers: Subscribers,
+        socket_path: PathBuf,
+    }
+
+    impl ControlServer {
+        // 在 run_dir 中创建套接字；run_dir 权限为 0700，套接字为 0600，且只接受同一用户的连接
+        pub fn start(run_dir: &Path, waker: impl Fn() + Send + Sync + 'static) -> std::io::Result<Self> {
+            fs::DirBuilder::new().recursive(true).mode(0o700).create(run_dir)?;
+            fs::set_permissions(run_dir, fs::Permissions::from_mode(0o700))?;
+            let socket_path = run_dir.join(CONTROL_SOCKET_NAME);
+
+            if socket_path.exists() {
+                if UnixStream::connect(&socket_path).is_ok() {
+                    return Err(std::io::Error::new(std::io::ErrorKind::AddrInUse, t!("control.in_use")));
+                }
+                // 上次异常退出留下的套接字文件
+                fs::remove_file(&socket_path)?;
+            }
+
+            let listener = UnixListener::bind(&socket_path)?;
+            fs::set_permissions(&socket_path, fs::Permissions::from_mode(0o600))?;
+
+            let (sender, requests) = mpsc::channel();
+            let subscribers = Subscribers::default();
+            let shared = Shared { sender, subscribers: subscribers.clone(), waker: Arc::new(waker) };
+            std::thread::spawn(move || {
+                for stream in listener.incoming().filter_map(Result::ok) {
+                    if peer_uid(&stream) != Some(current_uid()) {
+                        continue;
+                    }
+                    let shared = shared.clone();
+                    std::thread::spawn(move || handle_connection(stream, shared));
+                }
+            });
+
+            Ok(Self { requests, subscribers, socket_path })
+        }
+
+        pub fn socket_path(&self) -> &Path {
+            &self.socket_path
+        }
+
+        pub fn try_recv(&self) -> Option<ControlRequest> {
+            self.requests.try_recv().ok()
+        }
+
+        pub fn recv_timeout(&self, timeout: Duration) -> Option<ControlRequest> {
+            self.requests.recv_timeout(timeout).ok()
+        }
+
+        // 把通知交给各订阅连接的写线程，不在调用线程上写套接字；连接已关闭或积压过多的订阅者被移除
+        pub fn notify(&self, method: &str, params: Value) {
+            let line = json!({ "jsonrpc": "2.0", "method": method, "params": params }).to_string();
+            if let Ok(mut subscribers) = self.subscribers.lock() {
+                subscribers.retain(|subscriber| subscriber.try_send(line.clone()).is_ok());
+            }
+        }
+    }
+
+    impl Drop for ControlServer {
+        fn drop(&mut self) {
+            let _ = fs::remove_file(&self.socket_path);
+        }
+    }
+
+    // 回复和通知都经由同一个写线程按顺序写出，不会交错
+    fn handle_connection(stream: UnixStream, shared: Shared) {
+        let Ok(writer) = stream.try_clone() else { return };
+        let (outgoing, lines) = mpsc::sync_channel(OUTGOING_CAPACITY);
+        std::thread::spawn(move || write_lines(writer, lines));
+        for line in BufReader::new(stream).lines() {
+            let Ok(line) = line else { break };
+            if let Some(reply) = handle_line(&line, &outgoing, &shared) {
+                if outgoing.send(reply.to_string()).is_err() {
+                    break;
+                }
+            }
+        }
+    }
+
+    fn write_lines(mut writer: UnixStream, lines: mpsc::Receiver<String>) {
+        for line in lines {
+            if writer.write_all((line + "\n").as_bytes()).is_err() {
+                break;
+            }
+        }
+    }
+
+    // 返回 None 表示不需要回复：空行，或者没有 id 的通知
+    fn handle_line(line: &str, outgoing: &mpsc::SyncSender<String>, shared: &Shared) -> Option<Value> {
+        if line.trim().is_empty() {
+            return None;
+        }
+        match serde_json::from_str::<Value>(line) {
+            Ok(request) => handle_request(request, outgoing, shared),
+            Err(e) => Some(response(Value::Null, Err((PARSE_ERROR, e.to_string())))),
+        }
+    }
+
+    fn handle_request(request: Value, outgoing: &mpsc::SyncSender<String>, shared: &Shared) -> Option<Value> {
+        let id = request.get("id").cloned();
+        let Some(method) = request.get("method").and_then(Value::as_str) else {
+            return Some(response(id.unwrap_or(Value::Null), Err((INVALID_REQUEST, t!("control.missing_method").to_string()))));
+        };
+
+        let result = if method == "subscribe" {
+            if let Ok(mut subscribers) = shared.subscribers.lock() {
+                subscribers.push(outgoing.clone());
+            }
+            Ok(json!({ "subscribed": true }))
+        } else if !METHODS.contains(&method) {
+            Err((METHOD_NOT_FOUND, t!("control.unknown_method", method = method)))
+        } else {
+            let (reply, receiver) = mpsc::channel();
+            let request = ControlRequest {
+                method: method.to_string(),
+                params: request.get("params").cloned().unwrap_or(Value::Null),
+                reply,
+            };
+            if shared.sender.send(request).is_err() {
+                return None;
+            }
+            (shared.waker)();
+            receiver.recv().unwrap_or_else(|_| Err((APPLICATION_ERROR, t!("control.shutting_down").to_string())))
+        };
+        id.map(|id| response(id, result))
+    }
+
+    fn current_uid() -> u32 {
+        unsafe { libc::geteuid() }
+    }
+
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    fn peer_uid(stream: &UnixStream) -> Option<u32> {
+        let mut cred = libc::ucred { pid: 0, uid: 0, gid: 0 };
+        let mut len = std::mem::size_of::<libc::ucred>() as libc::socklen_t;
+        let result = unsafe {
+            libc::getsockopt(
+                stream.as_raw_fd(),
+                libc::SOL_SOCKET,
+                libc::SO_PEERCRED,
+                &mut cred as *mut libc::ucred as *mut libc::c_void,
+                &mut len,
+            )
+        };
+        (result == 0).then_some(cred.uid)
+    }
+
+    #[cfg(not(any(target_os = "linux", target_os = "android")))]
+    fn peer_uid(stream: &UnixStream) -> Option<u32> {
+        let mut uid = 0;
+        let mut gid = 0;
+        let result = unsafe { libc::getpeereid(stream.as_raw_fd(), &mut uid, &mut gid) };
+        (result == 0).then_some(uid)
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        fn shared() -> (Shared, mpsc::Receiver<ControlRequest>) {
+            let (sender, requests) = mpsc::channel();
+            (Shared { sender, subscribers: Subscribers::default(), waker: Arc::new(|| {}) }, requests)
+        }
+
+        fn error_code(reply: &Value) -> Option<i64> {
+            reply.pointer("/error/code").and_then(Value::as_i64)
+        }
+
+        #[test]
+        fn reports_parse_errors_and_unknown_methods() {
+            let (shared, requests) = shared();
+            let (outgoing, _lines) = mpsc::sync_channel(1);
+            let reply = handle_line("{\"method\": ", &outgoing, &shared).unwrap();
+            assert_eq!((error_code(&reply), &reply["id"]), (Some(PARSE_ERROR), &Value::Null));
+            let reply = handle_line(r#"{"jsonrpc": "2.0", "id": 7, "method": "nope"}"#, &outgoing, &shared).unwrap();
+            assert_eq!((error_code(&reply), &reply["id"]), (Some(METHOD_NOT_FOUND), &json!(7)));
+            let reply = handle_line(r#"{"id": 8}"#, &outgoing, &shared).unwrap();
+            assert_eq!(error_code(&reply), Some(INVALID_REQUEST));
+            assert!(handle_line("  ", &outgoing, &shared).is_none());
+            assert!(requests.try_recv().is_err());
+        }
+
+        #[test]
+        fn notifications_are_forwarded_without_reply() {
+            let (shared, requests) = shared();
+            let (outgoing, _lines) = mpsc::sync_channel(1);
+            let main = std::thread::spawn(move || {
+                let request = requests.recv().unwrap();
+                let method = request.method.clone();
+                let name = request.string_param("name");
+                request.respond(Ok(Value::Null));
+                (method, name)
+            });
+            assert!(handle_line(r#"{"jsonrpc": "2.0", "method": "apply", "params": ["relay"]}"#, &outgoing, &shared).is_none());
+            assert_eq!(main.join().unwrap(), ("apply".to_string(), Some("relay".to_string())));
+        }
+
+        #[test]
+        fn subscribers_receive_notifications_through_their_connection() {
+            let (shared, _requests) = shared();
+            let (outgoing, lines) = mpsc::sync_channel(OUTGOING_CAPACITY);
+            let reply = handle_line(r#"{"jsonrpc": "2.0", "id": 1, "method": "subscribe"}"#, &outgoing, &shared).unwrap();
+            assert_eq!(reply["result"], json!({ "subscribed": true }));
+            let server = ControlServer { requests: mpsc::channel().1, subscribers: shared.subscribers.clone(), socket_path: PathBuf::new() };
+            server.notify("switched", json!({ "profile": "a.json" }));
+            let line: Value = serde_json::from_str(&lines.try_recv().unwrap()).unwrap();
+            assert_eq!(line["params"]["profile"], "a.json");
+            // 连接关闭后取消订阅
+            drop(lines);
+            server.notify("switched", json!({}));
+            assert!(shared.subscribers.lock().unwrap().is_empty());
+        }
+    }
+}
+
+// 非 Unix 平台暂不提供控制接口
+#[cfg(not(unix))]
+pub struct ControlServer {
+    requests: mpsc::Receiver<ControlRequest>,
+}
+
+#[cfg(not(unix))]
+impl ControlServer {
+    pub fn start(_run_dir: &std::path::Path, _waker: impl Fn() + Send + Sync + 'static) -> std::io::Result<Self> {
+        Err(std::io::Error::new(std::io::ErrorKind::Unsupported, t!("control.unsupported")))
+    }
+
+    pub fn socket_path(&self) -> &std::path::Path {
+        std::path::Path::new("")
+    }
+
+    pub fn try_recv(&self) -> Option<ControlRequest> {
+        self.requests.try_recv().ok()
+    }
+
+    pub fn recv_timeout(&self, timeout: Duration) -> Option<ControlRequest> {
+        self.requests.recv_timeout(timeout).ok()
+    }
+
+    pub fn notify(&self, _method: &str, _params: Value) {}
+}
@@ -0,0 +1,9 @@
+// CSV 导出：含逗号、引号或换行的字段加上引号，字段中的引号写成两个
+
+pub(crate) fn field(text: &str) -> String {
+    if text.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", text.replace('"', "\"\""))
+    } else {
+        text.to_string()
+    }
+}
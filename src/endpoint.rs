@@ -0,0 +1,125 @@
+// 配置中的 Claude API 端点：从 env 解析地址和凭据，用于连通性测试和获取模型列表
+
+use crate::error::{Error, Result};
+use crate::jsonc;
+use crate::models::{with_context_windows, ModelInfo};
+use serde_json::Value;
+use std::time::{Duration, Instant};
+
+pub const DEFAULT_BASE_URL: &str = "https://api.anthropic.com";
+const API_VERSION: &str = "2023-06-01";
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Clone, PartialEq, Eq, Hash)]
+pub enum Credential {
+    // ANTHROPIC_API_KEY，以 x-api-key 发送
+    ApiKey(String),
+    // ANTHROPIC_AUTH_TOKEN，以 Bearer 令牌发送
+    AuthToken(String),
+}
+
+#[derive(Clone, PartialEq, Eq, Hash)]
+pub struct Endpoint {
+    pub base_url: String,
+    pub credential: Option<Credential>,
+}
+
+pub struct ConnectionReport {
+    pub url: String,
+    pub status: u16,
+    pub latency: Duration,
+}
+
+impl ConnectionReport {
+    pub fn is_success(&self) -> bool {
+        (200..300).contains(&self.status)
+    }
+
+    // 401/403 说明端点可达但凭据被拒绝
+    pub fn is_unauthorized(&self) -> bool {
+        matches!(self.status, 401 | 403)
+    }
+}
+
+impl Endpoint {
+    // 未设置 ANTHROPIC_BASE_URL 时使用官方地址
+    pub fn from_settings(settings: &Value) -> Self {
+        let env = |name: &str| {
+            settings
+                .get("env")
+                .and_then(|env| env.get(name))
+                .and_then(Value::as_str)
+                .map(str::trim)
+                .filter(|value| !value.is_empty())
+                .map(str::to_string)
+        };
+        let credential = env("ANTHROPIC_AUTH_TOKEN")
+            .map(Credential::AuthToken)
+            .or_else(|| env("ANTHROPIC_API_KEY").map(Credential::ApiKey));
+        Self {
+            base_url: env("ANTHROPIC_BASE_URL").unwrap_or_else(|| DEFAULT_BASE_URL.to_string()),
+            credential,
+        }
+    }
+
+    pub fn from_content(content: &str) -> Result<Self> {
+        let settings: Value = jsonc::from_str(content).map_err(Error::InvalidJson)?;
+        Ok(Self::from_settings(&settings))
+    }
+
+    pub fn url(&self, path: &str) -> String {
+        format!("{}{}", self.base_url.trim_end_matches('/'), path)
+    }
+
+    // 对端点发起 GET 请求，HTTP 错误状态也作为响应返回
+    fn get(&self, path: &str) -> Result<(ureq::Response, Duration)> {
+        let url = self.url(path);
+        let agent = ureq::AgentBuilder::new().timeout(TIMEOUT).build();
+        let mut request = agent.get(&url).set("anthropic-version", API_VERSION);
+        match &self.credential {
+            Some(Credential::ApiKey(key)) => request = request.set("x-api-key", key),
+            Some(Credential::AuthToken(token)) => request = request.set("Authorization", &format!("Bearer {}", token)),
+            None => {}
+        }
+        let started = Instant::now();
+        match request.call() {
+            Ok(response) | Err(ureq::Error::Status(_, response)) => Ok((response, started.elapsed())),
+            Err(ureq::Error::Transport(e)) => Err(Error::Request(e.to_string())),
+        }
+    }
+
+    // 分页读取 /v1/models；非 2xx 响应作为错误返回
+    pub fn list_models(&self) -> Result<Vec<ModelInfo>> {
+        let mut models = Vec::new();
+        let mut after: Option<String> = None;
+        // 最多读取 10 页，避免端点一直返回 has_more
+        for _ in 0..10 {
+            let path = match &after {
+                Some(id) => format!("/v1/models?limit=1000&after_id={}", id),
+                None => "/v1/models?limit=1000".to_string(),
+            };
+            let (response, _) = self.get(&path)?;
+            let status = response.status();
+            if !(200..300).contains(&status) {
+                return Err(Error::Request(format!("HTTP {} {}", status, response.status_text())));
+            }
+            let page: Value = response.into_json().map_err(|e| Error::Request(e.to_string()))?;
+            for item in page.get("data").and_then(Value::as_array).into_iter().flatten() {
+                let Some(id) = item.get("id").and_then(Value::as_str) else { continue };
+                let name = item.get("display_name").and_then(Value::as_str).unwrap_or(id);
+                models.push(ModelInfo::new(id, name, None));
+            }
+            after = page.get("last_id").and_then(Value::as_str).map(str::to_string);
+            if !page.get("has_more").and_then(Value::as_bool).unwrap_or(false) || after.is_none() {
+                break;
+            }
+        }
+        Ok(with_context_windows(models))
+    }
+
+    // 请求模型列表接口，据此判断地址是否可达、凭据是否有效
+    pub fn test_connection(&self) -> Result<ConnectionReport> {
+        let (response, latency) = self.get("/v1/models")?;
+        Ok(ConnectionReport { url: response.get_url().to_string(), status: response.status(), latency })
+    }
+}
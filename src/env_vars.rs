@@ -0,0 +1,221 @@
+// Claude Code 认识的环境变量目录：类型、取值范围、默认值和说明，
+// 用于在配置的 env 中以复选框、数值和下拉框编辑，并找出未知或已弃用的变量
+
+use crate::t;
+use serde_json::{Map, Value};
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum Category {
+    Privacy,
+    Limits,
+    Network,
+    Provider,
+    Behavior,
+}
+
+impl Category {
+    pub const ALL: [Category; 5] = [Category::Privacy, Category::Limits, Category::Network, Category::Provider, Category::Behavior];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Category::Privacy => t!("env_vars.category.privacy"),
+            Category::Limits => t!("env_vars.category.limits"),
+            Category::Network => t!("env_vars.category.network"),
+            Category::Provider => t!("env_vars.category.provider"),
+            Category::Behavior => t!("env_vars.category.behavior"),
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Kind {
+    // 设为 1 表示开启，未设置表示关闭
+    Flag,
+    Integer { min: i64, max: i64 },
+    Choice(&'static [&'static str]),
+    Text,
+}
+
+pub struct EnvVar {
+    pub name: &'static str,
+    pub category: Category,
+    pub kind: Kind,
+    pub default: Option<&'static str>,
+    // 说明的语言包键
+    description: &'static str,
+    // 已弃用时的替代变量
+    pub replaced_by: Option<&'static str>,
+}
+
+impl EnvVar {
+    const fn new(name: &'static str, category: Category, kind: Kind, default: Option<&'static str>, description: &'static str) -> Self {
+        Self { name, category, kind, default, description, replaced_by: None }
+    }
+
+    const fn deprecated(mut self, replacement: &'static str) -> Self {
+        self.replaced_by = Some(replacement);
+        self
+    }
+
+    pub fn description(&self) -> &'static str {
+        t!(self.description)
+    }
+
+    // 检查取值是否符合类型和范围，返回问题说明
+    pub fn check(&self, value: &str) -> Option<String> {
+        let value = value.trim();
+        match self.kind {
+            Kind::Flag if !value.is_empty() && !is_truthy(value) && !matches!(value, "0" | "false") => {
+                Some(t!("env_vars.invalid_flag", name = self.name, value = value))
+            }
+            Kind::Integer { min, max } => match value.parse::<i64>() {
+                Ok(number) if (min..=max).contains(&number) => None,
+                _ => Some(t!("env_vars.out_of_range", name = self.name, value = value, min = min, max = max)),
+            },
+            Kind::Choice(options) if !options.contains(&value) => Some(t!("env_vars.invalid_choice", name = self.name, value = value, options = options.join(", "))),
+            _ => None,
+        }
+    }
+}
+
+const MS_MAX: i64 = 24 * 60 * 60 * 1000;
+
+pub const CATALOG: &[EnvVar] = &[
+    // 隐私与遥测
+    EnvVar::new("DISABLE_TELEMETRY", Category::Privacy, Kind::Flag, None, "env_vars.desc.disable_telemetry"),
+    EnvVar::new("DISABLE_ERROR_REPORTING", Category::Privacy, Kind::Flag, None, "env_vars.desc.disable_error_reporting"),
+    EnvVar::new("DISABLE_BUG_COMMAND", Category::Privacy, Kind::Flag, None, "env_vars.desc.disable_bug_command"),
+    EnvVar::new("DISABLE_AUTOUPDATER", Category::Privacy, Kind::Flag, None, "env_vars.desc.disable_autoupdater"),
+    EnvVar::new("DISABLE_COST_WARNINGS", Category::Privacy, Kind::Flag, None, "env_vars.desc.disable_cost_warnings"),
+    EnvVar::new("DISABLE_NON_ESSENTIAL_MODEL_CALLS", Category::Privacy, Kind::Flag, None, "env_vars.desc.disable_non_essential_model_calls"),
+    EnvVar::new("CLAUDE_CODE_DISABLE_NONESSENTIAL_TRAFFIC", Category::Privacy, Kind::Flag, None, "env_vars.desc.disable_nonessential_traffic"),
+    EnvVar::new("CLAUDE_CODE_ENABLE_TELEMETRY", Category::Privacy, Kind::Flag, None, "env_vars.desc.enable_telemetry"),
+    // 限制与超时
+    EnvVar::new("CLAUDE_CODE_MAX_OUTPUT_TOKENS", Category::Limits, Kind::Integer { min: 1, max: 128_000 }, Some("32000"), "env_vars.desc.max_output_tokens"),
+    EnvVar::new("MAX_THINKING_TOKENS", Category::Limits, Kind::Integer { min: 0, max: 128_000 }, None, "env_vars.desc.max_thinking_tokens"),
+    EnvVar::new("BASH_DEFAULT_TIMEOUT_MS", Category::Limits, Kind::Integer { min: 1000, max: MS_MAX }, Some("120000"), "env_vars.desc.bash_default_timeout"),
+    EnvVar::new("BASH_MAX_TIMEOUT_MS", Category::Limits, Kind::Integer { min: 1000, max: MS_MAX }, Some("600000"), "env_vars.desc.bash_max_timeout"),
+    EnvVar::new("BASH_MAX_OUTPUT_LENGTH", Category::Limits, Kind::Integer { min: 1, max: 10_000_000 }, Some("30000"), "env_vars.desc.bash_max_output_length"),
+    EnvVar::new("MCP_TIMEOUT", Category::Limits, Kind::Integer { min: 1000, max: MS_MAX }, None, "env_vars.desc.mcp_timeout"),
+    EnvVar::new("MCP_TOOL_TIMEOUT", Category::Limits, Kind::Integer { min: 1000, max: MS_MAX }, None, "env_vars.desc.mcp_tool_timeout"),
+    EnvVar::new("MAX_MCP_OUTPUT_TOKENS", Category::Limits, Kind::Integer { min: 1, max: 1_000_000 }, Some("25000"), "env_vars.desc.max_mcp_output_tokens"),
+    EnvVar::new("CLAUDE_CODE_API_KEY_HELPER_TTL_MS", Category::Limits, Kind::Integer { min: 0, max: MS_MAX }, None, "env_vars.desc.api_key_helper_ttl"),
+    // 网络
+    EnvVar::new("ANTHROPIC_BASE_URL", Category::Network, Kind::Text, None, "env_vars.desc.base_url"),
+    EnvVar::new("ANTHROPIC_API_KEY", Category::Network, Kind::Text, None, "env_vars.desc.api_key"),
+    EnvVar::new("ANTHROPIC_AUTH_TOKEN", Category::Network, Kind::Text, None, "env_vars.desc.auth_token"),
+    EnvVar::new("ANTHROPIC_CUSTOM_HEADERS", Category::Network, Kind::Text, None, "env_vars.desc.custom_headers"),
+    EnvVar::new("HTTP_PROXY", Category::Network, Kind::Text, None, "env_vars.desc.http_proxy"),
+    EnvVar::new("HTTPS_PROXY", Category::Network, Kind::Text, None, "env_vars.desc.https_proxy"),
+    EnvVar::new("NO_PROXY", Category::Network, Kind::Text, None, "env_vars.desc.no_proxy"),
+    EnvVar::new("DISABLE_PROMPT_CACHING", Category::Network, Kind::Flag, None, "env_vars.desc.disable_prompt_caching"),
+    // 模型提供方
+    EnvVar::new("ANTHROPIC_MODEL", Category::Provider, Kind::Text, None, "env_vars.desc.model"),
+    EnvVar::new("ANTHROPIC_DEFAULT_OPUS_MODEL", Category::Provider, Kind::Text, None, "env_vars.desc.default_opus_model"),
+    EnvVar::new("ANTHROPIC_DEFAULT_SONNET_MODEL", Category::Provider, Kind::Text, None, "env_vars.desc.default_sonnet_model"),
+    EnvVar::new("ANTHROPIC_DEFAULT_HAIKU_MODEL", Category::Provider, Kind::Text, None, "env_vars.desc.default_haiku_model"),
+    EnvVar::new("ANTHROPIC_SMALL_FAST_MODEL", Category::Provider, Kind::Text, None, "env_vars.desc.small_fast_model").deprecated("ANTHROPIC_DEFAULT_HAIKU_MODEL"),
+    EnvVar::new("CLAUDE_CODE_SUBAGENT_MODEL", Category::Provider, Kind::Text, None, "env_vars.desc.subagent_model"),
+    EnvVar::new("CLAUDE_CODE_USE_BEDROCK", Category::Provider, Kind::Flag, None, "env_vars.desc.use_bedrock"),
+    EnvVar::new("CLAUDE_CODE_USE_VERTEX", Category::Provider, Kind::Flag, None, "env_vars.desc.use_vertex"),
+    EnvVar::new("CLAUDE_CODE_SKIP_BEDROCK_AUTH", Category::Provider, Kind::Flag, None, "env_vars.desc.skip_bedrock_auth"),
+    EnvVar::new("CLAUDE_CODE_SKIP_VERTEX_AUTH", Category::Provider, Kind::Flag, None, "env_vars.desc.skip_vertex_auth"),
+    EnvVar::new("AWS_REGION", Category::Provider, Kind::Text, None, "env_vars.desc.aws_region"),
+    EnvVar::new("AWS_PROFILE", Category::Provider, Kind::Text, None, "env_vars.desc.aws_profile"),
+    EnvVar::new("AWS_BEARER_TOKEN_BEDROCK", Category::Provider, Kind::Text, None, "env_vars.desc.aws_bearer_token"),
+    EnvVar::new("CLOUD_ML_REGION", Category::Provider, Kind::Text, None, "env_vars.desc.cloud_ml_region"),
+    EnvVar::new("ANTHROPIC_VERTEX_PROJECT_ID", Category::Provider, Kind::Text, None, "env_vars.desc.vertex_project_id"),
+    // 行为
+    EnvVar::new("CLAUDE_BASH_MAINTAIN_PROJECT_WORKING_DIR", Category::Behavior, Kind::Flag, None, "env_vars.desc.maintain_working_dir"),
+    EnvVar::new("CLAUDE_CODE_DISABLE_TERMINAL_TITLE", Category::Behavior, Kind::Flag, None, "env_vars.desc.disable_terminal_title"),
+    EnvVar::new("CLAUDE_CODE_IDE_SKIP_AUTO_INSTALL", Category::Behavior, Kind::Flag, None, "env_vars.desc.ide_skip_auto_install"),
+    EnvVar::new("USE_BUILTIN_RIPGREP", Category::Behavior, Kind::Choice(&["0", "1"]), Some("1"), "env_vars.desc.use_builtin_ripgrep"),
+    EnvVar::new("CLAUDE_CODE_SHELL_PREFIX", Category::Behavior, Kind::Text, None, "env_vars.desc.shell_prefix"),
+];
+
+pub fn find(name: &str) -> Option<&'static EnvVar> {
+    CATALOG.iter().find(|var| var.name == name)
+}
+
+pub fn is_truthy(value: &str) -> bool {
+    matches!(value.trim().to_ascii_lowercase().as_str(), "1" | "true" | "yes" | "on")
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub enum Issue {
+    Unknown(String),
+    // 变量名及其替代变量
+    Deprecated(String, &'static str),
+    Invalid(String, String),
+}
+
+impl Issue {
+    pub fn name(&self) -> &str {
+        match self {
+            Issue::Unknown(name) | Issue::Deprecated(name, _) | Issue::Invalid(name, _) => name,
+        }
+    }
+
+    pub fn message(&self) -> String {
+        match self {
+            Issue::Unknown(name) => t!("env_vars.unknown", name = name),
+            Issue::Deprecated(name, replacement) => t!("env_vars.deprecated", name = name, replacement = replacement),
+            Issue::Invalid(_, message) => message.clone(),
+        }
+    }
+}
+
+// 按 env 中的顺序检查每个变量；非字符串的值由配置检查处理
+pub fn check(env: &Map<String, Value>) -> Vec<Issue> {
+    env.iter()
+        .filter_map(|(name, value)| {
+            let Some(var) = find(name) else { return Some(Issue::Unknown(name.clone())) };
+            if let Some(replacement) = var.replaced_by {
+                return Some(Issue::Deprecated(name.clone(), replacement));
+            }
+            value.as_str().and_then(|value| var.check(value)).map(|message| Issue::Invalid(name.clone(), message))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn issues(env: Value) -> Vec<Issue> {
+        check(env.as_object().unwrap())
+    }
+
+    #[test]
+    fn unknown_and_deprecated_variables_are_reported_by_name() {
+        let found = issues(json!({"MY_TOOL_TOKEN": "x", "ANTHROPIC_SMALL_FAST_MODEL": "haiku", "DISABLE_TELEMETRY": "1"}));
+        assert_eq!(found, [
+            Issue::Unknown("MY_TOOL_TOKEN".to_string()),
+            Issue::Deprecated("ANTHROPIC_SMALL_FAST_MODEL".to_string(), "ANTHROPIC_DEFAULT_HAIKU_MODEL"),
+        ]);
+        assert_eq!(found[1].message(), t!("env_vars.deprecated", name = "ANTHROPIC_SMALL_FAST_MODEL", replacement = "ANTHROPIC_DEFAULT_HAIKU_MODEL"));
+    }
+
+    #[test]
+    fn values_are_checked_against_type_range_and_choices() {
+        let found = issues(json!({
+            "DISABLE_TELEMETRY": "maybe",
+            "BASH_DEFAULT_TIMEOUT_MS": "500",
+            "MAX_THINKING_TOKENS": "many",
+            "USE_BUILTIN_RIPGREP": "2",
+            "BASH_MAX_TIMEOUT_MS": " 600000 ",
+            "CLAUDE_CODE_USE_BEDROCK": "true",
+            // 非字符串的值由配置检查处理
+            "CLAUDE_CODE_MAX_OUTPUT_TOKENS": 0
+        }));
+        let names: Vec<&str> = found.iter().map(Issue::name).collect();
+        assert_eq!(names, ["DISABLE_TELEMETRY", "BASH_DEFAULT_TIMEOUT_MS", "MAX_THINKING_TOKENS", "USE_BUILTIN_RIPGREP"]);
+        assert!(found.iter().all(|issue| matches!(issue, Issue::Invalid(..))));
+        assert_eq!(
+            found[1].message(),
+            t!("env_vars.out_of_range", name = "BASH_DEFAULT_TIMEOUT_MS", value = "500", min = 1000, max = MS_MAX)
+        );
+        assert_eq!(found[3].message(), t!("env_vars.invalid_choice", name = "USE_BUILTIN_RIPGREP", value = "2", options = "0, 1"));
+    }
+}
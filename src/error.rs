@@ -0,0 +1,73 @@
+// 核心库的错误类型
+
+use crate::t;
+use std::fmt;
+use std::io;
+use std::path::PathBuf;
+
+#[derive(Debug)]
+pub enum Error {
+    CreateDir { path: PathBuf, source: io::Error },
+    Read { path: PathBuf, source: io::Error },
+    Write { path: PathBuf, source: io::Error },
+    Rename { path: PathBuf, source: io::Error },
+    Remove { path: PathBuf, source: io::Error },
+    ReadDir { path: PathBuf, source: io::Error },
+    InvalidJson(serde_json::Error),
+    InvalidSettings(serde_json::Error),
+    // 重新排版会丢失注释
+    HasComments,
+    // 需要 JSON 对象的地方（配置根节点、mcpServers）不是对象
+    NotAnObject,
+    EmptyProfileName,
+    ProfileNotFound(String),
+    ProfileExists(String),
+    Request(String),
+    // 其他工具的配置文件无法解析或修改
+    InvalidTarget { path: PathBuf, message: String },
+    Spawn { command: String, source: io::Error },
+    // 配置有错误级检查结果，未确认忽略时阻止应用
+    LintFailed { name: String, count: usize },
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::CreateDir { path, source } => f.write_str(&t!("error.create_dir", path = path.display(), error = source)),
+            Error::Read { path, source } => f.write_str(&t!("error.read", path = path.display(), error = source)),
+            Error::Write { path, source } => f.write_str(&t!("error.write", path = path.display(), error = source)),
+            Error::Rename { path, source } => f.write_str(&t!("error.rename", path = path.display(), error = source)),
+            Error::Remove { path, source } => f.write_str(&t!("error.remove", path = path.display(), error = source)),
+            Error::ReadDir { path, source } => f.write_str(&t!("error.read_dir", path = path.display(), error = source)),
+            Error::InvalidJson(e) => f.write_str(&t!("error.invalid_json", error = e)),
+            Error::InvalidSettings(e) => f.write_str(&t!("error.invalid_settings", error = e)),
+            Error::HasComments => f.write_str(t!("error.has_comments")),
+            Error::NotAnObject => f.write_str(t!("error.not_an_object")),
+            Error::EmptyProfileName => f.write_str(t!("error.empty_profile_name")),
+            Error::ProfileNotFound(name) => f.write_str(&t!("error.profile_not_found", name = name)),
+            Error::ProfileExists(name) => f.write_str(&t!("error.profile_exists", name = name)),
+            Error::Request(error) => f.write_str(&t!("error.request", error = error)),
+            Error::InvalidTarget { path, message } => f.write_str(&t!("error.invalid_target", path = path.display(), error = message)),
+            Error::Spawn { command, source } => f.write_str(&t!("error.spawn", command = command, error = source)),
+            Error::LintFailed { name, count } => f.write_str(&t!("error.lint_failed", name = name, count = count)),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::CreateDir { source, .. }
+            | Error::Read { source, .. }
+            | Error::Write { source, .. }
+            | Error::Rename { source, .. }
+            | Error::Remove { source, .. }
+            | Error::ReadDir { source, .. }
+            | Error::Spawn { source, .. } => Some(source),
+            Error::InvalidJson(e) | Error::InvalidSettings(e) => Some(e),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,23 @@
+// 文件写入：先写同目录下的临时文件再替换，读取方不会看到写了一半的文件，
+// 写入中途失败时原文件保持不变
+
+use crate::error::{Error, Result};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+pub(crate) fn write_atomically(path: &Path, content: &str) -> Result<()> {
+    if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        fs::create_dir_all(parent).map_err(|source| Error::CreateDir { path: parent.to_path_buf(), source })?;
+    }
+    let mut temp = path.as_os_str().to_owned();
+    temp.push(".tmp");
+    let temp = PathBuf::from(temp);
+    if let Err(source) = fs::write(&temp, content) {
+        let _ = fs::remove_file(&temp);
+        return Err(Error::Write { path: temp, source });
+    }
+    fs::rename(&temp, path).map_err(|source| {
+        let _ = fs::remove_file(&temp);
+        Error::Rename { path: path.to_path_buf(), source }
+    })
+}
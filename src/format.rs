@@ -0,0 +1,139 @@
+// JSON 格式化设置：缩进、键顺序和末尾换行，格式化按钮和自动修复都按它输出
+
+use crate::error::{Error, Result};
+use crate::jsonc;
+use crate::t;
+use serde::{Deserialize, Serialize};
+use serde_json::ser::{PrettyFormatter, Serializer};
+use serde_json::Value;
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum IndentStyle {
+    Spaces,
+    Tabs,
+}
+
+impl IndentStyle {
+    pub const ALL: [IndentStyle; 2] = [IndentStyle::Spaces, IndentStyle::Tabs];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            IndentStyle::Spaces => t!("format.indent.spaces"),
+            IndentStyle::Tabs => t!("format.indent.tabs"),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum KeyOrder {
+    // 保持文件中的键顺序
+    Original,
+    Sorted,
+}
+
+impl KeyOrder {
+    pub const ALL: [KeyOrder; 2] = [KeyOrder::Original, KeyOrder::Sorted];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            KeyOrder::Original => t!("format.order.original"),
+            KeyOrder::Sorted => t!("format.order.sorted"),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+#[serde(default)]
+pub struct FormatSettings {
+    pub indent_style: IndentStyle,
+    // 使用空格缩进时每级的空格数
+    pub indent_width: usize,
+    pub key_order: KeyOrder,
+    pub final_newline: bool,
+}
+
+impl Default for FormatSettings {
+    fn default() -> Self {
+        Self { indent_style: IndentStyle::Spaces, indent_width: 2, key_order: KeyOrder::Original, final_newline: false }
+    }
+}
+
+impl FormatSettings {
+    fn indent(&self) -> Vec<u8> {
+        match self.indent_style {
+            IndentStyle::Spaces => vec![b' '; self.indent_width.clamp(1, 8)],
+            IndentStyle::Tabs => vec![b'\t'],
+        }
+    }
+
+    // 一级缩进
+    pub fn indent_unit(&self) -> String {
+        String::from_utf8(self.indent()).expect("缩进只包含空格或制表符")
+    }
+
+    pub fn format_value(&self, value: &Value) -> Result<String> {
+        let mut value = value.clone();
+        if self.key_order == KeyOrder::Sorted {
+            sort_keys(&mut value);
+        }
+        let indent = self.indent();
+        let mut output = Vec::new();
+        let mut serializer = Serializer::with_formatter(&mut output, PrettyFormatter::with_indent(&indent));
+        value.serialize(&mut serializer).map_err(Error::InvalidJson)?;
+        let mut text = String::from_utf8(output).expect("serde_json 只输出 UTF-8");
+        if self.final_newline {
+            text.push('\n');
+        }
+        Ok(text)
+    }
+
+    // 重新排版会丢失注释，因此带注释的配置不做格式化
+    pub fn format(&self, content: &str) -> Result<String> {
+        if jsonc::has_comments(content) {
+            return Err(Error::HasComments);
+        }
+        let value: Value = jsonc::from_str(content).map_err(Error::InvalidJson)?;
+        self.format_value(&value)
+    }
+}
+
+fn sort_keys(value: &mut Value) {
+    match value {
+        Value::Object(map) => {
+            map.sort_keys();
+            map.values_mut().for_each(sort_keys);
+        }
+        Value::Array(items) => items.iter_mut().for_each(sort_keys),
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CONTENT: &str = r#"{"b": {"y": 1, "x": [{"d": 1, "c": 2}]}, "a": true}"#;
+
+    #[test]
+    fn format_keeps_original_order_by_default() {
+        let format = FormatSettings::default();
+        assert_eq!(format.format(CONTENT).unwrap(), "{\n  \"b\": {\n    \"y\": 1,\n    \"x\": [\n      {\n        \"d\": 1,\n        \"c\": 2\n      }\n    ]\n  },\n  \"a\": true\n}");
+    }
+
+    #[test]
+    fn format_sorts_nested_keys_with_tabs_and_final_newline() {
+        let format = FormatSettings { indent_style: IndentStyle::Tabs, indent_width: 2, key_order: KeyOrder::Sorted, final_newline: true };
+        assert_eq!(format.format(CONTENT).unwrap(), "{\n\t\"a\": true,\n\t\"b\": {\n\t\t\"x\": [\n\t\t\t{\n\t\t\t\t\"c\": 2,\n\t\t\t\t\"d\": 1\n\t\t\t}\n\t\t],\n\t\t\"y\": 1\n\t}\n}\n");
+    }
+
+    #[test]
+    fn format_clamps_width_and_refuses_comments() {
+        let format = FormatSettings { indent_width: 0, ..FormatSettings::default() };
+        assert_eq!(format.indent_unit(), " ");
+        assert_eq!(FormatSettings { indent_width: 20, ..format.clone() }.indent_unit().len(), 8);
+        assert!(matches!(format.format("{} // x"), Err(Error::HasComments)));
+        assert!(matches!(format.format("{"), Err(Error::InvalidJson(_))));
+    }
+}
@@ -0,0 +1,832 @@
+// 编辑器语法高亮：按行缓存记号和排版结果，各行的排版拼成整个文档的排版并按窗格缓存。
+// 内容变化时只重新扫描和排版改动的行，替换拼好的结果中对应的部分；后面的行只有高度或格式段数量
+// 变化时才需要平移。每行在文档中的位置也一并记录，行号栏据此只处理可见的行。
+// 括号匹配、折叠区域和缩进参考线都基于同一份记号
+
+use crate::theme::SyntaxColors;
+use crate::workspace::Pane;
+use eframe::egui::{self, epaint::text::TAB_SIZE, text::LayoutJob, text::LayoutSection, Color32, Galley, Rect, TextFormat};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::ops::Range;
+use std::sync::Arc;
+
+pub const FONT_SIZE: f32 = 14.0;
+// 折叠的行使用极小的字号，排版后行高为零
+const HIDDEN_FONT_SIZE: f32 = 0.01;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+enum Token {
+    Text,
+    Key,
+    String,
+    Number,
+    Keyword,
+    Punctuation,
+    Comment,
+}
+
+struct Line {
+    // 不含换行符
+    text: String,
+    // 行首是否处在 /* */ 注释中
+    starts_in_comment: bool,
+    ends_in_comment: bool,
+    // 按顺序覆盖整行
+    spans: Vec<(Range<usize>, Token)>,
+    // 字符串和注释之外的括号及其字节偏移
+    brackets: Vec<(usize, u8)>,
+    chars: usize,
+    // 本行单独排版的结果，每个窗格的宽度各一份
+    layouts: Vec<LineLayout>,
+}
+
+struct LineLayout {
+    wrap_width: f32,
+    style: Style,
+    hidden: bool,
+    galley: Arc<Galley>,
+}
+
+impl Line {
+    fn new(text: &str, starts_in_comment: bool) -> Self {
+        let (spans, ends_in_comment) = tokenize(text, starts_in_comment);
+        let brackets = spans
+            .iter()
+            .filter(|(_, token)| *token == Token::Punctuation)
+            .flat_map(|(range, _)| range.clone())
+            .map(|i| (i, text.as_bytes()[i]))
+            .filter(|(_, b)| matches!(b, b'{' | b'}' | b'[' | b']'))
+            .collect();
+        Self { text: text.to_string(), starts_in_comment, ends_in_comment, spans, brackets, chars: text.chars().count(), layouts: Vec::new() }
+    }
+
+    // 不含换行符单独排版；空行也要有一段格式，否则排版后行高为零
+    fn layout(&mut self, ui: &egui::Ui, wrap_width: f32, style: Style, hidden: bool) -> Arc<Galley> {
+        if let Some(cached) = self.layouts.iter().find(|l| l.wrap_width == wrap_width && l.style == style && l.hidden == hidden) {
+            return cached.galley.clone();
+        }
+        let mut job = LayoutJob { text: self.text.clone(), ..Default::default() };
+        job.wrap.max_width = wrap_width;
+        job.sections = if hidden {
+            vec![LayoutSection {
+                leading_space: 0.0,
+                byte_range: 0..self.text.len(),
+                format: TextFormat { color: Color32::TRANSPARENT, font_id: egui::FontId::monospace(HIDDEN_FONT_SIZE), ..Default::default() },
+            }]
+        } else if self.spans.is_empty() {
+            vec![section(0..0, style.color(Token::Text), false)]
+        } else {
+            self.spans.iter().map(|(range, token)| section(range.clone(), style.color(*token), *token == Token::Comment)).collect()
+        };
+        let galley = ui.fonts(|f| f.layout_job(job));
+        // 分屏时最多两个窗格，保留最近的两份
+        if self.layouts.len() >= 2 {
+            self.layouts.remove(0);
+        }
+        self.layouts.push(LineLayout { wrap_width, style, hidden, galley: galley.clone() });
+        galley
+    }
+
+    // 行首空白占的列数，制表符按排版时的宽度计算
+    fn indent_columns(&self) -> usize {
+        self.text
+            .chars()
+            .take_while(|c| c.is_whitespace())
+            .map(|c| if c == '\t' { TAB_SIZE } else { 1 })
+            .sum()
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+struct Style {
+    syntax: SyntaxColors,
+    text: Color32,
+}
+
+struct CachedGalley {
+    pane: Pane,
+    wrap_width: f32,
+    style: Style,
+    revision: u64,
+    galley: Arc<Galley>,
+    lines: Vec<StitchedLine>,
+}
+
+// 一行在拼好的排版中的位置
+#[derive(Clone, Copy)]
+struct StitchedLine {
+    first_row: usize,
+    first_section: usize,
+    rect: Rect,
+    mesh_bounds: Rect,
+}
+
+// 最近一次内容变化：旧内容中 start..old_end 行被替换为新内容中的 start..new_end 行
+#[derive(Clone, Copy)]
+struct LineEdit {
+    start: usize,
+    old_end: usize,
+    new_end: usize,
+}
+
+// 若干行拼接后的排版片段
+#[derive(Default)]
+struct Stitched {
+    rows: Vec<egui::epaint::text::Row>,
+    sections: Vec<LayoutSection>,
+    lines: Vec<StitchedLine>,
+    num_vertices: usize,
+    num_indices: usize,
+    pixels_per_point: Option<f32>,
+}
+
+#[derive(Default)]
+pub struct Highlighter {
+    content: String,
+    lines: Vec<Line>,
+    galleys: Vec<CachedGalley>,
+    // 上一个版本到当前版本的变化，用于修补按窗格缓存的排版
+    last_edit: Option<LineEdit>,
+    // 每行起始的字节偏移和字符偏移
+    line_starts: Vec<(usize, usize)>,
+    // 跨多行的对象和数组：起始行 → 结束行，同一行有多个时取最先出现的
+    regions: BTreeMap<usize, usize>,
+    // 互相匹配的括号，按字节偏移双向记录
+    pairs: HashMap<usize, usize>,
+    // 已折叠区域的起始行
+    folded: BTreeSet<usize>,
+    // 内容每变化一次加一，大纲据此判断是否需要重新解析
+    revision: u64,
+}
+
+impl Highlighter {
+    pub fn line_count(&self) -> usize {
+        self.lines.len().max(1)
+    }
+
+    pub fn revision(&self) -> u64 {
+        self.revision
+    }
+
+    // 与上次的内容比较，只重新扫描首尾未变部分之间的行；
+    // 改动影响到后面的注释状态时继续向后扫描
+    pub fn update(&mut self, content: &str) {
+        if content == self.content && !self.lines.is_empty() {
+            return;
+        }
+        self.content = content.to_string();
+        self.revision += 1;
+        let old_hidden = self.hidden_ranges();
+
+        let new_lines: Vec<&str> = content.split('\n').collect();
+        let prefix = self.lines.iter().zip(&new_lines).take_while(|(old, new)| old.text == **new).count();
+        let max_suffix = self.lines.len().min(new_lines.len()) - prefix;
+        let suffix = (1..=max_suffix)
+            .take_while(|k| self.lines[self.lines.len() - k].text == new_lines[new_lines.len() - k])
+            .count();
+
+        // 改动之后的折叠区域随行号平移，包含改动的折叠区域展开
+        let (old_end, new_end) = (self.lines.len() - suffix, new_lines.len() - suffix);
+        let folded = std::mem::take(&mut self.folded);
+        self.folded = folded
+            .into_iter()
+            .filter_map(|start| {
+                let end = self.regions.get(&start).copied().unwrap_or(start);
+                if end < prefix {
+                    Some(start)
+                } else if start >= old_end {
+                    Some(start + new_end - old_end)
+                } else {
+                    None
+                }
+            })
+            .collect();
+
+        let tail = self.lines.split_off(self.lines.len() - suffix);
+        self.lines.truncate(prefix);
+        let mut in_comment = self.lines.last().is_some_and(|line| line.ends_in_comment);
+        for text in &new_lines[prefix..new_lines.len() - suffix] {
+            let line = Line::new(text, in_comment);
+            in_comment = line.ends_in_comment;
+            self.lines.push(line);
+        }
+        // 注释状态变化而重新扫描的行也算作改动
+        let mut changed_end = new_end;
+        for mut line in tail {
+            if line.starts_in_comment != in_comment {
+                line = Line::new(&line.text, in_comment);
+                changed_end = self.lines.len() + 1;
+            }
+            in_comment = line.ends_in_comment;
+            self.lines.push(line);
+        }
+        self.rebuild_structure();
+        let regions = &self.regions;
+        self.folded.retain(|start| regions.contains_key(start));
+
+        // 最后一行是否带换行符可能改变，改动到末尾时连同前一行一起替换
+        let mut start = prefix;
+        if suffix == 0 {
+            start = start.min(old_end.saturating_sub(1)).min(new_end.saturating_sub(1));
+        }
+        let edit = LineEdit { start, old_end: changed_end + old_end - new_end, new_end: changed_end };
+        // 隐藏的行发生变化时无法只替换改动的行
+        let shifted: Vec<Range<usize>> = old_hidden
+            .iter()
+            .filter_map(|range| {
+                if range.end <= edit.start {
+                    Some(range.clone())
+                } else if range.start >= edit.old_end {
+                    Some(range.start + edit.new_end - edit.old_end..range.end + edit.new_end - edit.old_end)
+                } else {
+                    None
+                }
+            })
+            .collect();
+        if shifted.len() != old_hidden.len() || shifted != self.hidden_ranges() {
+            self.galleys.clear();
+        }
+        self.last_edit = Some(edit);
+    }
+
+    fn rebuild_structure(&mut self) {
+        self.line_starts.clear();
+        self.regions.clear();
+        self.pairs.clear();
+        let (mut byte, mut char) = (0, 0);
+        let mut stack: Vec<(usize, usize, u8)> = Vec::new();
+        for (index, line) in self.lines.iter().enumerate() {
+            self.line_starts.push((byte, char));
+            for &(offset, bracket) in &line.brackets {
+                let position = byte + offset;
+                match bracket {
+                    b'{' | b'[' => stack.push((index, position, bracket)),
+                    // 不匹配的右括号忽略
+                    _ => {
+                        let expected = if bracket == b'}' { b'{' } else { b'[' };
+                        if let Some(&(open_line, open, _)) = stack.last().filter(|(_, _, b)| *b == expected) {
+                            stack.pop();
+                            self.pairs.insert(open, position);
+                            self.pairs.insert(position, open);
+                            if index > open_line {
+                                self.regions.entry(open_line).or_insert(index);
+                            }
+                        }
+                    }
+                }
+            }
+            byte += line.text.len() + 1;
+            char += line.chars + 1;
+        }
+    }
+
+    // --- 折叠 ---
+    pub fn regions(&self) -> &BTreeMap<usize, usize> {
+        &self.regions
+    }
+
+    pub fn is_folded(&self, line: usize) -> bool {
+        self.folded.contains(&line)
+    }
+
+    pub fn toggle_fold(&mut self, line: usize) {
+        if !self.folded.remove(&line) && self.regions.contains_key(&line) {
+            self.folded.insert(line);
+        }
+        self.galleys.clear();
+    }
+
+    // 折叠区域隐藏起始行之后直到结束行（包含）的内容
+    fn hidden_ranges(&self) -> Vec<Range<usize>> {
+        let mut ranges: Vec<Range<usize>> = Vec::new();
+        for (&start, &end) in self.folded.iter().filter_map(|start| self.regions.get_key_value(start)) {
+            match ranges.last_mut() {
+                Some(last) if start < last.end => last.end = last.end.max(end + 1),
+                _ => ranges.push(start + 1..end + 1),
+            }
+        }
+        ranges
+    }
+
+    pub fn is_hidden(&self, line: usize) -> bool {
+        self.hidden_ranges().iter().any(|range| range.contains(&line))
+    }
+
+    // 光标进入折叠内容时展开所在的区域
+    pub fn unfold_line(&mut self, line: usize) {
+        let regions = &self.regions;
+        let before = self.folded.len();
+        self.folded.retain(|start| !regions.get(start).is_some_and(|&end| *start < line && line <= end));
+        if self.folded.len() != before {
+            self.galleys.clear();
+        }
+    }
+
+    // --- 位置换算 ---
+    pub fn line_of_char(&self, char_index: usize) -> usize {
+        self.line_starts.partition_point(|&(_, start)| start <= char_index).saturating_sub(1)
+    }
+
+    fn line_of_byte(&self, byte: usize) -> usize {
+        self.line_starts.partition_point(|&(start, _)| start <= byte).saturating_sub(1)
+    }
+
+    pub fn char_to_byte(&self, char_index: usize) -> Option<usize> {
+        let line = self.line_of_char(char_index);
+        let (byte, char) = *self.line_starts.get(line)?;
+        let text = &self.lines[line].text;
+        text.char_indices().map(|(i, _)| i).chain([text.len()]).nth(char_index - char).map(|i| byte + i)
+    }
+
+    // 超出末尾的偏移按末尾处理
+    pub fn byte_to_char(&self, byte_index: usize) -> usize {
+        let byte_index = byte_index.min(self.content.len());
+        let line = self.line_of_byte(byte_index);
+        let (byte, char) = self.line_starts[line];
+        char + self.lines[line].text[..byte_index - byte].chars().count()
+    }
+
+    pub fn indent_columns(&self, line: usize) -> usize {
+        self.lines.get(line).map_or(0, Line::indent_columns)
+    }
+
+    // 光标右侧或左侧是括号时返回这对括号的字符位置
+    pub fn matching_bracket(&self, cursor: usize) -> Option<(usize, usize)> {
+        std::iter::once(cursor).chain(cursor.checked_sub(1)).find_map(|char_index| {
+            let byte = self.char_to_byte(char_index)?;
+            let other = *self.pairs.get(&byte)?;
+            Some((char_index, self.byte_to_char(other)))
+        })
+    }
+
+    // 作为 TextEdit 的 layouter 使用
+    pub fn layout(&mut self, ui: &egui::Ui, content: &str, wrap_width: f32, pane: Pane, syntax: SyntaxColors, text: Color32) -> Arc<Galley> {
+        self.update(content);
+        let style = Style { syntax, text };
+        let hidden = self.hidden_ranges();
+        let position = self.galleys.iter().position(|g| g.pane == pane && g.wrap_width == wrap_width && g.style == style);
+        if let Some(index) = position {
+            let cached = &mut self.galleys[index];
+            if cached.revision == self.revision {
+                return cached.galley.clone();
+            }
+            if let Some(edit) = self.last_edit.filter(|_| cached.revision + 1 == self.revision) {
+                patch(cached, &mut self.lines, ui, &self.line_starts, &hidden, edit, content);
+                cached.revision = self.revision;
+                return cached.galley.clone();
+            }
+        }
+
+        let count = self.lines.len();
+        let stitched = stitch(&mut self.lines, ui, 0..count, wrap_width, style, &hidden, (0, 0.0, 0, 0));
+        let mut job = LayoutJob { text: content.to_string(), sections: stitched.sections, ..Default::default() };
+        job.wrap.max_width = wrap_width;
+        let galley = Arc::new(Galley {
+            job: Arc::new(job),
+            rows: stitched.rows,
+            elided: false,
+            rect: bounds(stitched.lines.iter().map(|line| line.rect)),
+            mesh_bounds: stitched.lines.iter().fold(Rect::NOTHING, |bounds, line| bounds.union(line.mesh_bounds)),
+            num_vertices: stitched.num_vertices,
+            num_indices: stitched.num_indices,
+            pixels_per_point: stitched.pixels_per_point.unwrap_or_else(|| ui.ctx().pixels_per_point()),
+        });
+        self.galleys.retain(|g| g.pane != pane);
+        self.galleys.push(CachedGalley { pane, wrap_width, style, revision: self.revision, galley: galley.clone(), lines: stitched.lines });
+        galley
+    }
+
+    // 与裁剪范围相交的行及其区域（排版坐标），折叠隐藏的行不包含在内
+    pub fn visible_lines(&self, pane: Pane, top: f32, bottom: f32) -> Vec<(usize, Rect)> {
+        let Some(cached) = self.galleys.iter().filter(|g| g.pane == pane).max_by_key(|g| g.revision) else { return Vec::new() };
+        let first = cached.lines.partition_point(|line| line.rect.bottom() < top);
+        cached.lines[first..]
+            .iter()
+            .enumerate()
+            .take_while(|(_, line)| line.rect.top() <= bottom)
+            .filter(|(_, line)| line.rect.height() > 0.0)
+            .map(|(offset, line)| (first + offset, line.rect))
+            .collect()
+    }
+}
+
+// 排版 range 中的各行并依次向下拼接；start 是第一行的字节偏移、纵坐标、排版行序号和格式段序号
+fn stitch(
+    lines: &mut [Line],
+    ui: &egui::Ui,
+    range: Range<usize>,
+    wrap_width: f32,
+    style: Style,
+    hidden: &[Range<usize>],
+    start: (usize, f32, usize, usize),
+) -> Stitched {
+    let (mut offset, mut y, first_row, first_section) = start;
+    let last = lines.len().saturating_sub(1);
+    let mut stitched = Stitched::default();
+    for index in range {
+        let line = &mut lines[index];
+        let is_hidden = hidden.iter().any(|range| range.contains(&index));
+        let line_galley = line.layout(ui, wrap_width, style, is_hidden);
+        let newline = usize::from(index < last);
+        let section_start = (first_section + stitched.sections.len()) as u32;
+        let section_count = line_galley.job.sections.len();
+        stitched.lines.push(StitchedLine {
+            first_row: first_row + stitched.rows.len(),
+            first_section: section_start as usize,
+            rect: line_galley.rect.translate(egui::vec2(0.0, y)),
+            mesh_bounds: line_galley.mesh_bounds.translate(egui::vec2(0.0, y)),
+        });
+        // 字节范围和格式段序号换算到整个文档，换行符并入本行最后一段
+        stitched.sections.extend(line_galley.job.sections.iter().enumerate().map(|(i, section)| {
+            let end = section.byte_range.end + if i + 1 == section_count { newline } else { 0 };
+            LayoutSection { byte_range: offset + section.byte_range.start..offset + end, ..section.clone() }
+        }));
+        let row_count = line_galley.rows.len();
+        for (i, row) in line_galley.rows.iter().enumerate() {
+            let mut row = row.clone();
+            row.ends_with_newline = newline > 0 && i + 1 == row_count;
+            shift_row(&mut row, y, section_start);
+            stitched.rows.push(row);
+        }
+        stitched.num_vertices += line_galley.num_vertices;
+        stitched.num_indices += line_galley.num_indices;
+        stitched.pixels_per_point = Some(line_galley.pixels_per_point);
+        y += line_galley.rect.height();
+        offset += line.text.len() + newline;
+    }
+    stitched
+}
+
+// 用改动的行重新排版的结果替换缓存中对应的部分，后面的行按高度和格式段数量的变化平移
+fn patch(cached: &mut CachedGalley, lines: &mut [Line], ui: &egui::Ui, line_starts: &[(usize, usize)], hidden: &[Range<usize>], edit: LineEdit, content: &str) {
+    let old_lines = &cached.lines;
+    let old_galley = &cached.galley;
+    let next = |index: usize| old_lines.get(index).copied();
+    let start = next(edit.start);
+    let first_row = start.map_or(old_galley.rows.len(), |line| line.first_row);
+    let first_section = start.map_or(old_galley.job.sections.len(), |line| line.first_section);
+    let top = start.map_or_else(|| old_lines.last().map_or(0.0, |line| line.rect.bottom()), |line| line.rect.top());
+    let (end_row, end_section, end_top) = match next(edit.old_end) {
+        Some(line) => (line.first_row, line.first_section, line.rect.top()),
+        None => (old_galley.rows.len(), old_galley.job.sections.len(), old_lines.last().map_or(0.0, |line| line.rect.bottom())),
+    };
+    let old_bytes = old_galley.job.sections.get(first_section).map_or(old_galley.job.text.len(), |s| s.byte_range.start)
+        ..old_galley.job.sections.get(end_section).map_or(old_galley.job.text.len(), |s| s.byte_range.start);
+
+    let offset = line_starts.get(edit.start).map_or(content.len(), |&(byte, _)| byte);
+    let stitched = stitch(lines, ui, edit.start..edit.new_end, cached.wrap_width, cached.style, hidden, (offset, top, first_row, first_section));
+    let new_bottom = stitched.lines.last().map_or(top, |line| line.rect.bottom());
+    let dy = new_bottom - end_top;
+    let row_delta = stitched.rows.len() as isize - (end_row - first_row) as isize;
+    let section_delta = stitched.sections.len() as isize - (end_section - first_section) as isize;
+    let new_bytes = line_starts.get(edit.new_end).map_or(content.len(), |&(byte, _)| byte) - offset;
+    let byte_delta = new_bytes as isize - old_bytes.len() as isize;
+
+    let galley = Arc::make_mut(&mut cached.galley);
+    let (old_vertices, old_indices) = galley.rows[first_row..end_row].iter().fold((0, 0), |(v, i), row| (v + row.visuals.mesh.vertices.len(), i + row.visuals.mesh.indices.len()));
+    galley.num_vertices = galley.num_vertices + stitched.num_vertices - old_vertices;
+    galley.num_indices = galley.num_indices + stitched.num_indices - old_indices;
+    galley.rows.splice(first_row..end_row, stitched.rows);
+    if dy != 0.0 || section_delta != 0 {
+        for row in &mut galley.rows[(end_row as isize + row_delta) as usize..] {
+            shift_row(row, dy, section_delta as u32);
+        }
+    }
+
+    let job = Arc::make_mut(&mut galley.job);
+    job.text.replace_range(old_bytes.clone(), &content[offset..offset + new_bytes]);
+    job.sections.splice(first_section..end_section, stitched.sections);
+    if byte_delta != 0 {
+        for section in &mut job.sections[(end_section as isize + section_delta) as usize..] {
+            section.byte_range = (section.byte_range.start as isize + byte_delta) as usize..(section.byte_range.end as isize + byte_delta) as usize;
+        }
+    }
+
+    cached.lines.splice(edit.start..edit.old_end, stitched.lines);
+    for line in &mut cached.lines[edit.new_end..] {
+        line.first_row = (line.first_row as isize + row_delta) as usize;
+        line.first_section = (line.first_section as isize + section_delta) as usize;
+        line.rect = line.rect.translate(egui::vec2(0.0, dy));
+        line.mesh_bounds = line.mesh_bounds.translate(egui::vec2(0.0, dy));
+    }
+    galley.rect = bounds(cached.lines.iter().map(|line| line.rect));
+    galley.mesh_bounds = cached.lines.iter().fold(Rect::NOTHING, |bounds, line| bounds.union(line.mesh_bounds));
+    if let Some(pixels_per_point) = stitched.pixels_per_point {
+        galley.pixels_per_point = pixels_per_point;
+    }
+}
+
+// 整个文档的区域：从顶部开始，宽度取最宽的行
+fn bounds(rects: impl Iterator<Item = Rect>) -> Rect {
+    let (mut min_x, mut max_x, mut bottom) = (0.0f32, 0.0f32, 0.0f32);
+    for rect in rects {
+        min_x = min_x.min(rect.min.x);
+        max_x = max_x.max(rect.max.x);
+        bottom = bottom.max(rect.max.y);
+    }
+    Rect::from_min_max(egui::pos2(min_x, 0.0), egui::pos2(max_x, bottom))
+}
+
+// 下移 dy 并把格式段序号加上 sections（可以是补码表示的负数）
+fn shift_row(row: &mut egui::epaint::text::Row, dy: f32, sections: u32) {
+    row.section_index_at_start = row.section_index_at_start.wrapping_add(sections);
+    row.rect = row.rect.translate(egui::vec2(0.0, dy));
+    for glyph in &mut row.glyphs {
+        glyph.pos.y += dy;
+        glyph.section_index = glyph.section_index.wrapping_add(sections);
+    }
+    if dy != 0.0 {
+        for vertex in &mut row.visuals.mesh.vertices {
+            vertex.pos.y += dy;
+        }
+        row.visuals.mesh_bounds = row.visuals.mesh_bounds.translate(egui::vec2(0.0, dy));
+    }
+}
+
+impl Style {
+    fn color(&self, token: Token) -> Color32 {
+        match token {
+            Token::Text => self.text,
+            Token::Key => self.syntax.key,
+            Token::String => self.syntax.string,
+            Token::Number => self.syntax.number,
+            Token::Keyword => self.syntax.keyword,
+            Token::Punctuation => self.syntax.punctuation,
+            Token::Comment => self.syntax.comment,
+        }
+    }
+}
+
+fn section(byte_range: Range<usize>, color: Color32, italics: bool) -> LayoutSection {
+    LayoutSection {
+        leading_space: 0.0,
+        byte_range,
+        format: TextFormat { color, italics, font_id: egui::FontId::monospace(FONT_SIZE), ..Default::default() },
+    }
+}
+
+// 扫描一行，返回记号和行尾是否仍在块注释中。JSON 字符串不能跨行，只有块注释需要跨行状态
+fn tokenize(text: &str, mut in_comment: bool) -> (Vec<(Range<usize>, Token)>, bool) {
+    let bytes = text.as_bytes();
+    let mut spans: Vec<(Range<usize>, Token)> = Vec::new();
+    let mut push = |range: Range<usize>, token: Token| match spans.last_mut() {
+        Some((last, last_token)) if *last_token == token && last.end == range.start => last.end = range.end,
+        _ => spans.push((range, token)),
+    };
+    let mut i = 0;
+    while i < bytes.len() {
+        if in_comment {
+            let end = text[i..].find("*/").map(|offset| i + offset + 2);
+            in_comment = end.is_none();
+            let end = end.unwrap_or(bytes.len());
+            push(i..end, Token::Comment);
+            i = end;
+            continue;
+        }
+        let start = i;
+        match bytes[i] {
+            b'"' => {
+                i += 1;
+                while i < bytes.len() && bytes[i] != b'"' {
+                    i += if bytes[i] == b'\\' { 2 } else { 1 };
+                }
+                i = (i + 1).min(bytes.len());
+                // 后面紧跟冒号的字符串是键名
+                let token = if text[i..].trim_start().starts_with(':') { Token::Key } else { Token::String };
+                push(start..i, token);
+            }
+            b'/' if bytes.get(i + 1) == Some(&b'/') => {
+                push(i..bytes.len(), Token::Comment);
+                i = bytes.len();
+            }
+            b'/' if bytes.get(i + 1) == Some(&b'*') => {
+                push(i..i + 2, Token::Comment);
+                in_comment = true;
+                i += 2;
+            }
+            b'{' | b'}' | b'[' | b']' | b',' | b':' => {
+                push(i..i + 1, Token::Punctuation);
+                i += 1;
+            }
+            b'-' | b'0'..=b'9' => {
+                while i < bytes.len() && matches!(bytes[i], b'0'..=b'9' | b'-' | b'+' | b'.' | b'e' | b'E') {
+                    i += 1;
+                }
+                push(start..i, Token::Number);
+            }
+            b if b.is_ascii_alphabetic() => {
+                while i < bytes.len() && bytes[i].is_ascii_alphanumeric() {
+                    i += 1;
+                }
+                let token = if matches!(&text[start..i], "true" | "false" | "null") { Token::Keyword } else { Token::Text };
+                push(start..i, token);
+            }
+            _ => {
+                // 其余字符（空白、非 ASCII 等）按完整字符前进
+                i += text[i..].chars().next().map_or(1, char::len_utf8);
+                push(start..i, Token::Text);
+            }
+        }
+    }
+    (spans, in_comment)
+}
+
+// 输入左括号或引号时自动补全的右半部分
+pub fn closing_pair(ch: char) -> Option<char> {
+    match ch {
+        '{' => Some('}'),
+        '[' => Some(']'),
+        '"' => Some('"'),
+        _ => None,
+    }
+}
+
+// 光标后正好是输入的右括号或引号（通常是自动补全的）时，跳过它而不是再插入一个
+pub fn skips_closing(content: &str, cursor: usize, typed: char) -> bool {
+    matches!(typed, '}' | ']' | '"') && content.chars().nth(cursor) == Some(typed)
+}
+
+// 刚输入左括号或引号后补上右半部分，光标留在中间。只在后面是空白或结构符号时补全，
+// 引号还要求前面不是字符内容，避免在关闭字符串时多补一个
+pub fn auto_close(content: &mut String, cursor: usize, typed: char) -> bool {
+    let Some(close) = closing_pair(typed) else { return false };
+    if cursor == 0 || content.chars().nth(cursor - 1) != Some(typed) {
+        return false;
+    }
+    let before = cursor.checked_sub(2).and_then(|i| content.chars().nth(i));
+    let (byte, next) = content.char_indices().nth(cursor).map_or((content.len(), None), |(i, c)| (i, Some(c)));
+    let boundary = |c: Option<char>, symbols: &[char]| c.is_none_or(|c| c.is_whitespace() || symbols.contains(&c));
+    if !boundary(next, &[',', '}', ']', ':']) || (typed == '"' && !boundary(before, &[':', ',', '[', '{'])) {
+        return false;
+    }
+    content.insert(byte, close);
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::theme::Palette;
+
+    const SAMPLE: &str = "{\n  // 注释\n  \"env\": {\"A\": \"1\", \"B\": 2},\n\n  \"on\": true /* a\n  b */\n}\n";
+
+    fn with_ui(f: impl FnOnce(&egui::Ui)) {
+        let ctx = egui::Context::default();
+        let mut f = Some(f);
+        let _ = ctx.run(egui::RawInput::default(), |ctx| {
+            egui::CentralPanel::default().show(ctx, |ui| (f.take().unwrap())(ui));
+        });
+    }
+
+    // 把整个文档当作一个排版任务，与逐行排版拼接的结果对照
+    fn full_layout(ui: &egui::Ui, highlighter: &Highlighter, content: &str, wrap_width: f32, style: Style) -> Arc<Galley> {
+        let mut job = LayoutJob { text: content.to_string(), ..Default::default() };
+        job.wrap.max_width = wrap_width;
+        let mut offset = 0;
+        for (index, line) in highlighter.lines.iter().enumerate() {
+            let newline = usize::from(index + 1 < highlighter.lines.len());
+            if line.spans.is_empty() {
+                job.sections.push(section(offset..offset + line.text.len() + newline, style.color(Token::Text), false));
+            } else {
+                let last = line.spans.len() - 1;
+                for (i, (range, token)) in line.spans.iter().enumerate() {
+                    let end = offset + range.end + if i == last { newline } else { 0 };
+                    job.sections.push(section(offset + range.start..end, style.color(*token), *token == Token::Comment));
+                }
+            }
+            offset += line.text.len() + newline;
+        }
+        ui.fonts(|f| f.layout_job(job))
+    }
+
+    #[test]
+    fn stitched_layout_matches_full_layout() {
+        with_ui(|ui| {
+            let syntax = Palette::DARK.syntax;
+            let style = Style { syntax, text: Color32::WHITE };
+            for wrap_width in [f32::INFINITY, 60.0] {
+                let mut highlighter = Highlighter::default();
+                let stitched = highlighter.layout(ui, SAMPLE, wrap_width, Pane::Primary, syntax, Color32::WHITE);
+                let full = full_layout(ui, &highlighter, SAMPLE, wrap_width, style);
+                assert_eq!(stitched.rows.len(), full.rows.len());
+                assert_eq!(stitched.rect, full.rect);
+                assert_eq!(stitched.num_vertices, full.num_vertices);
+                for (a, b) in stitched.rows.iter().zip(&full.rows) {
+                    assert_eq!(a.rect, b.rect);
+                    assert_eq!(a.ends_with_newline, b.ends_with_newline);
+                    assert_eq!(a.glyphs.iter().map(|g| (g.chr, g.pos)).collect::<Vec<_>>(), b.glyphs.iter().map(|g| (g.chr, g.pos)).collect::<Vec<_>>());
+                }
+                for index in 0..=SAMPLE.chars().count() {
+                    let cursor = egui::text::CCursor::new(index);
+                    assert_eq!(stitched.pos_from_ccursor(cursor), full.pos_from_ccursor(cursor));
+                }
+            }
+        });
+    }
+
+    #[test]
+    fn edits_only_relayout_changed_lines() {
+        with_ui(|ui| {
+            let syntax = Palette::DARK.syntax;
+            let mut highlighter = Highlighter::default();
+            highlighter.layout(ui, SAMPLE, f32::INFINITY, Pane::Primary, syntax, Color32::WHITE);
+            let before: Vec<Arc<Galley>> = highlighter.lines.iter().map(|line| line.layouts[0].galley.clone()).collect();
+            let edited = SAMPLE.replacen("true", "false", 1);
+            highlighter.layout(ui, &edited, f32::INFINITY, Pane::Primary, syntax, Color32::WHITE);
+            let reused: Vec<bool> = highlighter.lines.iter().zip(&before).map(|(line, old)| Arc::ptr_eq(&line.layouts[0].galley, old)).collect();
+            assert_eq!(reused, [true, true, true, true, false, true, true, true]);
+        });
+    }
+
+    // 修补时后面的行按高度差平移，坐标与逐行累加的结果只有浮点误差
+    fn assert_same_galley(a: &Galley, b: &Galley) {
+        let point = |p: egui::Pos2| ((p.x * 1000.0).round() as i64, (p.y * 1000.0).round() as i64);
+        let rect = |r: Rect| (point(r.min), point(r.max));
+        assert_eq!(a.job.text, b.job.text);
+        assert_eq!(a.job.sections, b.job.sections);
+        assert_eq!((rect(a.rect), rect(a.mesh_bounds), a.num_vertices, a.num_indices), (rect(b.rect), rect(b.mesh_bounds), b.num_vertices, b.num_indices));
+        assert_eq!(a.rows.len(), b.rows.len());
+        for (a, b) in a.rows.iter().zip(&b.rows) {
+            assert_eq!((rect(a.rect), a.ends_with_newline, a.section_index_at_start), (rect(b.rect), b.ends_with_newline, b.section_index_at_start));
+            let glyphs = |row: &egui::epaint::text::Row| row.glyphs.iter().map(|g| (g.chr, point(g.pos), g.section_index)).collect::<Vec<_>>();
+            assert_eq!(glyphs(a), glyphs(b));
+            let vertices = |row: &egui::epaint::text::Row| row.visuals.mesh.vertices.iter().map(|v| point(v.pos)).collect::<Vec<_>>();
+            assert_eq!(vertices(a), vertices(b));
+        }
+    }
+
+    #[test]
+    fn patched_layout_matches_fresh_layout() {
+        with_ui(|ui| {
+            let syntax = Palette::DARK.syntax;
+            let mut highlighter = Highlighter::default();
+            let mut content = SAMPLE.to_string();
+            let mut previous = Arc::as_ptr(&highlighter.layout(ui, &content, 60.0, Pane::Primary, syntax, Color32::WHITE));
+            let edits: [&dyn Fn(&str) -> String; 7] = [
+                // 行内修改、增加格式段、插入和删除行、改变后面行的注释状态、修改末尾
+                &|c| c.replacen("true", "false", 1),
+                &|c| c.replacen("\"B\": 2", "\"B\": 2, \"C\": [1, 2]", 1),
+                &|c| c.replacen("\n\n", "\n  \"x\": 1,\n  \"y\": \"a very long value that wraps\",\n", 1),
+                &|c| c.replacen("  // 注释\n", "", 1),
+                &|c| c.replacen("/* a", "/* a */", 1),
+                &|c| c.trim_end().to_string(),
+                &|c| format!("{}\n", c),
+            ];
+            for edit in edits {
+                content = edit(&content);
+                let patched = highlighter.layout(ui, &content, 60.0, Pane::Primary, syntax, Color32::WHITE);
+                let fresh = Highlighter::default().layout(ui, &content, 60.0, Pane::Primary, syntax, Color32::WHITE);
+                assert_same_galley(&patched, &fresh);
+                // 没有其他引用时原地修补，而不是重新拼接
+                assert_eq!(Arc::as_ptr(&patched), previous);
+                previous = Arc::as_ptr(&patched);
+            }
+        });
+    }
+
+    #[test]
+    fn visible_lines_start_at_first_visible_line() {
+        with_ui(|ui| {
+            let mut highlighter = Highlighter::default();
+            let galley = highlighter.layout(ui, SAMPLE, f32::INFINITY, Pane::Primary, Palette::DARK.syntax, Color32::WHITE);
+            let height = galley.rows[0].rect.height();
+            let lines: Vec<usize> = highlighter.visible_lines(Pane::Primary, height * 2.5, height * 4.5).iter().map(|(line, _)| *line).collect();
+            assert_eq!(lines, [2, 3, 4]);
+            highlighter.toggle_fold(0);
+            highlighter.layout(ui, SAMPLE, f32::INFINITY, Pane::Primary, Palette::DARK.syntax, Color32::WHITE);
+            let lines: Vec<usize> = highlighter.visible_lines(Pane::Primary, 0.0, f32::INFINITY).iter().map(|(line, _)| *line).collect();
+            assert_eq!(lines, [0, 7]);
+        });
+    }
+
+    #[test]
+    fn tokenizes_block_comments_across_lines() {
+        let mut highlighter = Highlighter::default();
+        highlighter.update(SAMPLE);
+        assert!(highlighter.lines[4].ends_in_comment);
+        assert_eq!(highlighter.lines[5].spans[0].1, Token::Comment);
+        assert_eq!(highlighter.lines[2].spans[1], (2..7, Token::Key));
+    }
+
+    #[test]
+    fn folds_regions_and_matches_brackets() {
+        let mut highlighter = Highlighter::default();
+        highlighter.update(SAMPLE);
+        assert_eq!(highlighter.regions().get(&0), Some(&6));
+        assert_eq!(highlighter.matching_bracket(0), Some((0, SAMPLE.chars().count() - 2)));
+        highlighter.toggle_fold(0);
+        assert!(highlighter.is_hidden(3) && !highlighter.is_hidden(0));
+        highlighter.unfold_line(3);
+        assert!(!highlighter.is_folded(0));
+    }
+
+    #[test]
+    fn auto_closes_only_at_boundaries() {
+        let mut content = String::from("{}");
+        assert!(auto_close(&mut content, 1, '{'));
+        assert_eq!(content, "{}}");
+        let mut content = String::from("\"ab\"");
+        assert!(!auto_close(&mut content, 4, '"'));
+        assert!(skips_closing("{}", 1, '}'));
+    }
+}
@@ -0,0 +1,459 @@
+// Claude Code 的 hooks：按事件和匹配器组织的命令。提供与可视化编辑器之间的转换、
+// 命令引用的脚本检查，以及用示例输入试运行命令
+
+use crate::error::{Error, Result};
+use crate::format::FormatSettings;
+use crate::jsonc;
+use crate::outline;
+use crate::t;
+use serde_json::{json, Map, Value};
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, Stdio};
+use std::sync::mpsc::{self, Receiver};
+use std::thread;
+use std::time::{Duration, Instant};
+
+pub const PROFILE_KEY: &str = "hooks";
+// Claude Code 未设置 timeout 时的默认超时
+pub const DEFAULT_TIMEOUT_SECS: u64 = 60;
+pub const PROJECT_DIR_VAR: &str = "CLAUDE_PROJECT_DIR";
+
+pub const EVENTS: [&str; 9] = [
+    "PreToolUse",
+    "PostToolUse",
+    "UserPromptSubmit",
+    "Notification",
+    "Stop",
+    "SubagentStop",
+    "PreCompact",
+    "SessionStart",
+    "SessionEnd",
+];
+
+// 只有这些事件使用匹配器：工具事件匹配工具名，PreCompact 和 SessionStart 匹配触发方式
+pub fn uses_matcher(event: &str) -> bool {
+    matches!(event, "PreToolUse" | "PostToolUse" | "PreCompact" | "SessionStart")
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct HookCommand {
+    pub command: String,
+    pub timeout: Option<u64>,
+    // type 以外的其余字段原样保留
+    pub extra: Map<String, Value>,
+}
+
+impl HookCommand {
+    pub fn new(command: impl Into<String>) -> Self {
+        Self { command: command.into(), timeout: None, extra: Map::new() }
+    }
+
+    fn from_value(value: &Value) -> Option<Self> {
+        let object = value.as_object()?;
+        let extra = object
+            .iter()
+            .filter(|(key, _)| !matches!(key.as_str(), "type" | "command" | "timeout"))
+            .map(|(key, value)| (key.clone(), value.clone()))
+            .collect();
+        Some(Self {
+            command: object.get("command").and_then(Value::as_str).unwrap_or_default().to_string(),
+            timeout: object.get("timeout").and_then(Value::as_u64),
+            extra,
+        })
+    }
+
+    fn to_value(&self) -> Value {
+        let mut object = Map::new();
+        object.insert("type".to_string(), "command".into());
+        object.insert("command".to_string(), self.command.clone().into());
+        if let Some(timeout) = self.timeout {
+            object.insert("timeout".to_string(), timeout.into());
+        }
+        object.extend(self.extra.clone());
+        Value::Object(object)
+    }
+
+    pub fn timeout(&self) -> Duration {
+        Duration::from_secs(self.timeout.unwrap_or(DEFAULT_TIMEOUT_SECS))
+    }
+}
+
+// hooks.<event>[] 中的一项
+#[derive(Clone, PartialEq, Debug)]
+pub struct HookGroup {
+    pub event: String,
+    pub matcher: String,
+    pub commands: Vec<HookCommand>,
+}
+
+impl HookGroup {
+    pub fn new(event: impl Into<String>) -> Self {
+        Self { event: event.into(), matcher: String::new(), commands: vec![HookCommand::new("")] }
+    }
+
+    fn to_value(&self) -> Value {
+        let mut object = Map::new();
+        if uses_matcher(&self.event) && !self.matcher.is_empty() {
+            object.insert("matcher".to_string(), self.matcher.clone().into());
+        }
+        object.insert("hooks".to_string(), self.commands.iter().map(HookCommand::to_value).collect());
+        Value::Object(object)
+    }
+}
+
+// 按文件中的顺序展开为分组列表
+pub fn from_settings(settings: &Value) -> Vec<HookGroup> {
+    let Some(events) = settings.get(PROFILE_KEY).and_then(Value::as_object) else { return Vec::new() };
+    let mut groups = Vec::new();
+    for (event, items) in events {
+        for item in items.as_array().into_iter().flatten() {
+            let commands = item.get("hooks").and_then(Value::as_array).map_or_else(Vec::new, |hooks| hooks.iter().filter_map(HookCommand::from_value).collect());
+            groups.push(HookGroup {
+                event: event.clone(),
+                matcher: item.get("matcher").and_then(Value::as_str).unwrap_or_default().to_string(),
+                commands,
+            });
+        }
+    }
+    groups
+}
+
+pub fn to_value(groups: &[HookGroup]) -> Value {
+    let mut events = Map::new();
+    for group in groups.iter().filter(|g| !g.commands.is_empty()) {
+        let items = events.entry(group.event.clone()).or_insert_with(|| Value::Array(Vec::new()));
+        if let Value::Array(items) = items {
+            items.push(group.to_value());
+        }
+    }
+    Value::Object(events)
+}
+
+// 只替换配置中的 hooks 成员，其余内容和注释保持不变；没有任何命令时删除 hooks
+pub fn set_in_profile(content: &str, groups: &[HookGroup], format: &FormatSettings) -> Result<String> {
+    let value = to_value(groups);
+    let value = value.as_object().is_some_and(|events| !events.is_empty()).then_some(&value);
+    outline::replace_member(content, PROFILE_KEY, value, format)
+}
+
+pub fn from_content(content: &str) -> Result<Vec<HookGroup>> {
+    let settings: Value = jsonc::from_str(content).map_err(Error::InvalidJson)?;
+    Ok(from_settings(&settings))
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub enum Issue {
+    Empty,
+    // 命令用到了 $CLAUDE_PROJECT_DIR，但没有设置项目目录，无法检查
+    NoProjectDir,
+    NotOnPath(String),
+    NotFound(PathBuf),
+    NotAFile(PathBuf),
+    NotExecutable(PathBuf),
+}
+
+impl Issue {
+    pub fn message(&self) -> String {
+        match self {
+            Issue::Empty => t!("hooks.issue.empty").to_string(),
+            Issue::NoProjectDir => t!("hooks.issue.no_project_dir").to_string(),
+            Issue::NotOnPath(name) => t!("hooks.issue.not_on_path", name = name),
+            Issue::NotFound(path) => t!("hooks.issue.not_found", path = path.display()),
+            Issue::NotAFile(path) => t!("hooks.issue.not_a_file", path = path.display()),
+            Issue::NotExecutable(path) => t!("hooks.issue.not_executable", path = path.display()),
+        }
+    }
+}
+
+// 由解释器运行的脚本只需要存在，不要求可执行
+const INTERPRETERS: [&str; 11] = ["sh", "bash", "zsh", "fish", "python", "python3", "node", "deno", "bun", "ruby", "perl"];
+
+// shell 内置命令不在 PATH 中
+const BUILTINS: [&str; 14] = ["cd", "echo", "exit", "export", "test", "[", "true", "false", "source", ".", "exec", "printf", "set", "eval"];
+
+// 检查命令的程序以及交给解释器运行的脚本。只看第一段命令，管道和 && 之后的部分不检查
+pub fn check_command(command: &str, home: &Path, project_dir: Option<&Path>) -> Vec<Issue> {
+    let words = split_words(command);
+    // 跳过开头的 VAR=value 环境变量赋值
+    let start = words.iter().take_while(|w| w.split_once('=').is_some_and(|(name, _)| !name.is_empty() && !name.contains('/'))).count();
+    let words = &words[start..];
+    let Some(program) = words.first() else { return vec![Issue::Empty] };
+    if words.iter().any(|w| w.contains(PROJECT_DIR_VAR)) && project_dir.is_none() {
+        return vec![Issue::NoProjectDir];
+    }
+    let expand = |word: &str| expand_word(word, home, project_dir);
+    // 命令在项目目录中运行，相对路径相对于项目目录
+    let resolve = |word: &str| match project_dir {
+        Some(dir) => dir.join(expand(word)),
+        None => PathBuf::from(expand(word)),
+    };
+    let mut issues = Vec::new();
+    if program.contains('/') || program.contains('\\') {
+        issues.extend(check_file(&resolve(program), true));
+    } else if !BUILTINS.contains(&program.as_str()) && find_on_path(program).is_none() {
+        issues.push(Issue::NotOnPath(program.clone()));
+    }
+    let name = Path::new(program).file_name().and_then(|n| n.to_str()).unwrap_or_default();
+    if INTERPRETERS.contains(&name) {
+        // 第一个不是选项的参数就是脚本；-c 之后是内联代码
+        for arg in &words[1..] {
+            if arg == "-c" || arg == "-e" {
+                break;
+            }
+            if !arg.starts_with('-') {
+                issues.extend(check_file(&resolve(arg), false));
+                break;
+            }
+        }
+    }
+    issues
+}
+
+fn check_file(path: &Path, executable: bool) -> Option<Issue> {
+    let Ok(metadata) = path.metadata() else { return Some(Issue::NotFound(path.to_path_buf())) };
+    if !metadata.is_file() {
+        return Some(Issue::NotAFile(path.to_path_buf()));
+    }
+    (executable && !is_executable(&metadata)).then(|| Issue::NotExecutable(path.to_path_buf()))
+}
+
+#[cfg(unix)]
+fn is_executable(metadata: &std::fs::Metadata) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+    metadata.permissions().mode() & 0o111 != 0
+}
+
+#[cfg(not(unix))]
+fn is_executable(_metadata: &std::fs::Metadata) -> bool {
+    true
+}
+
+fn find_on_path(name: &str) -> Option<PathBuf> {
+    let extensions: &[&str] = if cfg!(windows) { &["", ".exe", ".cmd", ".bat"] } else { &[""] };
+    std::env::split_paths(&std::env::var_os("PATH")?)
+        .flat_map(|dir| extensions.iter().map(move |ext| dir.join(format!("{}{}", name, ext))))
+        .find(|path| path.metadata().is_ok_and(|m| m.is_file() && is_executable(&m)))
+}
+
+fn expand_word(word: &str, home: &Path, project_dir: Option<&Path>) -> String {
+    let home_dir = home.to_string_lossy();
+    let mut word = word.replace("${HOME}", &home_dir).replace("$HOME", &home_dir);
+    if let Some(dir) = project_dir {
+        let dir = dir.to_string_lossy();
+        word = word.replace("${CLAUDE_PROJECT_DIR}", &dir).replace("$CLAUDE_PROJECT_DIR", &dir);
+    }
+    match word.strip_prefix("~/") {
+        Some(rest) => home.join(rest).to_string_lossy().to_string(),
+        None => word,
+    }
+}
+
+// 按 shell 的规则拆出第一段命令的各个词，遇到 | ; & 停止
+fn split_words(command: &str) -> Vec<String> {
+    let mut words = Vec::new();
+    let mut word = String::new();
+    let mut in_word = false;
+    let mut quote = None;
+    let mut chars = command.chars();
+    while let Some(c) = chars.next() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (Some('"'), '\\') => word.extend(chars.next()),
+            (Some(_), c) => word.push(c),
+            (None, '\'' | '"') => {
+                quote = Some(c);
+                in_word = true;
+            }
+            (None, '\\') => {
+                word.extend(chars.next());
+                in_word = true;
+            }
+            (None, '|' | ';' | '&') => break,
+            (None, c) if c.is_whitespace() => {
+                if in_word {
+                    words.push(std::mem::take(&mut word));
+                    in_word = false;
+                }
+            }
+            (None, c) => {
+                word.push(c);
+                in_word = true;
+            }
+        }
+    }
+    if in_word {
+        words.push(word);
+    }
+    words
+}
+
+// 与 Claude Code 传给该事件的 JSON 结构相同的示例输入
+pub fn sample_input(event: &str, matcher: &str, cwd: &Path) -> String {
+    let mut input = json!({
+        "session_id": "00000000-0000-0000-0000-000000000000",
+        "transcript_path": cwd.join("transcript.jsonl").to_string_lossy(),
+        "cwd": cwd.to_string_lossy(),
+        "hook_event_name": event,
+    });
+    let tool = matcher.split('|').next().map(str::trim).filter(|m| !m.is_empty() && *m != "*").unwrap_or("Bash");
+    let tool_input = match tool {
+        "Bash" => json!({ "command": "ls -la", "description": "List files" }),
+        "Write" => json!({ "file_path": cwd.join("example.txt").to_string_lossy(), "content": "hello" }),
+        "Edit" | "MultiEdit" => json!({ "file_path": cwd.join("example.txt").to_string_lossy(), "old_string": "hello", "new_string": "world" }),
+        "Read" => json!({ "file_path": cwd.join("example.txt").to_string_lossy() }),
+        _ => json!({}),
+    };
+    let fields = match event {
+        "PreToolUse" => json!({ "tool_name": tool, "tool_input": tool_input }),
+        "PostToolUse" => json!({ "tool_name": tool, "tool_input": tool_input, "tool_response": { "success": true } }),
+        "UserPromptSubmit" => json!({ "prompt": "Write a function to calculate the factorial of a number" }),
+        "Notification" => json!({ "message": "Claude needs your permission to use Bash" }),
+        "Stop" | "SubagentStop" => json!({ "stop_hook_active": false }),
+        "PreCompact" => json!({ "trigger": if matcher.is_empty() { "manual" } else { matcher }, "custom_instructions": "" }),
+        "SessionStart" => json!({ "source": if matcher.is_empty() { "startup" } else { matcher } }),
+        "SessionEnd" => json!({ "reason": "exit" }),
+        _ => json!({}),
+    };
+    if let (Some(input), Value::Object(fields)) = (input.as_object_mut(), fields) {
+        input.extend(fields);
+    }
+    serde_json::to_string_pretty(&input).expect("示例输入可以序列化")
+}
+
+// 终止命令所在的整个进程组，包括它在后台启动的进程
+fn kill(child: &mut Child) {
+    #[cfg(unix)]
+    unsafe {
+        libc::kill(-(child.id() as i32), libc::SIGKILL);
+    }
+    let _ = child.kill();
+}
+
+// 终止进程组后等待读取线程结束的时间；进程脱离了进程组时不再等待
+const KILL_GRACE: Duration = Duration::from_millis(500);
+
+#[derive(Clone, Debug)]
+pub struct DryRun {
+    pub stdout: String,
+    pub stderr: String,
+    // 被信号终止或超时时为 None
+    pub exit_code: Option<i32>,
+    pub timed_out: bool,
+    pub duration: Duration,
+}
+
+// 与 Claude Code 相同，用 shell 运行命令并从标准输入传入 JSON；超时后终止进程
+pub fn dry_run(command: &str, input: &str, timeout: Duration, project_dir: Option<&Path>) -> Result<DryRun> {
+    let mut process = if cfg!(windows) {
+        let mut process = Command::new("cmd");
+        process.arg("/C").arg(command);
+        process
+    } else {
+        let mut process = Command::new("sh");
+        process.arg("-c").arg(command);
+        process
+    };
+    if let Some(dir) = project_dir {
+        process.env(PROJECT_DIR_VAR, dir).current_dir(dir);
+    }
+    // 单独的进程组，超时后连同命令启动的子进程一起终止
+    #[cfg(unix)]
+    std::os::unix::process::CommandExt::process_group(&mut process, 0);
+    let started = Instant::now();
+    let mut child = process
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|source| Error::Spawn { command: command.to_string(), source })?;
+
+    // 输入和输出都在单独的线程中处理，避免管道写满后互相等待
+    let stdin = child.stdin.take().map(|mut stdin| {
+        let input = input.to_string();
+        thread::spawn(move || {
+            let _ = stdin.write_all(input.as_bytes());
+        })
+    });
+    let read = |pipe: Option<Box<dyn Read + Send>>| {
+        let (sender, receiver) = mpsc::channel();
+        thread::spawn(move || {
+            let mut output = Vec::new();
+            if let Some(mut pipe) = pipe {
+                let _ = pipe.read_to_end(&mut output);
+            }
+            let _ = sender.send(String::from_utf8_lossy(&output).to_string());
+        });
+        receiver
+    };
+    let stdout = read(child.stdout.take().map(|p| Box::new(p) as Box<dyn Read + Send>));
+    let stderr = read(child.stderr.take().map(|p| Box::new(p) as Box<dyn Read + Send>));
+
+    let mut timed_out = false;
+    let status = loop {
+        match child.try_wait() {
+            Ok(Some(status)) => break Some(status),
+            Ok(None) if started.elapsed() >= timeout => {
+                timed_out = true;
+                kill(&mut child);
+                break child.wait().ok();
+            }
+            Ok(None) => thread::sleep(Duration::from_millis(20)),
+            Err(_) => break None,
+        }
+    };
+    // shell 退出后，后台进程可能仍占用输出管道；超时仍未结束时按超时处理并终止进程组。
+    // 写入标准输入的线程在管道关闭后自行结束，不等待它
+    drop(stdin);
+    let deadline = started + timeout;
+    let wait = |receiver: &Receiver<String>| receiver.recv_timeout(deadline.saturating_duration_since(Instant::now())).ok();
+    let (mut stdout_text, mut stderr_text) = (wait(&stdout), wait(&stderr));
+    if stdout_text.is_none() || stderr_text.is_none() {
+        timed_out = true;
+        kill(&mut child);
+        stdout_text = stdout_text.or_else(|| stdout.recv_timeout(KILL_GRACE).ok());
+        stderr_text = stderr_text.or_else(|| stderr.recv_timeout(KILL_GRACE).ok());
+    }
+    Ok(DryRun {
+        stdout: stdout_text.unwrap_or_default(),
+        stderr: stderr_text.unwrap_or_default(),
+        exit_code: status.and_then(|s| s.code()).filter(|_| !timed_out),
+        timed_out,
+        duration: started.elapsed(),
+    })
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn splits_first_command_words() {
+        assert_eq!(split_words(r#"jq -r '.tool_input | .command' && echo "a b""#), ["jq", "-r", ".tool_input | .command"]);
+        assert_eq!(split_words(r"run\ me --flag; other"), ["run me", "--flag"]);
+    }
+
+    #[test]
+    fn dry_run_passes_input_on_stdin() {
+        let run = dry_run("cat; echo oops >&2; exit 2", "{\"a\":1}", Duration::from_secs(5), None).unwrap();
+        assert_eq!(run.stdout, "{\"a\":1}");
+        assert_eq!(run.stderr, "oops\n");
+        assert_eq!(run.exit_code, Some(2));
+        assert!(!run.timed_out);
+    }
+
+    #[test]
+    fn dry_run_kills_on_timeout() {
+        let run = dry_run("sleep 5", "", Duration::from_millis(200), None).unwrap();
+        assert!(run.timed_out && run.exit_code.is_none());
+        assert!(run.duration < Duration::from_secs(2));
+    }
+
+    #[test]
+    fn background_process_holding_output_does_not_block() {
+        let run = dry_run("sleep 5 & echo started", "", Duration::from_millis(300), None).unwrap();
+        assert!(run.timed_out);
+        assert_eq!(run.stdout, "started\n");
+        assert!(run.duration < Duration::from_secs(2));
+    }
+}
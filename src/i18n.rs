@@ -0,0 +1,123 @@
+// 界面文本的多语言支持：语言包位于 locales/*.json，编译时嵌入，运行时可随时切换
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt::Display;
+use std::sync::atomic::{AtomicU8, Ordering};
+use std::sync::OnceLock;
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Language {
+    #[serde(rename = "zh-CN")]
+    SimplifiedChinese,
+    #[serde(rename = "en")]
+    English,
+}
+
+impl Language {
+    pub const ALL: [Language; 2] = [Language::SimplifiedChinese, Language::English];
+
+    pub fn code(self) -> &'static str {
+        match self {
+            Language::SimplifiedChinese => "zh-CN",
+            Language::English => "en",
+        }
+    }
+
+    // 语言选择器中始终以该语言本身显示
+    pub fn native_name(self) -> &'static str {
+        match self {
+            Language::SimplifiedChinese => "简体中文",
+            Language::English => "English",
+        }
+    }
+
+    // 解析 zh_CN.UTF-8、en_US、zh-Hans 之类的区域设置
+    pub fn from_locale(locale: &str) -> Option<Self> {
+        let locale = locale.trim().to_lowercase();
+        if locale.is_empty() || locale == "c" || locale == "posix" || locale.starts_with("c.") {
+            return None;
+        }
+        if locale.starts_with("zh") {
+            Some(Language::SimplifiedChinese)
+        } else {
+            Some(Language::English)
+        }
+    }
+
+    // 按 LC_ALL、LC_MESSAGES、LANG 的优先级读取环境中的区域设置
+    pub fn from_env() -> Option<Self> {
+        ["LC_ALL", "LC_MESSAGES", "LANG"]
+            .iter()
+            .filter_map(|name| std::env::var(name).ok())
+            .find_map(|value| Self::from_locale(&value))
+    }
+
+    // 无法从环境判断时使用简体中文
+    pub fn detect() -> Self {
+        Self::from_env().unwrap_or(Language::SimplifiedChinese)
+    }
+
+    fn index(self) -> u8 {
+        match self {
+            Language::SimplifiedChinese => 0,
+            Language::English => 1,
+        }
+    }
+}
+
+impl Default for Language {
+    fn default() -> Self {
+        Self::detect()
+    }
+}
+
+static CURRENT: AtomicU8 = AtomicU8::new(0);
+static CATALOGS: OnceLock<[HashMap<String, String>; 2]> = OnceLock::new();
+
+pub fn set_language(language: Language) {
+    CURRENT.store(language.index(), Ordering::Relaxed);
+}
+
+pub fn language() -> Language {
+    match CURRENT.load(Ordering::Relaxed) {
+        1 => Language::English,
+        _ => Language::SimplifiedChinese,
+    }
+}
+
+fn catalogs() -> &'static [HashMap<String, String>; 2] {
+    CATALOGS.get_or_init(|| {
+        let parse = |content: &str| serde_json::from_str(content).expect("语言包不是有效的 JSON");
+        [parse(include_str!("../locales/zh-CN.json")), parse(include_str!("../locales/en.json"))]
+    })
+}
+
+// 查找当前语言的文本；缺失时依次回退到简体中文和键名本身
+pub fn tr(key: &'static str) -> &'static str {
+    let catalogs = catalogs();
+    catalogs[language().index() as usize]
+        .get(key)
+        .or_else(|| catalogs[0].get(key))
+        .map_or(key, String::as_str)
+}
+
+// 替换文本中的 {name} 占位符
+pub fn tr_args(key: &'static str, args: &[(&str, &dyn Display)]) -> String {
+    let mut text = tr(key).to_string();
+    for (name, value) in args {
+        text = text.replace(&format!("{{{}}}", name), &value.to_string());
+    }
+    text
+}
+
+// t!("key") 返回 &'static str，t!("key", name = value) 返回替换占位符后的 String
+#[macro_export]
+macro_rules! t {
+    ($key:expr) => {
+        $crate::i18n::tr($key)
+    };
+    ($key:expr, $($name:ident = $value:expr),+ $(,)?) => {
+        $crate::i18n::tr_args($key, &[$((stringify!($name), &$value as &dyn std::fmt::Display)),+])
+    };
+}
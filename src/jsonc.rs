@@ -0,0 +1,153 @@
+// 带注释的 JSON（JSONC）：配置文件可以包含 // 和 /* */ 注释以及末尾多余的逗号，
+// 写入 Claude 活动配置前去掉，比较配置时只看解析后的内容
+
+use serde::de::DeserializeOwned;
+
+// 与 serde_json::from_str 相同，但先去掉注释和末尾逗号；错误中的行列号对应原文
+pub fn from_str<T: DeserializeOwned>(content: &str) -> serde_json::Result<T> {
+    serde_json::from_str(&strip(content))
+}
+
+// 把注释和末尾逗号替换为空格，换行保持不变，因此行号和字节偏移都与原文一致
+pub fn strip(content: &str) -> String {
+    strip_trailing_commas(&strip_comments(content))
+}
+
+pub fn has_comments(content: &str) -> bool {
+    strip_comments(content) != content
+}
+
+// 生成标准 JSON：没有注释和末尾逗号时原样返回，否则删除只剩注释的行，其余行保持原有缩进
+pub fn to_json(content: &str) -> serde_json::Result<String> {
+    let stripped = strip(content);
+    serde_json::from_str::<serde::de::IgnoredAny>(&stripped)?;
+    if stripped == content {
+        return Ok(content.to_string());
+    }
+    let lines: Vec<&str> = content
+        .lines()
+        .zip(stripped.lines())
+        .filter(|(original, stripped)| original.trim().is_empty() || !stripped.trim().is_empty())
+        .map(|(_, stripped)| stripped.trim_end())
+        .collect();
+    let mut json = lines.join("\n");
+    if content.ends_with('\n') {
+        json.push('\n');
+    }
+    Ok(json)
+}
+
+fn strip_comments(content: &str) -> String {
+    let mut output = String::with_capacity(content.len());
+    let mut chars = content.chars().peekable();
+    while let Some(ch) = chars.next() {
+        match ch {
+            '"' => {
+                output.push(ch);
+                while let Some(c) = chars.next() {
+                    output.push(c);
+                    match c {
+                        '\\' => output.extend(chars.next()),
+                        '"' => break,
+                        _ => {}
+                    }
+                }
+            }
+            '/' if chars.peek() == Some(&'/') => {
+                output.push(' ');
+                while let Some(&c) = chars.peek() {
+                    if c == '\n' {
+                        break;
+                    }
+                    blank(&mut output, c);
+                    chars.next();
+                }
+            }
+            '/' if chars.peek() == Some(&'*') => {
+                chars.next();
+                output.push_str("  ");
+                let mut previous = '\0';
+                for c in chars.by_ref() {
+                    blank(&mut output, c);
+                    if previous == '*' && c == '/' {
+                        break;
+                    }
+                    previous = c;
+                }
+            }
+            _ => output.push(ch),
+        }
+    }
+    output
+}
+
+// 换行保留，其余字符换成同样字节数的空格
+fn blank(output: &mut String, c: char) {
+    if c == '\n' {
+        output.push('\n');
+    } else {
+        output.extend(std::iter::repeat_n(' ', c.len_utf8()));
+    }
+}
+
+// 输入中已经没有注释
+fn strip_trailing_commas(content: &str) -> String {
+    let chars: Vec<char> = content.chars().collect();
+    let mut output = String::with_capacity(content.len());
+    let mut in_string = false;
+    let mut index = 0;
+    while index < chars.len() {
+        let ch = chars[index];
+        if in_string {
+            output.push(ch);
+            match ch {
+                '\\' => {
+                    index += 1;
+                    output.extend(chars.get(index));
+                }
+                '"' => in_string = false,
+                _ => {}
+            }
+        } else if ch == ',' && matches!(chars[index + 1..].iter().find(|c| !c.is_whitespace()), Some('}' | ']')) {
+            output.push(' ');
+        } else {
+            in_string = ch == '"';
+            output.push(ch);
+        }
+        index += 1;
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::{json, Value};
+
+    #[test]
+    fn strip_keeps_offsets_and_strings() {
+        let content = "{\n  // 说明\n  \"url\": \"http://x//y\", /* a\n b */ \"q\": \"\\\"/*\",\n}";
+        let stripped = strip(content);
+        assert_eq!(stripped.len(), content.len());
+        assert_eq!(stripped.lines().count(), content.lines().count());
+        assert!(stripped.contains("\"http://x//y\""));
+        assert!(has_comments(content));
+        assert!(!has_comments("{\"a\": \"// not a comment\"}"));
+        assert_eq!(from_str::<Value>(content).unwrap(), json!({"url": "http://x//y", "q": "\"/*"}));
+    }
+
+    #[test]
+    fn strip_removes_only_trailing_commas() {
+        assert_eq!(strip("[1, 2, ]"), "[1, 2  ]");
+        assert_eq!(strip("{\"a\": \",}\",\n}"), "{\"a\": \",}\" \n}");
+    }
+
+    #[test]
+    fn to_json_drops_comment_lines_and_keeps_indentation() {
+        let plain = "{\n\t\"a\": 1\n}";
+        assert_eq!(to_json(plain).unwrap(), plain);
+        let content = "{\n  // 说明\n  \"a\": 1, // 行尾\n\n  \"b\": [2,],\n}\n";
+        assert_eq!(to_json(content).unwrap(), "{\n  \"a\": 1,\n\n  \"b\": [2 ]\n}\n");
+        assert!(to_json("{\"a\": // x\n}").is_err());
+    }
+}
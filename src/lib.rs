@@ -0,0 +1,35 @@
+// Claude Code Switcher 核心库：管理配置文件并切换 Claude Code 的活动配置，不依赖图形界面。
+// 图形界面、无界面模式和控制接口共用这里的逻辑，各模块的职责见各自文件开头的说明
+
+pub mod audit;
+pub mod control;
+mod csv;
+pub mod endpoint;
+pub mod env_vars;
+pub mod error;
+mod files;
+pub mod format;
+pub mod hooks;
+pub mod i18n;
+pub mod jsonc;
+pub mod lint;
+pub mod mcp;
+pub mod models;
+pub mod outline;
+pub mod paths;
+pub mod permissions;
+pub mod schedule;
+pub mod settings;
+pub mod store;
+pub mod switcher;
+pub mod target;
+pub mod usage;
+
+pub use error::{Error, Result};
+pub use i18n::Language;
+pub use paths::Paths;
+pub use format::FormatSettings;
+pub use settings::{AppSettings, AppSettingsRepository, AutosaveSettings};
+pub use store::ProfileStore;
+pub use switcher::{SaveOutcome, Switcher, SyncOutcome};
+pub use target::ActiveSettingsTarget;
@@ -0,0 +1,316 @@
+// 配置的语义检查：在 JSON 有效的基础上发现常见错误，并尽量提供一键修复
+
+use crate::env_vars::{self, Issue};
+use crate::error::{Error, Result};
+use crate::format::FormatSettings;
+use crate::jsonc;
+use crate::outline;
+use crate::permissions::{self, Permissions, RuleList};
+use crate::t;
+use serde_json::Value;
+
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub enum Severity {
+    // 阻止应用，除非用户确认忽略
+    Error,
+    Warning,
+}
+
+impl Severity {
+    pub fn label(self) -> &'static str {
+        match self {
+            Severity::Error => t!("lint.severity.error"),
+            Severity::Warning => t!("lint.severity.warning"),
+        }
+    }
+
+    pub fn key(self) -> &'static str {
+        match self {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+        }
+    }
+}
+
+// 修复只作用于 env 中的单个变量
+#[derive(Clone, PartialEq, Debug)]
+pub enum Fix {
+    RemoveEnv(String),
+    SetEnv(String, String),
+    // 旧变量名及新变量名；新变量已存在时只删除旧变量
+    RenameEnv(String, String),
+}
+
+impl Fix {
+    pub fn label(&self) -> String {
+        match self {
+            Fix::RemoveEnv(key) => t!("lint.fix.remove_env", key = key),
+            Fix::SetEnv(key, value) => t!("lint.fix.set_env", key = key, value = value),
+            Fix::RenameEnv(from, to) => t!("lint.fix.rename_env", from = from, to = to),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Finding {
+    pub rule: &'static str,
+    pub severity: Severity,
+    pub message: String,
+    // 从 1 开始的行号
+    pub line: Option<usize>,
+    pub fixes: Vec<Fix>,
+}
+
+impl Finding {
+    fn new(rule: &'static str, severity: Severity, message: String, line: Option<usize>) -> Self {
+        Self { rule, severity, message, line, fixes: Vec::new() }
+    }
+
+    fn with_fix(mut self, fix: Fix) -> Self {
+        self.fixes.push(fix);
+        self
+    }
+}
+
+// 检查配置内容，结果按严重程度和行号排序
+pub fn lint(content: &str) -> Vec<Finding> {
+    // 注释替换为空格后行号不变，规则只需处理标准 JSON
+    let content = &jsonc::strip(content);
+    let mut findings = duplicate_keys(content);
+    match serde_json::from_str::<Value>(content) {
+        Ok(settings) => {
+            findings.extend(check_env(content, &settings));
+            findings.extend(check_permissions(content, &settings));
+        }
+        Err(e) => findings.push(Finding::new("invalid_json", Severity::Error, t!("error.invalid_json", error = e), Some(e.line()))),
+    }
+    findings.sort_by_key(|f| (f.severity, f.line));
+    findings
+}
+
+fn check_env(content: &str, settings: &Value) -> Vec<Finding> {
+    let mut findings = Vec::new();
+    let Some(env) = settings.get("env") else { return findings };
+    let Some(env) = env.as_object() else {
+        findings.push(Finding::new("env_not_object", Severity::Error, t!("lint.env_not_object").to_string(), line_of(content, "\"env\"")));
+        return findings;
+    };
+
+    for (key, value) in env {
+        let line = line_of(content, &format!("\"{}\"", key));
+        match value {
+            // 默认模板中的 sk-ant-XXX 之类的占位符
+            Value::String(text) if text.starts_with("sk-ant-") && text.contains("XXX") => {
+                findings.push(
+                    Finding::new("placeholder_key", Severity::Error, t!("lint.placeholder_key", key = key, value = text), line)
+                        .with_fix(Fix::RemoveEnv(key.clone())),
+                );
+            }
+            Value::String(_) => {}
+            Value::Null => {
+                findings.push(
+                    Finding::new("env_not_string", Severity::Error, t!("lint.env_null", key = key), line).with_fix(Fix::RemoveEnv(key.clone())),
+                );
+            }
+            other => {
+                findings.push(
+                    Finding::new("env_not_string", Severity::Error, t!("lint.env_not_string", key = key), line)
+                        .with_fix(Fix::SetEnv(key.clone(), other.to_string())),
+                );
+            }
+        }
+    }
+
+    // 未知变量可能是给 MCP 服务器或 hooks 用的，只在环境变量面板中提示
+    for issue in env_vars::check(env) {
+        let line = line_of(content, &format!("\"{}\"", issue.name()));
+        match &issue {
+            Issue::Deprecated(name, replacement) => findings.push(
+                Finding::new("deprecated_env", Severity::Warning, issue.message(), line).with_fix(Fix::RenameEnv(name.clone(), replacement.to_string())),
+            ),
+            Issue::Invalid(..) => findings.push(Finding::new("env_value", Severity::Warning, issue.message(), line)),
+            Issue::Unknown(_) => {}
+        }
+    }
+
+    let has = |key: &str| env.get(key).and_then(Value::as_str).is_some_and(|v| !v.trim().is_empty());
+    if has("ANTHROPIC_API_KEY") && has("ANTHROPIC_AUTH_TOKEN") {
+        findings.push(
+            Finding::new("conflicting_credentials", Severity::Warning, t!("lint.conflicting_credentials").to_string(), line_of(content, "\"ANTHROPIC_AUTH_TOKEN\""))
+                .with_fix(Fix::RemoveEnv("ANTHROPIC_API_KEY".to_string()))
+                .with_fix(Fix::RemoveEnv("ANTHROPIC_AUTH_TOKEN".to_string())),
+        );
+    }
+
+    if let Some(url) = env.get("ANTHROPIC_BASE_URL").and_then(Value::as_str) {
+        let key = "ANTHROPIC_BASE_URL".to_string();
+        let line = line_of(content, "\"ANTHROPIC_BASE_URL\"");
+        let trimmed = url.trim();
+        if !trimmed.is_empty() && !trimmed.starts_with("http://") && !trimmed.starts_with("https://") {
+            findings.push(
+                Finding::new("base_url_scheme", Severity::Error, t!("lint.base_url_scheme", url = url), line)
+                    .with_fix(Fix::SetEnv(key.clone(), format!("https://{}", trimmed.trim_start_matches('/')))),
+            );
+        }
+        let without_slash = trimmed.trim_end_matches('/');
+        if let Some(stripped) = without_slash.strip_suffix("/v1") {
+            findings.push(
+                Finding::new("base_url_v1", Severity::Warning, t!("lint.base_url_v1", url = url), line)
+                    .with_fix(Fix::SetEnv(key, stripped.to_string())),
+            );
+        }
+    }
+    findings
+}
+
+fn check_permissions(content: &str, settings: &Value) -> Vec<Finding> {
+    let mut findings = Vec::new();
+    let Some(value) = settings.get(permissions::PROFILE_KEY) else { return findings };
+    let line = line_of(content, "\"permissions\"");
+    if !value.is_object() {
+        findings.push(Finding::new("permissions_not_object", Severity::Error, t!("lint.permissions_not_object").to_string(), line));
+        return findings;
+    }
+    let permissions = Permissions::from_settings(settings);
+    for list in RuleList::ALL {
+        for rule in permissions.list(list) {
+            let rule_line = line_of(content, &Value::from(rule.as_str()).to_string());
+            for (severity, message) in permissions::check_rule(rule) {
+                findings.push(Finding::new("permission_rule", severity, t!("lint.permission_rule", rule = rule, message = message), rule_line));
+            }
+        }
+    }
+    for conflict in permissions.conflicts() {
+        let rule_line = line_of(content, &Value::from(conflict.rule.as_str()).to_string());
+        findings.push(Finding::new("permission_conflict", Severity::Warning, conflict.message(), rule_line));
+    }
+    if let Some(mode) = permissions.default_mode.as_deref().filter(|mode| !permissions::DEFAULT_MODES.contains(mode)) {
+        findings.push(Finding::new("default_mode", Severity::Warning, t!("lint.default_mode", mode = mode), line_of(content, "\"defaultMode\"")));
+    }
+    findings
+}
+
+// 只修改 env 中的对应成员，其余内容、格式和注释保持不变
+pub fn apply_fix(content: &str, fix: &Fix, format: &FormatSettings) -> Result<String> {
+    let settings: Value = jsonc::from_str(content).map_err(Error::InvalidJson)?;
+    match fix {
+        Fix::RemoveEnv(key) => outline::replace_nested_member(content, &["env"], key, None, format),
+        Fix::SetEnv(key, value) => outline::replace_nested_member(content, &["env"], key, Some(&Value::String(value.clone())), format),
+        Fix::RenameEnv(from, to) if settings.get("env").and_then(|env| env.get(to)).is_some() => {
+            outline::replace_nested_member(content, &["env"], from, None, format)
+        }
+        // 保持变量在 env 中的位置
+        Fix::RenameEnv(from, to) => outline::rename_nested_member(content, &["env"], from, to),
+    }
+}
+
+fn line_of(content: &str, needle: &str) -> Option<usize> {
+    content.lines().position(|line| line.contains(needle)).map(|index| index + 1)
+}
+
+// serde_json 遇到重复键时静默保留最后一个，这里逐字扫描找出同一对象中的重复键
+fn duplicate_keys(content: &str) -> Vec<Finding> {
+    // 每层对象已出现的键；数组层为 None
+    let mut stack: Vec<Option<Vec<String>>> = Vec::new();
+    let mut expect_key = false;
+    let mut findings = Vec::new();
+    let mut line = 1;
+    let mut chars = content.chars();
+
+    while let Some(ch) = chars.next() {
+        match ch {
+            '\n' => line += 1,
+            '{' => {
+                stack.push(Some(Vec::new()));
+                expect_key = true;
+            }
+            '[' => {
+                stack.push(None);
+                expect_key = false;
+            }
+            '}' | ']' => {
+                stack.pop();
+                expect_key = false;
+            }
+            ',' => expect_key = matches!(stack.last(), Some(Some(_))),
+            '"' => {
+                let start_line = line;
+                let mut text = String::new();
+                while let Some(c) = chars.next() {
+                    match c {
+                        '\\' => {
+                            if let Some(escaped) = chars.next() {
+                                text.push('\\');
+                                text.push(escaped);
+                            }
+                        }
+                        '"' => break,
+                        '\n' => {
+                            line += 1;
+                            text.push(c);
+                        }
+                        _ => text.push(c),
+                    }
+                }
+                if expect_key {
+                    if let Some(Some(keys)) = stack.last_mut() {
+                        if keys.contains(&text) {
+                            findings.push(Finding::new("duplicate_key", Severity::Warning, t!("lint.duplicate_key", key = text), Some(start_line)));
+                        } else {
+                            keys.push(text);
+                        }
+                    }
+                    expect_key = false;
+                }
+            }
+            _ => {}
+        }
+    }
+    findings
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rules(content: &str) -> Vec<(&'static str, Severity, Option<usize>)> {
+        lint(content).into_iter().map(|f| (f.rule, f.severity, f.line)).collect()
+    }
+
+    #[test]
+    fn lint_reports_errors_first_with_original_lines() {
+        let content = "{\n  // 注释不影响行号\n  \"env\": {\n    \"A\": null,\n    \"ANTHROPIC_BASE_URL\": \"https://x/v1\"\n  },\n  \"model\": \"a\", \"model\": \"b\"\n}";
+        assert_eq!(
+            rules(content),
+            [("env_not_string", Severity::Error, Some(4)), ("base_url_v1", Severity::Warning, Some(5)), ("duplicate_key", Severity::Warning, Some(7))]
+        );
+        assert_eq!(rules("{\n  \"env\": [1]\n}"), [("env_not_object", Severity::Error, Some(2))]);
+        assert_eq!(rules("{\n  \"a\": \n}")[0].0, "invalid_json");
+        assert!(lint("{\"env\": {\"A\": \"1\"}, \"b\": {\"A\": 1}}").is_empty());
+    }
+
+    #[test]
+    fn lint_suggests_fixes() {
+        let findings = lint(r#"{"env": {"ANTHROPIC_BASE_URL": "//api.example.com", "ANTHROPIC_SMALL_FAST_MODEL": "m"}}"#);
+        let fixes: Vec<&Fix> = findings.iter().flat_map(|f| &f.fixes).collect();
+        assert_eq!(
+            fixes,
+            [
+                &Fix::SetEnv("ANTHROPIC_BASE_URL".into(), "https://api.example.com".into()),
+                &Fix::RenameEnv("ANTHROPIC_SMALL_FAST_MODEL".into(), "ANTHROPIC_DEFAULT_HAIKU_MODEL".into()),
+            ]
+        );
+    }
+
+    #[test]
+    fn apply_fix_edits_in_place() {
+        let format = FormatSettings::default();
+        let content = "{\n\t// 注释\n\t\"env\": {\"A\": \"1\", \"B\": null, \"C\": \"3\"},\n\t\"model\": \"x\"\n}";
+        let fix = |fix: Fix| apply_fix(content, &fix, &format).unwrap();
+        assert_eq!(fix(Fix::RemoveEnv("A".into())), content.replace("\"A\": \"1\", ", ""));
+        assert_eq!(fix(Fix::SetEnv("A".into(), "2".into())), content.replace("\"A\": \"1\"", "\"A\": \"2\""));
+        assert_eq!(fix(Fix::RenameEnv("B".into(), "X".into())), content.replace("\"B\"", "\"X\""));
+        assert_eq!(fix(Fix::RenameEnv("A".into(), "C".into())), content.replace("\"A\": \"1\", ", ""));
+        assert_eq!(fix(Fix::RemoveEnv("missing".into())), content);
+    }
+}
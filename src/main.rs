@@ -4,21 +4,41 @@
 // This version is self-contained, stable, and implements all requested features.
 
 use eframe::{egui, App, Frame};
-use serde_json::{from_str, to_string_pretty, Value};
+use serde_json::Value;
 use std::fs;
-use std::path::PathBuf;
-use directories::UserDirs;
-use egui::{Color32, Id, TextEdit, RichText, Layout, Align, SidePanel};
-use std::collections::VecDeque;
+use std::path::{Path, PathBuf};
+use egui::{Color32, TextEdit, RichText, Layout, Align, SidePanel};
+use std::collections::{HashMap, VecDeque};
+use std::sync::mpsc;
 use std::time::{Duration, Instant};
-use serde::{Deserialize, Serialize};
+use chrono::{Datelike, Local, NaiveDateTime, Timelike};
 
-const DEFAULT_CONFIG_DIR_NAME: &str = ".claude";  // Claude AI 配置目录
-const ACTIVE_CONFIG_NAME: &str = "settings.json";
-const APP_SETTINGS_NAME: &str = "app_settings.json";
-const APP_DIR_NAME: &str = ".claude-code-switcher";  // 应用程序目录
+use claude_code_switcher::audit::{self, AuditAction, AuditEntry, AuditResult};
+use claude_code_switcher::control::{self, ControlRequest, ControlServer};
+use claude_code_switcher::endpoint::{ConnectionReport, Endpoint};
+use claude_code_switcher::env_vars::{self, Category, EnvVar, Issue};
+use claude_code_switcher::schedule::{self, Schedule, ScheduleRule, RuleKind, SwitchEvent};
+use claude_code_switcher::store;
+use claude_code_switcher::usage::{self, GroupBy, PriceTable, UsageRecord, UsageRow};
+use claude_code_switcher::hooks::{self, DryRun, HookCommand, HookGroup};
+use claude_code_switcher::i18n::{self, Language};
+use claude_code_switcher::jsonc;
+use claude_code_switcher::format::{IndentStyle, KeyOrder};
+use claude_code_switcher::lint::{self, Finding, Fix, Severity};
+use claude_code_switcher::mcp::{self, McpServer, Scope, Transport};
+use claude_code_switcher::models::{self, ModelInfo, ModelSlot, ProfileCheck};
+use claude_code_switcher::permissions::{self, Permissions, RuleList};
+use claude_code_switcher::outline::{self, Edit, Kind, Node, Segment};
+use claude_code_switcher::target::{ActiveSettingsTarget, ClaudeRoot, Mapping, TargetFormat, TargetStatus, ToolTarget};
+use claude_code_switcher::{t, Error, FormatSettings, Paths, SyncOutcome, Switcher};
 
-const SETTINGS_SUBDIR: &str = "settings";  // 配置文件子目录
+mod command_palette;
+mod highlight;
+mod theme;
+mod workspace;
+use command_palette::{Action, CommandPalette};
+use theme::{Palette, Theme, ThemeColors, ThemeLibrary};
+use workspace::{AutosaveState, Document, Pane, Workspace};
 
 // --- Custom Toast Notification System ---
 #[derive(Clone)]
@@ -32,133 +52,242 @@ struct Toast {
     duration_secs: f32,
 }
 
-// --- 主题颜色定义 ---
-#[derive(Clone, Copy)]
-struct ThemeColors {
-    base: Color32, mantle: Color32, crust: Color32, surface0: Color32, surface1: Color32,
-    text: Color32, lavender: Color32, green: Color32, red: Color32, yellow: Color32,
-    // 新增颜色用于更精致的UI
-    accent: Color32, muted: Color32, border: Color32, highlight: Color32,
+// 可能丢弃未保存修改的操作，先询问用户保存、放弃还是取消
+#[derive(Clone)]
+enum PendingAction {
+    CloseTab(PathBuf),
+    Apply(PathBuf),
+    ChangeDirectory(PathBuf),
+    CloseWindow,
 }
 
-const CLAUDE_DARK: ThemeColors = ThemeColors {
-    base: Color32::from_rgb(18, 18, 23),       // 更亮的深色背景
-    crust: Color32::from_rgb(28, 28, 35),      // 侧边栏背景 - 更亮
-    mantle: Color32::from_rgb(22, 22, 28),     // 编辑器背景 - 更亮
-    surface0: Color32::from_rgb(40, 40, 48),   // 非交互元素 - 更亮
-    surface1: Color32::from_rgb(50, 50, 60),   // 悬停状态 - 更亮
-    text: Color32::from_rgb(248, 250, 252),    // 更亮的白色文字
-    lavender: Color32::from_rgb(167, 139, 250), // 更亮的紫色主色调
-    green: Color32::from_rgb(52, 211, 153),    // 更亮的绿色
-    red: Color32::from_rgb(248, 113, 113),     // 更亮的红色
-    yellow: Color32::from_rgb(251, 191, 36),   // 更亮的黄色
-    accent: Color32::from_rgb(124, 126, 251),  // 更亮的强调色
-    muted: Color32::from_rgb(156, 163, 175),   // 次要文字色
-    border: Color32::from_rgb(75, 85, 99),     // 更亮的边框色
-    highlight: Color32::from_rgb(45, 55, 72),  // 更亮的高亮背景
-};
-
-const CLAUDE_LIGHT: ThemeColors = ThemeColors {
-    base: Color32::from_rgb(255, 255, 255),    // 纯白背景
-    crust: Color32::from_rgb(248, 250, 252),   // 侧边栏背景 - 更亮的浅灰
-    mantle: Color32::from_rgb(255, 255, 255),  // 编辑器背景 - 纯白
-    surface0: Color32::from_rgb(241, 245, 249), // 非交互元素 - 更亮
-    surface1: Color32::from_rgb(226, 232, 240), // 悬停状态 - 更明显
-    text: Color32::from_rgb(15, 23, 42),       // 更深的文字色
-    lavender: Color32::from_rgb(139, 92, 246), // 鲜艳紫色
-    green: Color32::from_rgb(34, 197, 94),     // 鲜艳绿色
-    red: Color32::from_rgb(239, 68, 68),       // 鲜艳红色
-    yellow: Color32::from_rgb(245, 158, 11),   // 鲜艳黄色
-    accent: Color32::from_rgb(99, 102, 241),   // 鲜艳强调色
-    muted: Color32::from_rgb(100, 116, 139),   // 更深的次要文字色
-    border: Color32::from_rgb(203, 213, 225),  // 更明显的边框色
-    highlight: Color32::from_rgb(219, 234, 254), // 更明显的高亮背景
-};
-
-#[derive(Clone, Copy, PartialEq)]
-enum Theme {
-    Dark,
-    Light,
+struct UnsavedGuard {
+    // 有未保存修改的文档
+    paths: Vec<PathBuf>,
+    action: PendingAction,
 }
 
-// --- 应用设置结构 ---
-#[derive(Serialize, Deserialize, Clone)]
-struct AppSettings {
-    config_directory: PathBuf,
-    theme: String,
-    default_config_file: String,
+// MCP 对话框中正在编辑的服务器；参数、环境变量和请求头按行编辑，保存时再解析
+struct McpDraft {
+    server: McpServer,
+    args: String,
+    env: String,
+    headers: String,
 }
 
-impl Default for AppSettings {
-    fn default() -> Self {
-        let default_dir = if let Some(user_dirs) = UserDirs::new() {
-            user_dirs.home_dir().join(APP_DIR_NAME)
-        } else {
-            PathBuf::from(APP_DIR_NAME)
-        };
-
+impl McpDraft {
+    fn new(server: McpServer) -> Self {
         Self {
-            config_directory: default_dir,
-            theme: "Dark".to_string(),
-            default_config_file: String::new(),
+            args: server.args.join("\n"),
+            env: mcp::format_pairs(&server.env, "="),
+            headers: mcp::format_pairs(&server.headers, ": "),
+            server,
         }
     }
+
+    fn to_server(&self) -> McpServer {
+        let mut server = self.server.clone();
+        server.name = server.name.trim().to_string();
+        server.args = self.args.lines().map(str::trim).filter(|line| !line.is_empty()).map(str::to_string).collect();
+        server.env = mcp::parse_pairs(&self.env, '=');
+        server.headers = mcp::parse_pairs(&self.headers, ':');
+        server
+    }
 }
 
+// 获取模型列表的配置及结果
+type ModelFetch = (PathBuf, Result<Vec<ModelInfo>, String>);
+
+// 试运行的（分组, 命令）序号及结果
+type HookRun = ((usize, usize), Result<DryRun, Error>);
+
 struct ConfigManagerApp {
     config_files: Vec<PathBuf>,
-    selected_file: Option<PathBuf>,
-    editor_content: String,
-    config_dir: PathBuf,
+    workspace: Workspace,
+    // 重命名和删除对话框针对的配置
+    action_target: Option<PathBuf>,
+    unsaved_guard: Option<UnsavedGuard>,
+    // 因错误级检查结果被阻止应用的配置，等待用户确认是否强制应用
+    lint_override: Option<(PathBuf, Vec<Finding>)>,
+    show_outline: bool,
+    // 用户已确认关闭窗口，不再拦截关闭请求
+    close_confirmed: bool,
     show_delete_confirmation: bool,
-    char_count: usize,
     toasts: VecDeque<Toast>,
     status_text: String,
     show_rename_dialog: bool,
     new_file_name: String,
     current_theme: Theme,
+    themes: ThemeLibrary,
+    // 配置核心（配置文件、活动配置、应用设置）
+    switcher: Switcher,
     // 设置相关字段
-    app_settings: AppSettings,
-    app_settings_path: PathBuf,
     show_settings_dialog: bool,
     new_config_dir_input: String,
+    // Claude 配置目录，留空表示自动确定
+    claude_dir_input: String,
+    // 同时写入的其他 Claude 配置目录
+    claude_roots_draft: Vec<ClaudeRoot>,
     // 新增字段
-    is_content_modified: bool,
-    original_content: String,
+    // 定时切换相关字段
+    schedule: Schedule,
+    schedule_last_check: NaiveDateTime,
+    next_switch: Option<SwitchEvent>,
+    show_schedule_dialog: bool,
+    schedule_draft: Schedule,
+    // 审计日志中的自动切换记录，最新的在前
+    schedule_log_entries: Vec<AuditEntry>,
+    // MCP 服务器相关字段：对话框编辑的配置及其服务器
+    show_mcp_dialog: bool,
+    mcp_profile: Option<PathBuf>,
+    mcp_drafts: Vec<McpDraft>,
+    // MCP 和 hooks 对话框共用的项目目录
+    project_dir_input: String,
+    // hooks 相关字段：对话框编辑的配置及其分组，试运行的输入和结果按（分组, 命令）序号保存
+    show_hooks_dialog: bool,
+    hooks_profile: Option<PathBuf>,
+    hooks_drafts: Vec<HookGroup>,
+    hooks_input: String,
+    hooks_results: HashMap<(usize, usize), Result<DryRun, String>>,
+    hooks_receiver: Option<mpsc::Receiver<HookRun>>,
+    // 权限相关字段：对话框编辑的配置、规则和测试用的工具调用
+    show_permissions_dialog: bool,
+    permissions_profile: Option<PathBuf>,
+    permissions_draft: Permissions,
+    permissions_test: String,
+    // 模型选择相关字段：对话框编辑的配置、各位置的模型 ID，以及端点的模型列表和全部配置的检查结果
+    show_models_dialog: bool,
+    models_profile: Option<PathBuf>,
+    models_draft: HashMap<ModelSlot, String>,
+    models_base_url: String,
+    models_list: Vec<ModelInfo>,
+    // 获取模型列表失败的原因，此时列表为内置目录
+    models_error: Option<String>,
+    models_receiver: Option<mpsc::Receiver<ModelFetch>>,
+    models_checks: Vec<ProfileCheck>,
+    models_check_receiver: Option<mpsc::Receiver<Vec<ProfileCheck>>>,
+    // 环境变量相关字段：对话框编辑的配置、env 的草稿及打开时的内容（保存时逐项比较）、搜索和新变量名
+    show_env_dialog: bool,
+    env_profile: Option<PathBuf>,
+    env_draft: serde_json::Map<String, Value>,
+    env_original: serde_json::Map<String, Value>,
+    env_filter: String,
+    env_new_name: String,
+    // 其他工具目标相关字段：对话框编辑的目标及其与默认配置的对比结果
+    show_targets_dialog: bool,
+    targets_draft: Vec<ToolTarget>,
+    targets_status: Vec<TargetStatus>,
+    // 审计日志相关字段
+    show_audit_dialog: bool,
+    audit_entries: Vec<AuditEntry>,
+    audit_filter_action: Option<AuditAction>,
+    audit_filter_result: Option<AuditResult>,
+    audit_filter_text: String,
+    audit_query_time: String,
+    // 本地控制接口
+    control_server: Option<ControlServer>,
+    // 用量统计相关字段
+    price_table: PriceTable,
+    show_usage_dialog: bool,
+    usage_records: Vec<UsageRecord>,
+    usage_receiver: Option<mpsc::Receiver<Vec<UsageRecord>>>,
+    usage_rows: Vec<UsageRow>,
+    usage_group_by: GroupBy,
+    usage_range_days: Option<i64>,
+    usage_chart_tokens: bool,
+    show_price_editor: bool,
+    // 命令面板和连接测试
+    command_palette: CommandPalette,
+    connection_receiver: Option<mpsc::Receiver<(String, Result<ConnectionReport, Error>)>>,
 }
 
 impl Default for ConfigManagerApp {
     fn default() -> Self {
-        let app_settings = AppSettings::default();
-        let app_settings_path = if let Some(user_dirs) = UserDirs::new() {
-            user_dirs.home_dir().join(APP_DIR_NAME).join(APP_SETTINGS_NAME)
-        } else {
-            PathBuf::from(APP_DIR_NAME).join(APP_SETTINGS_NAME)
-        };
+        Self::with_paths(Paths::from_env())
+    }
+}
 
+impl ConfigManagerApp {
+    fn with_paths(paths: Paths) -> Self {
         Self {
             config_files: Vec::new(),
-            selected_file: None,
-            editor_content: String::new(),
-            status_text: "欢迎使用 Claude 配置管理器!".to_string(),
-            config_dir: app_settings.config_directory.clone(),
+            workspace: Workspace::default(),
+            action_target: None,
+            unsaved_guard: None,
+            lint_override: None,
+            show_outline: false,
+            close_confirmed: false,
+            status_text: t!("status.welcome").to_string(),
             show_delete_confirmation: false,
-            char_count: 0,
             toasts: VecDeque::new(),
             show_rename_dialog: false,
             new_file_name: String::new(),
             current_theme: Theme::Dark,
-            app_settings,
-            app_settings_path,
+            themes: ThemeLibrary::new(paths.app_dir().join(theme::THEMES_DIR_NAME)),
+            switcher: Switcher::new(paths),
             show_settings_dialog: false,
             new_config_dir_input: String::new(),
-            is_content_modified: false,
-            original_content: String::new(),
+            claude_dir_input: String::new(),
+            claude_roots_draft: Vec::new(),
+            schedule: Schedule::default(),
+            schedule_last_check: Local::now().naive_local(),
+            next_switch: None,
+            show_schedule_dialog: false,
+            schedule_draft: Schedule::default(),
+            schedule_log_entries: Vec::new(),
+            show_mcp_dialog: false,
+            mcp_profile: None,
+            mcp_drafts: Vec::new(),
+            project_dir_input: String::new(),
+            show_hooks_dialog: false,
+            hooks_profile: None,
+            hooks_drafts: Vec::new(),
+            hooks_input: String::new(),
+            hooks_results: HashMap::new(),
+            hooks_receiver: None,
+            show_permissions_dialog: false,
+            permissions_profile: None,
+            permissions_draft: Permissions::default(),
+            permissions_test: String::new(),
+            show_models_dialog: false,
+            models_profile: None,
+            models_draft: HashMap::new(),
+            models_base_url: String::new(),
+            models_list: Vec::new(),
+            models_error: None,
+            models_receiver: None,
+            models_checks: Vec::new(),
+            models_check_receiver: None,
+            show_env_dialog: false,
+            env_profile: None,
+            env_draft: serde_json::Map::new(),
+            env_original: serde_json::Map::new(),
+            env_filter: String::new(),
+            env_new_name: String::new(),
+            show_targets_dialog: false,
+            targets_draft: Vec::new(),
+            targets_status: Vec::new(),
+            show_audit_dialog: false,
+            audit_entries: Vec::new(),
+            audit_filter_action: None,
+            audit_filter_result: None,
+            audit_filter_text: String::new(),
+            audit_query_time: String::new(),
+            control_server: None,
+            price_table: PriceTable::default(),
+            show_usage_dialog: false,
+            usage_records: Vec::new(),
+            usage_receiver: None,
+            usage_rows: Vec::new(),
+            usage_group_by: GroupBy::Profile,
+            usage_range_days: Some(30),
+            usage_chart_tokens: false,
+            show_price_editor: false,
+            command_palette: CommandPalette::default(),
+            connection_receiver: None,
         }
     }
-}
 
-impl ConfigManagerApp {
     fn new(cc: &eframe::CreationContext<'_>) -> Self {
         let mut fonts = egui::FontDefinitions::default();
         fonts.font_data.insert("my_font".to_owned(), egui::FontData::from_static(include_bytes!("font.ttf")));
@@ -168,12 +297,28 @@ impl ConfigManagerApp {
 
         let mut app = Self::default();
         app.load_app_settings();
+        app.poll_themes(&cc.egui_ctx);
         app.update_theme_style(cc);
 
         app.ensure_config_directory();
-        app.new_config_dir_input = app.config_dir.to_string_lossy().to_string();
+        app.new_config_dir_input = app.switcher.settings().config_directory.to_string_lossy().to_string();
+        app.load_claude_dir_input();
         app.refresh_file_list();
         app.sync_with_claude_config();
+        app.load_schedule();
+        let ctx = cc.egui_ctx.clone();
+        app.start_control_server(move || ctx.request_repaint());
+        app
+    }
+
+    // 无界面模式：只运行定时切换规则
+    fn new_headless() -> Self {
+        let mut app = Self::default();
+        app.load_app_settings();
+        app.ensure_config_directory();
+        app.refresh_file_list();
+        app.load_schedule();
+        app.start_control_server(|| {});
         app
     }
 
@@ -184,117 +329,115 @@ impl ConfigManagerApp {
 
     // --- 应用设置相关方法 ---
     fn load_app_settings(&mut self) {
-        if self.app_settings_path.exists() {
-            match fs::read_to_string(&self.app_settings_path) {
-                Ok(content) => {
-                    match from_str::<AppSettings>(&content) {
-                        Ok(settings) => {
-                            self.app_settings = settings;
-                            self.config_dir = self.app_settings.config_directory.clone();
-                            // 根据保存的主题设置更新当前主题
-                            self.current_theme = match self.app_settings.theme.as_str() {
-                                "Light" => Theme::Light,
-                                _ => Theme::Dark,
-                            };
-                        }
-                        Err(e) => {
-                            self.show_toast(format!("解析应用设置时出错: {}", e), ToastKind::Warning);
-                        }
-                    }
-                }
-                Err(_) => {
-                    // 设置文件不存在或无法读取，使用默认设置
-                    self.save_app_settings();
-                }
-            }
-        } else {
-            // 首次运行，保存默认设置
-            self.save_app_settings();
+        if let Err(e) = self.switcher.load_settings() {
+            let kind = if matches!(e, Error::InvalidSettings(_)) { ToastKind::Warning } else { ToastKind::Error };
+            self.show_toast(e.to_string(), kind);
         }
+        i18n::set_language(self.switcher.settings().language);
+        // 根据保存的主题设置更新当前主题
+        self.current_theme = Theme::from_setting(&self.switcher.settings().theme);
     }
 
     fn save_app_settings(&mut self) {
         // 更新设置中的主题
-        self.app_settings.theme = match self.current_theme {
-            Theme::Dark => "Dark".to_string(),
-            Theme::Light => "Light".to_string(),
-        };
-
-        match to_string_pretty(&self.app_settings) {
-            Ok(content) => {
-                if let Err(e) = fs::write(&self.app_settings_path, content) {
-                    self.show_toast(format!("保存应用设置时出错: {}", e), ToastKind::Error);
-                }
-            }
-            Err(e) => {
-                self.show_toast(format!("序列化应用设置时出错: {}", e), ToastKind::Error);
-            }
+        self.switcher.settings_mut().theme = self.current_theme.setting_name();
+        if let Err(e) = self.switcher.save_settings() {
+            self.show_toast(t!("toast.settings_save_failed", error = e), ToastKind::Error);
         }
     }
 
+    // 立即切换界面语言并保存到应用设置
+    fn set_language(&mut self, language: Language) {
+        i18n::set_language(language);
+        self.switcher.settings_mut().language = language;
+        self.save_app_settings();
+    }
+
     fn ensure_config_directory(&mut self) {
-        // 确保主配置目录存在
-        if !self.config_dir.exists() {
-            if let Err(e) = fs::create_dir_all(&self.config_dir) {
-                self.show_toast(format!("创建配置目录时出错: {}", e), ToastKind::Error);
-                return;
-            }
+        if let Err(e) = self.switcher.ensure_layout() {
+            self.show_toast(e.to_string(), ToastKind::Error);
         }
+    }
 
-        // 确保 settings 子目录存在
-        let settings_subdir = self.config_dir.join(SETTINGS_SUBDIR);
-        if !settings_subdir.exists() {
-            if let Err(e) = fs::create_dir_all(&settings_subdir) {
-                self.show_toast(format!("创建 settings 子目录时出错: {}", e), ToastKind::Error);
-                return;
-            }
+    fn change_config_directory(&mut self, new_dir: PathBuf) {
+        let result = self.switcher.change_config_directory(new_dir);
+        self.refresh_file_list();
+        self.workspace.close_all();
+        match result {
+            Ok(()) => self.show_toast(t!("toast.config_dir_changed"), ToastKind::Success),
+            Err(e) => self.show_toast(e.to_string(), ToastKind::Error),
         }
+    }
 
-        // 确保应用设置文件的父目录存在
-        if let Some(parent) = self.app_settings_path.parent() {
-            if !parent.exists() {
-                if let Err(e) = fs::create_dir_all(parent) {
-                    self.show_toast(format!("创建应用设置目录时出错: {}", e), ToastKind::Error);
-                }
+    // 把核心库记录的审计条目和警告转给界面
+    fn poll_core_events(&mut self) {
+        let recorded = self.switcher.take_recorded();
+        // 每次写入或匹配到活动配置都通知订阅者，包括设为默认、保存默认配置和自动同步
+        if let Some(server) = &self.control_server {
+            for entry in recorded.iter().filter(|entry| entry.result == AuditResult::Success && entry.active_settings_hash.is_some()) {
+                server.notify("switched", serde_json::json!({
+                    "profile": entry.profile,
+                    "action": entry.action.key(),
+                    "timestamp": entry.timestamp,
+                }));
             }
         }
-
-        // 创建默认的 settings.json 文件在 settings 子目录中
-        let settings_path = settings_subdir.join(ACTIVE_CONFIG_NAME);
-        if !settings_path.exists() {
-            let default_content = "{\n\t\"env\": {\n\t\t\"ANTHROPIC_API_KEY\": \"sk-ant-XXX\",\n\t\t\"ANTHROPIC_BASE_URL\": \"https://api.anthropic.com\"\n\t}\n}";
-            if let Err(e) = fs::write(&settings_path, default_content) {
-                self.show_toast(format!("创建默认 settings.json 时出错: {}", e), ToastKind::Error);
+        if self.show_schedule_dialog {
+            for entry in recorded.iter().filter(|entry| entry.action == AuditAction::AutomaticSync) {
+                self.schedule_log_entries.insert(0, entry.clone());
             }
         }
-    }
-
-    fn change_config_directory(&mut self, new_dir: PathBuf) {
-        self.config_dir = new_dir.clone();
-        self.app_settings.config_directory = new_dir;
-        self.ensure_config_directory();
-        self.refresh_file_list();
-        self.selected_file = None;
-        self.editor_content = String::new();
-        self.save_app_settings();
-        self.show_toast("配置目录已更改", ToastKind::Success);
+        if self.show_audit_dialog {
+            self.audit_entries.extend(recorded);
+        }
+        for warning in self.switcher.take_warnings() {
+            self.show_toast(warning, ToastKind::Warning);
+        }
     }
     
     fn get_theme_colors(&self) -> ThemeColors {
-        match self.current_theme {
-            Theme::Dark => CLAUDE_DARK,
-            Theme::Light => CLAUDE_LIGHT,
+        self.palette().colors
+    }
+
+    // 自定义主题文件被删除或无法解析时回退到深色主题
+    fn palette(&self) -> Palette {
+        match &self.current_theme {
+            Theme::Dark => Palette::DARK,
+            Theme::Light => Palette::LIGHT,
+            Theme::Custom(name) => self.themes.get(name).copied().unwrap_or(Palette::DARK),
         }
     }
     
     fn toggle_theme(&mut self, ctx: &egui::Context) {
-        self.current_theme = match self.current_theme {
-            Theme::Dark => Theme::Light,
-            Theme::Light => Theme::Dark,
-        };
+        let theme = if self.palette().dark { Theme::Light } else { Theme::Dark };
+        self.set_theme(theme, ctx);
+    }
+
+    fn set_theme(&mut self, theme: Theme, ctx: &egui::Context) {
+        self.current_theme = theme;
         self.update_theme_style_with_ctx(ctx);
         self.save_app_settings(); // 保存主题设置
     }
+
+    // 主题目录有变化时重新加载，当前主题随之更新
+    fn poll_themes(&mut self, ctx: &egui::Context) {
+        let Some(errors) = self.themes.poll() else { return };
+        for error in errors {
+            self.show_toast(t!("toast.theme_load_failed", error = error), ToastKind::Warning);
+        }
+        self.update_theme_style_with_ctx(ctx);
+    }
+
+    fn export_current_theme(&mut self) {
+        let name = match &self.current_theme {
+            Theme::Custom(name) => format!("{}-copy", name),
+            theme => theme.setting_name().to_lowercase(),
+        };
+        match self.themes.export(&name, &self.palette()) {
+            Ok(path) => self.show_toast(t!("toast.theme_exported", path = path.display()), ToastKind::Success),
+            Err(e) => self.show_toast(t!("toast.theme_export_failed", error = e), ToastKind::Error),
+        }
+    }
     
     fn create_custom_style(&self) -> egui::Style {
         let mut style = egui::Style::default();
@@ -360,43 +503,38 @@ impl ConfigManagerApp {
             )
         } else if is_hovered {
             // 悬停状态：颜色稍浅
-            match self.current_theme {
-                Theme::Dark => Color32::from_rgb(
+            if self.palette().dark {
+                Color32::from_rgb(
                     (base_color.r() as f32 * 0.2 + colors.surface1.r() as f32 * 0.8) as u8,
                     (base_color.g() as f32 * 0.2 + colors.surface1.g() as f32 * 0.8) as u8,
                     (base_color.b() as f32 * 0.2 + colors.surface1.b() as f32 * 0.8) as u8,
-                ),
-                Theme::Light => Color32::from_rgb(
+                )
+            } else {
+                Color32::from_rgb(
                     (base_color.r() as f32 * 0.1 + colors.surface1.r() as f32 * 0.9) as u8,
                     (base_color.g() as f32 * 0.1 + colors.surface1.g() as f32 * 0.9) as u8,
                     (base_color.b() as f32 * 0.1 + colors.surface1.b() as f32 * 0.9) as u8,
-                ),
+                )
             }
-        } else {
+        } else if self.palette().dark {
             // 默认状态：透明背景
-            match self.current_theme {
-                Theme::Dark => colors.surface0,
-                Theme::Light => colors.base,
-            }
+            colors.surface0
+        } else {
+            colors.base
         }
     }
 
     fn get_custom_visuals(&self) -> egui::Visuals {
-        let colors = self.get_theme_colors();
-        let mut visuals = match self.current_theme {
-            Theme::Dark => egui::Visuals::dark(),
-            Theme::Light => egui::Visuals::light(),
-        };
+        let palette = self.palette();
+        let colors = palette.colors;
+        let mut visuals = if palette.dark { egui::Visuals::dark() } else { egui::Visuals::light() };
         
         visuals.override_text_color = Some(colors.text);
         visuals.window_rounding = egui::Rounding::same(8.0);
         visuals.window_stroke = egui::Stroke::new(1.0, colors.border);
         visuals.window_shadow = egui::epaint::Shadow {
             extrusion: 16.0,
-            color: match self.current_theme {
-                Theme::Dark => Color32::from_black_alpha(100),
-                Theme::Light => Color32::from_black_alpha(25),
-            },
+            color: if palette.dark { Color32::from_black_alpha(100) } else { Color32::from_black_alpha(25) },
         };
         
         let rounding = egui::Rounding::same(6.0);
@@ -418,10 +556,7 @@ impl ConfigManagerApp {
         
         // 悬停状态 - 更明显的效果
         visuals.widgets.hovered = egui::style::WidgetVisuals {
-            bg_fill: match self.current_theme {
-                Theme::Dark => Color32::from_rgb(60, 60, 70),  // 更亮的悬停背景
-                Theme::Light => Color32::from_rgb(219, 234, 254), // 更明显的浅色悬停
-            },
+            bg_fill: palette.hover,
             weak_bg_fill: colors.surface1,
             bg_stroke: egui::Stroke::new(2.0, colors.accent),
             fg_stroke: egui::Stroke::new(1.0, colors.text),
@@ -456,35 +591,11 @@ impl ConfigManagerApp {
 
 
     fn refresh_file_list(&mut self) {
-        self.refresh_file_list_with_sort(true);
-    }
-
-    fn refresh_file_list_with_sort(&mut self, should_sort: bool) {
-        self.config_files.clear();
-        let settings_subdir = self.config_dir.join(SETTINGS_SUBDIR);
-        
-        if settings_subdir.exists() {
-            if let Ok(entries) = fs::read_dir(&settings_subdir) {
-                self.config_files = entries
-                    .filter_map(Result::ok)
-                    .map(|e| e.path())
-                    .filter(|p| {
-                        p.is_file() &&
-                        p.extension().map_or(false, |ext| ext == "json") &&
-                        p.file_name().and_then(|s| s.to_str()).unwrap_or_default() != ACTIVE_CONFIG_NAME
-                    })
-                    .collect();
-
-                if should_sort {
-                    self.config_files.sort_by(|a, b| {
-                        let a_name = a.file_name().and_then(|s| s.to_str()).unwrap_or_default();
-                        let b_name = b.file_name().and_then(|s| s.to_str()).unwrap_or_default();
-                        a_name.cmp(b_name)
-                    });
-                }
-
-            } else {
-                self.show_toast("无法读取配置目录。", ToastKind::Error);
+        match self.switcher.list_profiles() {
+            Ok(files) => self.config_files = files,
+            Err(e) => {
+                self.config_files.clear();
+                self.show_toast(e.to_string(), ToastKind::Error);
             }
         }
     }
@@ -497,7 +608,7 @@ impl ConfigManagerApp {
             .collect();
         
         // 先获取所有文件
-        self.refresh_file_list_with_sort(false);
+        self.refresh_file_list();
         
         // 如果有之前的顺序，尝试保持
         if !current_order.is_empty() {
@@ -520,308 +631,2930 @@ impl ConfigManagerApp {
         }
     }
 
-    fn sync_with_claude_config(&mut self) {
-        // 获取 Claude 配置文件路径
-        let claude_config_dir = if let Some(user_dirs) = UserDirs::new() {
-            user_dirs.home_dir().join(DEFAULT_CONFIG_DIR_NAME)
-        } else {
-            PathBuf::from(DEFAULT_CONFIG_DIR_NAME)
-        };
+    fn load_claude_dir_input(&mut self) {
+        self.claude_dir_input = self.switcher.settings().claude_config_dir.as_ref().map(|d| d.to_string_lossy().to_string()).unwrap_or_default();
+        self.claude_roots_draft = self.switcher.settings().claude_roots.clone();
+    }
+
+    fn apply_claude_roots(&mut self) {
+        let roots: Vec<ClaudeRoot> = self.claude_roots_draft.iter().filter(|root| !root.dir.as_os_str().is_empty()).cloned().collect();
+        if roots != self.switcher.settings().claude_roots {
+            if let Err(e) = self.switcher.set_claude_roots(roots) {
+                self.show_toast(t!("toast.settings_save_failed", error = e), ToastKind::Error);
+            }
+        }
+        self.claude_roots_draft = self.switcher.settings().claude_roots.clone();
+    }
 
-        let claude_settings_path = claude_config_dir.join(ACTIVE_CONFIG_NAME);
+    // 写入了多个 Claude 配置目录时逐个列出结果
+    fn root_report_text(&self) -> Option<String> {
+        let reports = self.switcher.root_reports();
+        (reports.len() > 1).then(|| {
+            reports.iter().map(|report| format!("{} {}: {}", if report.status.is_ok() { "✔" } else { "✖" }, report.name, report.status.label())).collect::<Vec<_>>().join("\n")
+        })
+    }
 
-        // 如果 Claude 配置文件不存在，跳过同步
-        if !claude_settings_path.exists() {
+    // 活动配置换了位置，重新与新位置的活动配置对比
+    fn apply_claude_dir(&mut self) {
+        let input = self.claude_dir_input.trim();
+        let dir = (!input.is_empty()).then(|| PathBuf::from(input));
+        if dir == self.switcher.settings().claude_config_dir {
+            return;
+        }
+        if let Err(e) = self.switcher.set_claude_config_dir(dir) {
+            self.show_toast(t!("toast.settings_save_failed", error = e), ToastKind::Error);
+            self.load_claude_dir_input();
             return;
         }
+        self.load_claude_dir_input();
+        let path = self.switcher.target().path();
+        self.show_toast(t!("toast.claude_dir_changed", path = path.display()), ToastKind::Success);
+        self.sync_with_claude_config();
+    }
 
-        // 读取 Claude 配置文件内容
-        let claude_content = match fs::read_to_string(&claude_settings_path) {
-            Ok(content) => content,
-            Err(_) => return,
-        };
+    fn sync_with_claude_config(&mut self) {
+        match self.switcher.sync_with_active() {
+            Ok(SyncOutcome::Skipped) => {}
+            Ok(SyncOutcome::Matched { .. }) => {
+                self.show_toast(t!("toast.sync_matched"), ToastKind::Success);
+            }
+            Ok(SyncOutcome::Created(name)) => {
+                self.refresh_file_list();
+                self.show_toast(t!("toast.sync_created", name = name), ToastKind::Success);
+            }
+            Err(e) => self.show_toast(t!("toast.sync_create_failed", error = e), ToastKind::Error),
+        }
+    }
 
-        // 解析 Claude 配置为 JSON
-        let claude_json: Value = match from_str(&claude_content) {
-            Ok(json) => json,
-            Err(_) => return,
-        };
+    // 已打开的配置直接切换到对应标签页，否则读取文件并新建标签页
+    fn open_file(&mut self, path: PathBuf) {
+        if self.workspace.activate(&path) {
+            return;
+        }
+        match fs::read_to_string(&path) {
+            Ok(content) => {
+                self.set_status(t!("status.loaded", name = path.file_name().unwrap_or_default().to_string_lossy()));
+                self.workspace.open(path, content);
+            }
+            Err(e) => self.show_toast(t!("toast.read_failed", error = e), ToastKind::Error),
+        }
+    }
 
-        // 检查是否有配置文件与 Claude 配置相同
-        let mut found_matching_config = false;
-        for file_path in &self.config_files {
-            if let Ok(content) = fs::read_to_string(file_path) {
-                if let Ok(json_val) = from_str::<Value>(&content) {
-                    if json_val == claude_json {
-                        // 找到匹配的配置，设为默认
-                        let file_name = file_path.file_name().unwrap().to_str().unwrap().to_string();
-                        self.app_settings.default_config_file = file_name;
-                        self.save_app_settings();
-                        found_matching_config = true;
-                        self.show_toast("已找到与 Claude 配置匹配的文件并设为默认", ToastKind::Success);
-                        break;
-                    }
+    fn save_current_file(&mut self) {
+        if let Some(path) = self.workspace.active_path().map(PathBuf::from) {
+            self.save_document(&path);
+        }
+    }
+
+    // 保存指定标签页的内容，返回是否成功
+    fn save_document(&mut self, path: &Path) -> bool {
+        let Some(document) = self.workspace.find(path) else { return false };
+        let file_name = document.name();
+        let content = document.content.clone();
+        match self.switcher.save_profile(&file_name, &content) {
+            Ok(outcome) => {
+                self.show_toast(t!("toast.saved", name = file_name), ToastKind::Success);
+                if let Some(document) = self.workspace.find_mut(path) {
+                    document.content = outcome.content.clone();
+                    document.original = outcome.content;
+                    document.last_edit = None;
+                    document.autosave = AutosaveState::Idle;
+                    document.relint();
+                }
+
+                // 如果保存的是默认配置文件，同时更新到 Claude 配置文件
+                match outcome.synced {
+                    Some(Ok(())) => self.show_toast(t!("toast.synced_to_claude"), ToastKind::Success),
+                    // 有错误级检查结果时与应用一样先确认
+                    Some(Err(Error::LintFailed { .. })) => self.lint_override = Some((path.to_path_buf(), lint::lint(&content))),
+                    Some(Err(e)) => self.show_toast(t!("toast.sync_to_claude_failed", error = e), ToastKind::Error),
+                    None => {}
                 }
+                true
+            }
+            Err(e) => {
+                self.show_toast(e.to_string(), ToastKind::Error);
+                false
             }
         }
+    }
 
-        // 如果没有找到匹配的配置，创建新的配置文件
-        if !found_matching_config {
-            let settings_subdir = self.config_dir.join(SETTINGS_SUBDIR);
-            let mut new_path = settings_subdir.join("Claude默认配置.json");
-            let mut i = 1;
-            while new_path.exists() {
-                new_path = settings_subdir.join(format!("Claude默认配置_{}.json", i));
-                i += 1;
+    // 没有输入时不再重绘；后台任务、自动保存和提示框按需要的时间唤醒，
+    // 其余情况每秒一次，用于主题热重载和定时切换
+    fn schedule_repaint(&self, ctx: &egui::Context) {
+        let mut wait = Duration::from_secs(1);
+        let models_pending = self.models_receiver.is_some() || self.models_check_receiver.is_some();
+        if self.usage_receiver.is_some() || self.connection_receiver.is_some() || self.hooks_receiver.is_some() || models_pending {
+            wait = Duration::from_millis(100);
+        }
+        let autosave = &self.switcher.settings().autosave;
+        if autosave.enabled {
+            let delay = Duration::from_secs(autosave.delay_secs);
+            for document in self.workspace.documents().iter().filter(|d| d.is_modified()) {
+                if let Some(edited) = document.last_edit {
+                    wait = wait.min(delay.saturating_sub(edited.elapsed()));
+                }
             }
+        }
+        for toast in &self.toasts {
+            let remaining = toast.duration_secs - toast.spawn_time.elapsed().as_secs_f32();
+            wait = wait.min(Duration::from_secs_f32(remaining.max(0.0)));
+        }
+        ctx.request_repaint_after(wait);
+    }
 
-            // 创建新配置文件，内容为 Claude 的配置
-            match fs::write(&new_path, &claude_content) {
-                Ok(_) => {
-                    let file_name = new_path.file_name().unwrap().to_str().unwrap().to_string();
-                    self.app_settings.default_config_file = file_name.clone();
-                    self.save_app_settings();
-                    self.refresh_file_list();
-                    self.show_toast(format!("已创建新配置文件 '{}' 并设为默认", file_name), ToastKind::Success);
-                }
-                Err(e) => {
-                    self.show_toast(format!("创建配置文件时出错: {}", e), ToastKind::Error);
+    // 停止输入超过设定时间且内容为有效 JSON 的标签页自动保存
+    fn tick_autosave(&mut self) {
+        let settings = self.switcher.settings().autosave.clone();
+        if !settings.enabled {
+            return;
+        }
+        let delay = Duration::from_secs(settings.delay_secs);
+        let due: Vec<PathBuf> = self
+            .workspace
+            .documents()
+            .iter()
+            .filter(|d| d.is_modified() && d.last_edit.is_some_and(|t| t.elapsed() >= delay))
+            .map(|d| d.path.clone())
+            .collect();
+        for path in due {
+            self.autosave_document(&path);
+        }
+    }
+
+    fn autosave_document(&mut self, path: &Path) {
+        let Some(document) = self.workspace.find_mut(path) else { return };
+        document.last_edit = None;
+        if jsonc::from_str::<Value>(&document.content).is_err() {
+            document.autosave = AutosaveState::InvalidJson;
+            return;
+        }
+        let (name, content) = (document.name(), document.content.clone());
+        let result = self.switcher.autosave_profile(&name, &content);
+        let Some(document) = self.workspace.find_mut(path) else { return };
+        match result {
+            Ok(outcome) => {
+                document.original = outcome.content;
+                document.autosave = AutosaveState::Saved(Local::now());
+                document.relint();
+                match outcome.synced {
+                    Some(Ok(())) => self.set_status(t!("status.autosaved_synced", name = name)),
+                    // 自动保存不弹出确认，手动保存时再确认是否强制应用
+                    Some(Err(Error::LintFailed { count, .. })) => {
+                        self.show_toast(t!("toast.autosave_not_synced", name = name, count = count), ToastKind::Warning)
+                    }
+                    Some(Err(e)) => self.show_toast(t!("toast.sync_to_claude_failed", error = e), ToastKind::Error),
+                    None => self.set_status(t!("status.autosaved", name = name)),
                 }
             }
+            Err(e) => {
+                document.autosave = AutosaveState::Failed;
+                self.show_toast(t!("toast.autosave_failed", name = name, error = e), ToastKind::Error);
+            }
         }
     }
 
-    fn load_file_content(&mut self) {
-        if let Some(path) = &self.selected_file {
-            match fs::read_to_string(path) {
-                Ok(content) => {
-                    self.editor_content = content.clone();
-                    self.original_content = content;
-                    self.is_content_modified = false;
-                    self.set_status(&format!("已加载 {}", path.file_name().unwrap_or_default().to_str().unwrap_or_default()));
-                },
-                Err(e) => {
-                    self.show_toast(format!("读取文件时出错: {}", e), ToastKind::Error);
-                    self.editor_content = String::new();
-                    self.original_content = String::new();
-                    self.is_content_modified = false;
+    // 编辑器标题栏中显示的保存状态
+    fn save_state_text(&self, document: &Document) -> Option<(String, Color32)> {
+        let colors = self.get_theme_colors();
+        if !self.switcher.settings().autosave.enabled {
+            return None;
+        }
+        match &document.autosave {
+            AutosaveState::InvalidJson if document.is_modified() => Some((t!("autosave.invalid_json").to_string(), colors.yellow)),
+            AutosaveState::Failed if document.is_modified() => Some((t!("autosave.failed").to_string(), colors.red)),
+            _ if document.is_modified() => Some((t!("autosave.pending").to_string(), colors.muted)),
+            AutosaveState::Saved(time) => Some((t!("autosave.saved", time = time.format("%H:%M:%S")), colors.green)),
+            _ => None,
+        }
+    }
+
+    // 涉及的文档有未保存修改时先弹出确认框，否则直接执行
+    fn guard_unsaved(&mut self, paths: Vec<PathBuf>, action: PendingAction, ctx: &egui::Context) {
+        let mut paths: Vec<PathBuf> = paths
+            .into_iter()
+            .filter(|path| self.workspace.find(path).is_some_and(|d| d.is_modified()))
+            .collect();
+        paths.dedup();
+        if paths.is_empty() {
+            self.run_pending_action(action, ctx);
+        } else {
+            self.unsaved_guard = Some(UnsavedGuard { paths, action });
+        }
+    }
+
+    fn run_pending_action(&mut self, action: PendingAction, ctx: &egui::Context) {
+        match action {
+            PendingAction::CloseTab(path) => self.workspace.close(&path),
+            PendingAction::Apply(path) => {
+                if let Err(Error::LintFailed { name, .. }) = self.set_as_default(path.clone(), false) {
+                    let findings = self.switcher.store().read(&name).map(|content| lint::lint(&content)).unwrap_or_default();
+                    self.lint_override = Some((path, findings));
                 }
             }
-        } else {
-            self.editor_content = String::new();
-            self.original_content = String::new();
-            self.is_content_modified = false;
+            PendingAction::ChangeDirectory(path) => self.change_config_directory(path),
+            PendingAction::CloseWindow => {
+                self.close_confirmed = true;
+                ctx.send_viewport_cmd(egui::ViewportCommand::Close);
+            }
         }
     }
 
-    fn save_current_file(&mut self) {
-        if let Some(path) = &self.selected_file.clone() {
-            match from_str::<Value>(&self.editor_content) {
-                Ok(json_val) => {
-                    let pretty_content = to_string_pretty(&json_val).unwrap_or_else(|_| self.editor_content.clone());
-                    match fs::write(path, &pretty_content) {
-                        Ok(_) => {
-                            let file_name = path.file_name().unwrap().to_str().unwrap().to_string();
-                            self.show_toast(format!("成功保存 {}", file_name), ToastKind::Success);
-                            self.editor_content = pretty_content.clone();
-                            self.original_content = pretty_content.clone();
-                            self.is_content_modified = false;
-                            
-                            // 如果保存的是默认配置文件，同时更新到 Claude 配置文件
-                            if self.app_settings.default_config_file == file_name {
-                                let claude_config_dir = if let Some(user_dirs) = UserDirs::new() {
-                                    user_dirs.home_dir().join(DEFAULT_CONFIG_DIR_NAME)
-                                } else {
-                                    PathBuf::from(DEFAULT_CONFIG_DIR_NAME)
-                                };
-                                
-                                // 确保 Claude 配置目录存在
-                                if !claude_config_dir.exists() {
-                                    if let Err(e) = fs::create_dir_all(&claude_config_dir) {
-                                        self.show_toast(format!("创建 Claude 配置目录时出错: {}", e), ToastKind::Error);
-                                        return;
-                                    }
-                                }
-                                
-                                let claude_settings_path = claude_config_dir.join(ACTIVE_CONFIG_NAME);
-                                
-                                // 将内容写入 Claude 配置文件
-                                match fs::write(&claude_settings_path, &pretty_content) {
-                                    Ok(_) => {
-                                        self.show_toast("已同步更新到 Claude 配置文件", ToastKind::Success);
-                                    }
-                                    Err(e) => {
-                                        self.show_toast(format!("同步到 Claude 配置文件时出错: {}", e), ToastKind::Error);
-                                    }
-                                }
-                            }
+    fn show_unsaved_window(&mut self, ctx: &egui::Context) {
+        let colors = self.get_theme_colors();
+        let Some(guard) = &self.unsaved_guard else { return };
+        let names: Vec<String> = guard.paths.iter().map(|p| p.file_name().unwrap_or_default().to_string_lossy().to_string()).collect();
+        let mut choice = None;
+        egui::Window::new(t!("unsaved.title"))
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+            .show(ctx, |ui| {
+                ui.label(t!("unsaved.prompt"));
+                ui.add_space(5.0);
+                for name in &names {
+                    ui.label(RichText::new(format!("● {}", name)).color(self.get_button_color("warning")));
+                }
+                ui.add_space(10.0);
+                ui.horizontal(|ui| {
+                    if ui.button(RichText::new(t!("unsaved.save")).color(self.get_button_color("save"))).clicked() {
+                        choice = Some(true);
+                    }
+                    if ui.button(RichText::new(t!("unsaved.discard")).color(colors.red)).clicked() {
+                        choice = Some(false);
+                    }
+                    if ui.button(RichText::new(t!("button.cancel")).color(self.get_button_color("secondary"))).clicked() {
+                        if let Some(UnsavedGuard { action: PendingAction::ChangeDirectory(_), .. }) = self.unsaved_guard.take() {
+                            self.new_config_dir_input = self.switcher.settings().config_directory.to_string_lossy().to_string();
                         }
-                        Err(e) => self.show_toast(format!("保存文件时出错: {}", e), ToastKind::Error),
                     }
+                });
+            });
+
+        let Some(save) = choice else { return };
+        let Some(mut guard) = self.unsaved_guard.take() else { return };
+        while let Some(path) = guard.paths.first().cloned() {
+            if save {
+                // 保存失败时保留提示和尚未保存的文档，用户可以重试或放弃修改，错误已通过提示显示
+                if !self.save_document(&path) {
+                    self.unsaved_guard = Some(guard);
+                    return;
                 }
-                Err(_e) => self.show_toast("JSON 格式无效.", ToastKind::Error),
+            } else {
+                self.workspace.revert(&path);
             }
+            guard.paths.remove(0);
         }
+        self.run_pending_action(guard.action, ctx);
     }
 
     fn format_json(&mut self) {
-        if self.editor_content.trim().is_empty() {
-            self.show_toast("编辑器内容为空", ToastKind::Warning);
+        let Some(document) = self.workspace.active_mut() else { return };
+        if document.content.trim().is_empty() {
+            self.show_toast(t!("toast.editor_empty"), ToastKind::Warning);
             return;
         }
 
-        match from_str::<Value>(&self.editor_content) {
-            Ok(json_val) => {
-                match to_string_pretty(&json_val) {
-                    Ok(formatted) => {
-                        self.editor_content = formatted;
-                        self.show_toast("JSON 格式化成功", ToastKind::Success);
-                    }
-                    Err(e) => {
-                        self.show_toast(format!("格式化失败: {}", e), ToastKind::Error);
-                    }
-                }
-            }
-            Err(e) => {
-                self.show_toast(format!("JSON 格式无效: {}", e), ToastKind::Error);
+        // 按设置中的缩进、键顺序和末尾换行输出
+        match self.switcher.settings().format.format(&document.content) {
+            Ok(formatted) => {
+                document.content = formatted;
+                document.mark_edited();
+                self.show_toast(t!("toast.formatted"), ToastKind::Success);
             }
+            Err(e) => self.show_toast(e.to_string(), ToastKind::Error),
         }
     }
 
     fn add_new_config(&mut self) {
-        let settings_subdir = self.config_dir.join(SETTINGS_SUBDIR);
-        let mut new_path = settings_subdir.join("新配置.json");
-        let mut i = 1;
-        while new_path.exists() {
-            new_path = settings_subdir.join(format!("新配置_{}.json", i));
-            i += 1;
-        }
-
-        match fs::write(&new_path, "{\n\t\"env\": {\n\t\t\"ANTHROPIC_API_KEY\": \"sk-ant-XXX\",\n\t\t\"ANTHROPIC_BASE_URL\": \"https://api.anthropic.com\"\n\t}\n}") {
-            Ok(_) => {
-                let file_name = new_path.file_name().unwrap().to_str().unwrap().to_string();
-                self.show_toast(format!("已创建新文件: {}", file_name), ToastKind::Success);
+        match self.switcher.create_profile() {
+            Ok(file_name) => {
+                self.show_toast(t!("toast.created", name = file_name), ToastKind::Success);
                 self.refresh_file_list();
-                self.selected_file = Some(new_path);
-                self.load_file_content();
+                self.open_file(self.switcher.store().path(&file_name));
             }
-            Err(e) => self.show_toast(format!("创建新文件时出错: {}", e), ToastKind::Error),
+            Err(e) => self.show_toast(t!("toast.create_failed", error = e), ToastKind::Error),
         }
     }
 
     fn delete_selected_file(&mut self) {
-        if let Some(path) = self.selected_file.clone() {
+        if let Some(path) = self.action_target.take() {
             let file_name = path.file_name().unwrap().to_str().unwrap();
-            match fs::remove_file(&path) {
-                Ok(_) => {
-                    // 如果删除的是默认配置文件，清除默认设置
-                    if self.app_settings.default_config_file == file_name {
-                        self.app_settings.default_config_file = String::new();
-                        self.save_app_settings();
-                    }
-                    self.show_toast(format!("已删除 {}", file_name), ToastKind::Success);
-                    self.selected_file = None;
-                    self.editor_content = String::new();
+            match self.switcher.delete_profile(file_name) {
+                Ok(()) => {
+                    self.show_toast(t!("toast.deleted", name = file_name), ToastKind::Success);
+                    self.workspace.close(&path);
                     self.refresh_file_list();
                 }
-                Err(e) => self.show_toast(format!("删除文件时出错: {}", e), ToastKind::Error),
+                Err(e) => self.show_toast(e.to_string(), ToastKind::Error),
             }
         }
     }
 
     fn rename_selected_file(&mut self) {
-        if let Some(selected_path) = self.selected_file.clone() {
+        if let Some(selected_path) = self.action_target.clone() {
             let old_file_name = selected_path.file_name().unwrap().to_str().unwrap();
-            let mut new_name = self.new_file_name.trim().to_string();
-            if !new_name.ends_with(".json") { new_name.push_str(".json"); }
-            if new_name.is_empty() || new_name == ".json" { self.show_toast("文件名不能为空.", ToastKind::Error); return; }
-
-            // 新路径需要在同一个 settings 子目录中
-            let new_path = selected_path.parent().unwrap().join(&new_name);
-            if new_path.exists() { self.show_toast("文件名已存在.", ToastKind::Error); return; }
-            match fs::rename(&selected_path, &new_path) {
-                Ok(_) => {
-                    // 如果重命名的是默认配置文件，更新默认设置
-                    if self.app_settings.default_config_file == old_file_name {
-                        self.app_settings.default_config_file = new_name.clone();
-                        self.save_app_settings();
-                    }
-                    self.show_toast(format!("文件已重命名为 \"{}\"", new_name), ToastKind::Success);
-                    self.selected_file = Some(new_path);
+            let new_file_name = self.new_file_name.clone();
+            match self.switcher.rename_profile(old_file_name, &new_file_name) {
+                Ok(new_name) => {
+                    // 同步更新定时规则中引用的文件名
+                    if self.schedule.rename_profile(old_file_name, &new_name) {
+                        self.save_schedule();
+                    }
+                    self.show_toast(t!("toast.renamed", name = new_name), ToastKind::Success);
+                    let new_path = self.switcher.store().path(&new_name);
+                    self.workspace.rename(&selected_path, new_path.clone());
+                    self.action_target = Some(new_path);
                     self.show_rename_dialog = false;
                     self.refresh_file_list_preserve_order();
                 }
-                Err(e) => self.show_toast(format!("重命名文件时出错: {}", e), ToastKind::Error),
+                Err(e) => self.show_toast(e.to_string(), ToastKind::Error),
             }
         }
     }
 
+    // force 为 true 时忽略错误级检查结果
+    fn set_as_default(&mut self, file_path: PathBuf, force: bool) -> Result<(), Error> {
+        let file_name = file_path.file_name().unwrap().to_str().unwrap().to_string();
+        let result = if force { self.switcher.force_apply(&file_name) } else { self.switcher.apply(&file_name) };
+        self.show_apply_result(file_name, result)
+    }
 
+    // 应用配置后提示写入结果并刷新文件列表
+    fn show_apply_result(&mut self, file_name: String, result: Result<String, Error>) -> Result<(), Error> {
+        match result {
+            Ok(_) => {
+                let reports = self.switcher.target_reports();
+                let mut text = if reports.is_empty() {
+                    t!("toast.applied", name = file_name)
+                } else {
+                    let ok = reports.iter().filter(|report| report.result.is_ok()).count();
+                    t!("toast.applied_with_targets", name = file_name, ok = ok, total = reports.len())
+                };
+                if let Some(roots) = self.root_report_text() {
+                    text = format!("{}\n{}", text, roots);
+                }
+                self.show_toast(text, ToastKind::Success);
+                self.refresh_file_list();
+                Ok(())
+            }
+            Err(e) => {
+                let text = match self.root_report_text() {
+                    Some(roots) => format!("{}\n{}", t!("toast.roots_rolled_back", error = e), roots),
+                    None => e.to_string(),
+                };
+                self.show_toast(text, ToastKind::Error);
+                Err(e)
+            }
+        }
+    }
 
-    fn set_as_default(&mut self, file_path: PathBuf) {
-        let file_name = file_path.file_name().unwrap().to_str().unwrap().to_string();
-        
-        // 更新应用设置中的默认配置文件
-        self.app_settings.default_config_file = file_name.clone();
-        self.save_app_settings();
-        
-        // 将配置文件内容复制到 Claude 配置文件
-        let claude_config_dir = if let Some(user_dirs) = UserDirs::new() {
-            user_dirs.home_dir().join(DEFAULT_CONFIG_DIR_NAME)
+    // 侧边栏按钮和命令面板的操作都在这里执行
+    fn perform_action(&mut self, action: Action, ctx: &egui::Context) {
+        match action {
+            Action::Apply(path) => {
+                // 应用会读取磁盘上的内容，先处理目标配置和当前配置中未保存的修改
+                let mut dirty = vec![path.clone()];
+                dirty.extend(self.workspace.active_path().map(PathBuf::from));
+                self.guard_unsaved(dirty, PendingAction::Apply(path), ctx);
+            }
+            Action::Open(path) => self.open_file(path),
+            Action::Rename(path) => {
+                self.new_file_name = path.file_name().unwrap().to_str().unwrap().to_string();
+                self.action_target = Some(path);
+                self.show_rename_dialog = true;
+            }
+            Action::Delete(path) => {
+                self.action_target = Some(path);
+                self.show_delete_confirmation = true;
+            }
+            Action::Format(path) => {
+                self.open_file(path);
+                self.format_json();
+            }
+            Action::TestConnection(path) => self.test_connection(path),
+            Action::ToggleTheme => self.toggle_theme(ctx),
+            Action::OpenSettings => self.show_settings_dialog = true,
+        }
+    }
+
+    // 在后台线程请求配置中的端点，已打开的配置使用编辑器中的内容
+    fn test_connection(&mut self, path: PathBuf) {
+        let file_name = path.file_name().unwrap().to_str().unwrap().to_string();
+        let content = if let Some(document) = self.workspace.find(&path) {
+            Ok(document.content.clone())
         } else {
-            PathBuf::from(DEFAULT_CONFIG_DIR_NAME)
+            self.switcher.store().read(&file_name)
         };
-        
-        // 确保 Claude 配置目录存在
-        if !claude_config_dir.exists() {
-            if let Err(e) = fs::create_dir_all(&claude_config_dir) {
-                self.show_toast(format!("创建 Claude 配置目录时出错: {}", e), ToastKind::Error);
+        let endpoint = match content.and_then(|content| Endpoint::from_content(&content)) {
+            Ok(endpoint) => endpoint,
+            Err(e) => {
+                self.show_toast(t!("toast.connection_failed", name = file_name, error = e), ToastKind::Error);
                 return;
             }
-        }
-        
-        let claude_settings_path = claude_config_dir.join(ACTIVE_CONFIG_NAME);
-        
-        // 从 settings 子目录读取配置文件
-        match fs::read_to_string(&file_path) {
-            Ok(content) => {
-                match fs::write(&claude_settings_path, &content) {
-                    Ok(_) => {
-                        self.show_toast(format!("已将 '{}' 设为默认配置并复制到 Claude 配置文件", file_name), ToastKind::Success);
-                        self.refresh_file_list();
-                    }
-                    Err(e) => self.show_toast(format!("写入 Claude 配置文件时出错: {}", e), ToastKind::Error),
-                }
-            }
-            Err(e) => self.show_toast(format!("读取配置文件时出错: {}", e), ToastKind::Error),
-        }
+        };
+        self.set_status(t!("status.testing_connection", url = endpoint.base_url));
+        let (sender, receiver) = mpsc::channel();
+        std::thread::spawn(move || {
+            let _ = sender.send((file_name, endpoint.test_connection()));
+        });
+        self.connection_receiver = Some(receiver);
     }
-}
 
-impl App for ConfigManagerApp {
-    fn update(&mut self, ctx: &egui::Context, _frame: &mut Frame) {
+    fn poll_connection_test(&mut self) {
+        let Some(receiver) = &self.connection_receiver else { return };
+        let Ok((name, result)) = receiver.try_recv() else { return };
+        self.connection_receiver = None;
+        let (text, kind) = match result {
+            Ok(report) if report.is_success() => (
+                t!("toast.connection_ok", name = name, status = report.status, ms = report.latency.as_millis()),
+                ToastKind::Success,
+            ),
+            Ok(report) if report.is_unauthorized() => (t!("toast.connection_unauthorized", name = name, status = report.status), ToastKind::Error),
+            Ok(report) => (t!("toast.connection_unexpected", name = name, status = report.status, url = report.url), ToastKind::Warning),
+            Err(e) => (t!("toast.connection_failed", name = name, error = e), ToastKind::Error),
+        };
+        self.set_status(text.clone());
+        self.show_toast(text, kind);
+    }
+
+    fn show_command_palette(&mut self, ctx: &egui::Context) {
         let colors = self.get_theme_colors();
+        let matches = self.command_palette.matches(&self.config_files);
+        let mut chosen = None;
 
-        // 处理快捷键
-        if ctx.input(|i| i.key_pressed(egui::Key::S) && i.modifiers.command) {
-            if self.selected_file.is_some() {
-                self.save_current_file();
+        // 方向键和回车在文本框之前处理，避免被输入框消耗
+        ctx.input_mut(|i| {
+            if i.consume_key(egui::Modifiers::NONE, egui::Key::Escape) {
+                self.command_palette.close();
+            }
+            if i.consume_key(egui::Modifiers::NONE, egui::Key::ArrowDown) {
+                self.command_palette.selected += 1;
             }
+            if i.consume_key(egui::Modifiers::NONE, egui::Key::ArrowUp) {
+                self.command_palette.selected = self.command_palette.selected.saturating_sub(1);
+            }
+        });
+        self.command_palette.selected = self.command_palette.selected.min(matches.len().saturating_sub(1));
+        if ctx.input_mut(|i| i.consume_key(egui::Modifiers::NONE, egui::Key::Enter)) {
+            chosen = matches.get(self.command_palette.selected).cloned();
         }
 
-        egui::CentralPanel::default().frame(egui::Frame::default().fill(colors.base)).show(ctx, |ui| {
-            egui::TopBottomPanel::bottom("status_bar").frame(egui::Frame::default().inner_margin(egui::Margin::symmetric(10.0, 5.0)).fill(colors.crust)).show(ui.ctx(), |ui| {
-                ui.horizontal(|ui| {
-                    ui.label(&self.status_text);
-                    ui.with_layout(Layout::right_to_left(Align::Center), |ui| {
-                        ui.label(format!("字符: {}", self.char_count));
-                    });
+        egui::Window::new("command_palette")
+            .title_bar(false)
+            .collapsible(false)
+            .resizable(false)
+            .fixed_size([460.0, 0.0])
+            .anchor(egui::Align2::CENTER_TOP, [0.0, 60.0])
+            .show(ctx, |ui| {
+                let response = ui.add(
+                    TextEdit::singleline(&mut self.command_palette.query)
+                        .hint_text(t!("palette.hint"))
+                        .desired_width(f32::INFINITY),
+                );
+                response.request_focus();
+                if response.changed() {
+                    self.command_palette.selected = 0;
+                }
+                ui.separator();
+                if matches.is_empty() {
+                    ui.label(RichText::new(t!("palette.no_match")).color(colors.muted));
+                }
+                egui::ScrollArea::vertical().max_height(320.0).show(ui, |ui| {
+                    for (index, action) in matches.iter().enumerate() {
+                        let is_selected = index == self.command_palette.selected;
+                        let response = ui.selectable_label(is_selected, action.label());
+                        if is_selected {
+                            response.scroll_to_me(None);
+                        }
+                        if response.clicked() {
+                            chosen = Some(action.clone());
+                        }
+                    }
                 });
             });
-            SidePanel::left("file_list_panel")
-                .frame(egui::Frame::default()
-                    .fill(colors.crust)
-                    .stroke(egui::Stroke::new(1.0, colors.surface0))
+
+        if let Some(action) = chosen {
+            self.command_palette.close();
+            self.perform_action(action, ctx);
+        }
+    }
+
+    // --- 本地控制接口相关方法 ---
+    fn start_control_server(&mut self, waker: impl Fn() + Send + Sync + 'static) {
+        match ControlServer::start(&self.switcher.paths().control_run_dir(), waker) {
+            Ok(server) => self.control_server = Some(server),
+            Err(e) if e.kind() == std::io::ErrorKind::Unsupported => {}
+            Err(e) => self.show_toast(t!("toast.control_start_failed", error = e), ToastKind::Warning),
+        }
+    }
+
+    fn poll_control_requests(&mut self) {
+        while let Some(request) = self.control_server.as_ref().and_then(|server| server.try_recv()) {
+            self.handle_control_request(request);
+        }
+    }
+
+    fn handle_control_request(&mut self, request: ControlRequest) {
+        let result = match request.method.as_str() {
+            "list" => {
+                self.refresh_file_list_preserve_order();
+                let profiles: Vec<Value> = self.config_files
+                    .iter()
+                    .filter_map(|p| p.file_name().and_then(|n| n.to_str()))
+                    .map(|name| serde_json::json!({
+                        "name": name,
+                        "default": self.switcher.is_default(name),
+                    }))
+                    .collect();
+                Ok(Value::Array(profiles))
+            }
+            "current" => {
+                let name = self.switcher.default_profile();
+                Ok(if name.is_empty() {
+                    Value::Null
+                } else {
+                    serde_json::json!({
+                        "name": name,
+                        "path": self.switcher.store().path(name),
+                    })
+                })
+            }
+            "apply" => match self.control_profile_path(&request) {
+                Ok(path) => {
+                    let name = path.file_name().and_then(|n| n.to_str()).unwrap_or_default().to_string();
+                    let force = request.params.get("force").and_then(Value::as_bool).unwrap_or(false);
+                    self.set_as_default(path, force)
+                        .map(|_| serde_json::json!({ "name": name }))
+                        .map_err(|e| (control::APPLICATION_ERROR, e.to_string()))
+                }
+                Err(e) => Err(e),
+            },
+            "validate" => {
+                // 可以校验已有配置文件，也可以直接校验传入的内容
+                let content = match request.params.get("content").and_then(Value::as_str).map(str::to_string) {
+                    Some(content) => Ok(content),
+                    None => self.control_profile_path(&request)
+                        .and_then(|path| fs::read_to_string(path).map_err(|e| (control::APPLICATION_ERROR, e.to_string()))),
+                };
+                content.map(|content| match jsonc::from_str::<Value>(&content) {
+                    Ok(_) => {
+                        let findings: Vec<Value> = lint::lint(&content)
+                            .iter()
+                            .map(|f| serde_json::json!({ "rule": f.rule, "severity": f.severity.key(), "message": f.message, "line": f.line }))
+                            .collect();
+                        serde_json::json!({ "valid": true, "findings": findings })
+                    }
+                    Err(e) => serde_json::json!({ "valid": false, "error": e.to_string(), "line": e.line(), "column": e.column() }),
+                })
+            }
+            method => Err((control::METHOD_NOT_FOUND, t!("control.unknown_method", method = method))),
+        };
+        request.respond(result);
+    }
+
+    fn control_profile_path(&self, request: &ControlRequest) -> Result<PathBuf, (i64, String)> {
+        let name = request.string_param("name")
+            .ok_or((control::INVALID_PARAMS, t!("control.missing_name").to_string()))?;
+        let name = store::normalize_profile_name(&name).map_err(|e| (control::INVALID_PARAMS, e.to_string()))?;
+        let store = self.switcher.store();
+        if !store.contains(&name) {
+            return Err((control::INVALID_PARAMS, Error::ProfileNotFound(name).to_string()));
+        }
+        Ok(store.path(&name))
+    }
+
+    // --- 审计日志相关方法 ---
+    fn open_audit_dialog(&mut self) {
+        self.audit_entries = audit::read_entries(&self.switcher.audit_log_path());
+        self.show_audit_dialog = true;
+    }
+
+    fn filtered_audit_entries(&self) -> Vec<AuditEntry> {
+        let text = self.audit_filter_text.trim().to_lowercase();
+        self.audit_entries
+            .iter()
+            .filter(|e| self.audit_filter_action.is_none_or(|a| e.action == a))
+            .filter(|e| self.audit_filter_result.is_none_or(|r| e.result == r))
+            .filter(|e| text.is_empty()
+                || e.profile.to_lowercase().contains(&text)
+                || e.detail.to_lowercase().contains(&text)
+                || e.timestamp.contains(&text))
+            .cloned()
+            .collect()
+    }
+
+    fn export_audit_log(&mut self, entries: &[AuditEntry]) {
+        let default_name = format!("claude-switcher-audit-{}.csv", Local::now().format("%Y%m%d"));
+        if let Some(path) = rfd::FileDialog::new()
+            .set_title(t!("audit.export_title"))
+            .set_file_name(default_name)
+            .add_filter("CSV", &["csv"])
+            .add_filter("JSON Lines", &["jsonl"])
+            .save_file() {
+            let content = if path.extension().is_some_and(|ext| ext == "jsonl") {
+                entries.iter().filter_map(|e| serde_json::to_string(e).ok()).map(|line| line + "\n").collect()
+            } else {
+                audit::to_csv(entries)
+            };
+            match fs::write(&path, content) {
+                Ok(_) => self.show_toast(t!("toast.exported", path = path.to_string_lossy()), ToastKind::Success),
+                Err(e) => self.show_toast(t!("toast.audit_export_failed", error = e), ToastKind::Error),
+            }
+        }
+    }
+
+    fn show_audit_window(&mut self, ctx: &egui::Context) {
+        let colors = self.get_theme_colors();
+        let entries = self.filtered_audit_entries();
+        let mut export = false;
+
+        egui::Window::new(t!("audit.title"))
+            .collapsible(false)
+            .resizable(true)
+            .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+            .default_size([760.0, 480.0])
+            .show(ctx, |ui| {
+                // 时间点查询：某个时间使用的是哪个配置
+                ui.horizontal(|ui| {
+                    ui.label(t!("audit.query_time"));
+                    ui.add(TextEdit::singleline(&mut self.audit_query_time).hint_text("YYYY-MM-DD HH:MM").desired_width(140.0));
+                    if !self.audit_query_time.trim().is_empty() {
+                        let answer = schedule::parse_date_time(&self.audit_query_time).map(|time| {
+                            let time = time.and_local_timezone(Local).earliest().map(|t| t.with_timezone(&chrono::Utc));
+                            let history = audit::SwitchHistory::from_entries(&self.audit_entries);
+                            time.and_then(|t| history.profile_at(t).map(|p| p.to_string()))
+                        });
+                        match answer {
+                            Ok(Some(profile)) => ui.label(RichText::new(t!("audit.profile_at", name = profile)).color(colors.green).strong()),
+                            Ok(None) => ui.label(RichText::new(t!("audit.no_switch_before")).color(colors.muted)),
+                            Err(e) => ui.label(RichText::new(e).color(colors.red).size(11.0)),
+                        };
+                    }
+                });
+                ui.add_space(5.0);
+
+                ui.horizontal(|ui| {
+                    ui.label(t!("audit.filter.action"));
+                    egui::ComboBox::from_id_source("audit_action_filter")
+                        .selected_text(self.audit_filter_action.map_or(t!("common.all"), |a| a.label()))
+                        .show_ui(ui, |ui| {
+                            ui.selectable_value(&mut self.audit_filter_action, None, t!("common.all"));
+                            for action in AuditAction::ALL {
+                                ui.selectable_value(&mut self.audit_filter_action, Some(action), action.label());
+                            }
+                        });
+                    ui.label(t!("audit.filter.result"));
+                    egui::ComboBox::from_id_source("audit_result_filter")
+                        .selected_text(match self.audit_filter_result {
+                            None => t!("common.all"),
+                            Some(AuditResult::Success) => t!("audit.result.success"),
+                            Some(AuditResult::Failure) => t!("audit.result.failure"),
+                        })
+                        .show_ui(ui, |ui| {
+                            ui.selectable_value(&mut self.audit_filter_result, None, t!("common.all"));
+                            ui.selectable_value(&mut self.audit_filter_result, Some(AuditResult::Success), t!("audit.result.success"));
+                            ui.selectable_value(&mut self.audit_filter_result, Some(AuditResult::Failure), t!("audit.result.failure"));
+                        });
+                    ui.add(TextEdit::singleline(&mut self.audit_filter_text).hint_text(t!("audit.search_hint")).desired_width(180.0));
+                });
+                ui.add_space(5.0);
+
+                egui::ScrollArea::vertical().id_source("audit_scroll").auto_shrink([false; 2]).max_height(340.0).show(ui, |ui| {
+                    egui::Grid::new("audit_grid").striped(true).show(ui, |ui| {
+                        for header in [t!("audit.col.time"), t!("audit.col.action"), t!("audit.col.profile"), t!("audit.col.hash"), t!("audit.col.result"), t!("audit.col.detail")] {
+                            ui.label(RichText::new(header).strong());
+                        }
+                        ui.end_row();
+                        // 最新的在前
+                        for entry in entries.iter().rev() {
+                            let time = chrono::DateTime::parse_from_rfc3339(&entry.timestamp)
+                                .map(|t| t.format("%Y-%m-%d %H:%M:%S").to_string())
+                                .unwrap_or_else(|_| entry.timestamp.clone());
+                            ui.label(RichText::new(time).size(12.0).color(colors.muted));
+                            ui.label(entry.action.label());
+                            ui.label(&entry.profile);
+                            match &entry.active_settings_hash {
+                                Some(hash) => ui.label(RichText::new(&hash[..12.min(hash.len())]).monospace()).on_hover_text(hash),
+                                None => ui.label(RichText::new("-").color(colors.muted)),
+                            };
+                            if entry.result == AuditResult::Success {
+                                ui.label(RichText::new(format!("✓ {}", t!("audit.result.success"))).color(colors.green));
+                            } else {
+                                ui.label(RichText::new(format!("✗ {}", t!("audit.result.failure"))).color(colors.red));
+                            }
+                            ui.label(RichText::new(&entry.detail).size(12.0));
+                            ui.end_row();
+                        }
+                    });
+                });
+
+                ui.add_space(10.0);
+                ui.horizontal(|ui| {
+                    ui.label(RichText::new(t!("audit.count", total = self.audit_entries.len(), shown = entries.len())).color(colors.muted));
+                    if ui.button(RichText::new(t!("button.export")).color(self.get_button_color("save"))).clicked() {
+                        export = true;
+                    }
+                    if ui.button(RichText::new(t!("button.close")).color(self.get_button_color("secondary"))).clicked() {
+                        self.show_audit_dialog = false;
+                    }
+                });
+            });
+
+        if export {
+            self.export_audit_log(&entries);
+        }
+    }
+
+    // --- 定时切换相关方法 ---
+    fn load_schedule(&mut self) {
+        match Schedule::load(&self.switcher.paths().schedule_file()) {
+            Ok(schedule) => self.schedule = schedule,
+            Err(e) => self.show_toast(t!("toast.schedule_load_failed", error = e), ToastKind::Warning),
+        }
+        let now = Local::now().naive_local();
+        self.schedule_last_check = now;
+        if self.schedule.apply_on_start {
+            if let Some(event) = self.schedule.scheduled_profile(now) {
+                self.apply_scheduled_switch(event);
+            }
+        }
+        self.next_switch = self.schedule.next_switch(now);
+    }
+
+    // 只替换规则，不触发启动时应用
+    fn reload_schedule(&mut self) {
+        match Schedule::load(&self.switcher.paths().schedule_file()) {
+            Ok(schedule) => self.schedule = schedule,
+            Err(e) => self.show_toast(t!("toast.schedule_load_failed", error = e), ToastKind::Warning),
+        }
+        self.next_switch = self.schedule.next_switch(Local::now().naive_local());
+    }
+
+    fn save_schedule(&mut self) {
+        if let Err(e) = self.schedule.save(&self.switcher.paths().schedule_file()) {
+            self.show_toast(t!("toast.schedule_save_failed", error = e), ToastKind::Error);
+        }
+        self.next_switch = self.schedule.next_switch(Local::now().naive_local());
+    }
+
+    // 每帧调用，分钟变化时检查是否有到期的切换
+    fn tick_schedule(&mut self) {
+        let now = Local::now().naive_local();
+        let last = self.schedule_last_check;
+        if now.date() == last.date() && now.hour() == last.hour() && now.minute() == last.minute() {
+            return;
+        }
+        let due = self.schedule.due_switch(self.schedule_last_check, now);
+        self.schedule_last_check = now;
+        if let Some(event) = due {
+            self.apply_scheduled_switch(event);
+        }
+        self.next_switch = self.schedule.next_switch(now);
+    }
+
+    fn apply_scheduled_switch(&mut self, event: SwitchEvent) {
+        if self.switcher.is_default(&event.profile) {
+            return;
+        }
+        if self.switcher.store().path(&event.profile).is_file() {
+            let result = self.switcher.apply_scheduled(&event.profile, &event.rule);
+            let _ = self.show_apply_result(event.profile, result);
+        } else {
+            let message = t!("schedule.missing_profile", rule = event.rule, name = event.profile);
+            self.show_toast(message.clone(), ToastKind::Error);
+            self.switcher.record(AuditEntry::new(AuditAction::AutomaticSync, event.profile, None, Err(message)));
+        }
+    }
+
+    fn open_schedule_dialog(&mut self) {
+        self.schedule_draft = self.schedule.clone();
+        self.schedule_log_entries = audit::read_entries(&self.switcher.audit_log_path())
+            .into_iter()
+            .rev()
+            .filter(|entry| entry.action == AuditAction::AutomaticSync)
+            .take(100)
+            .collect();
+        self.show_schedule_dialog = true;
+    }
+
+    fn next_switch_text(&self) -> Option<String> {
+        let event = self.next_switch.as_ref()?;
+        let weekdays = ["weekday.sun", "weekday.mon", "weekday.tue", "weekday.wed", "weekday.thu", "weekday.fri", "weekday.sat"];
+        let weekday = t!(weekdays[event.at.weekday().num_days_from_sunday() as usize]);
+        Some(t!(
+            "schedule.next_switch",
+            date = event.at.format("%m-%d"),
+            weekday = weekday,
+            time = event.at.format("%H:%M"),
+            name = event.profile,
+        ))
+    }
+
+    fn show_schedule_window(&mut self, ctx: &egui::Context) {
+        let colors = self.get_theme_colors();
+        let profile_names: Vec<String> = self.config_files
+            .iter()
+            .filter_map(|p| p.file_name().and_then(|n| n.to_str()).map(|s| s.to_string()))
+            .collect();
+
+        egui::Window::new(t!("schedule.title"))
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+            .default_width(560.0)
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    ui.checkbox(&mut self.schedule_draft.enabled, t!("schedule.enabled"));
+                    ui.checkbox(&mut self.schedule_draft.apply_on_start, t!("schedule.apply_on_start"));
+                });
+                ui.label(RichText::new(t!("schedule.help")).size(11.0).color(colors.muted));
+                ui.add_space(5.0);
+
+                let mut rule_to_remove = None;
+                egui::ScrollArea::vertical().id_source("schedule_rules_scroll").max_height(260.0).show(ui, |ui| {
+                    for (index, rule) in self.schedule_draft.rules.iter_mut().enumerate() {
+                        ui.group(|ui| {
+                            ui.horizontal(|ui| {
+                                ui.checkbox(&mut rule.enabled, "");
+                                ui.add(TextEdit::singleline(&mut rule.name).desired_width(120.0));
+                                egui::ComboBox::from_id_source(("schedule_profile", index))
+                                    .selected_text(if rule.profile.is_empty() { t!("schedule.select_profile") } else { rule.profile.as_str() })
+                                    .width(160.0)
+                                    .show_ui(ui, |ui| {
+                                        for name in &profile_names {
+                                            ui.selectable_value(&mut rule.profile, name.clone(), name);
+                                        }
+                                    });
+                                if ui.button(RichText::new(t!("button.delete")).color(colors.red).size(11.0)).clicked() {
+                                    rule_to_remove = Some(index);
+                                }
+                            });
+                            ui.horizontal(|ui| {
+                                match &mut rule.kind {
+                                    RuleKind::Cron { expression } => {
+                                        ui.label("cron:");
+                                        ui.add(TextEdit::singleline(expression).desired_width(160.0).font(egui::FontId::monospace(13.0)));
+                                    }
+                                    RuleKind::DateRange { start, end } => {
+                                        ui.label(t!("schedule.from"));
+                                        ui.add(TextEdit::singleline(start).desired_width(130.0));
+                                        ui.label(t!("schedule.to"));
+                                        ui.add(TextEdit::singleline(end).desired_width(130.0));
+                                    }
+                                }
+                            });
+                            if let Err(e) = rule.validate() {
+                                ui.label(RichText::new(e).size(11.0).color(colors.red));
+                            }
+                        });
+                    }
+                });
+                if let Some(index) = rule_to_remove {
+                    self.schedule_draft.rules.remove(index);
+                }
+
+                ui.horizontal(|ui| {
+                    if ui.button(RichText::new(t!("schedule.add_cron")).color(self.get_button_color("add"))).clicked() {
+                        self.schedule_draft.rules.push(ScheduleRule::new_cron());
+                    }
+                    if ui.button(RichText::new(t!("schedule.add_range")).color(self.get_button_color("add"))).clicked() {
+                        self.schedule_draft.rules.push(ScheduleRule::new_date_range());
+                    }
+                });
+
+                ui.add_space(10.0);
+                egui::CollapsingHeader::new(t!("schedule.log_header", count = self.schedule_log_entries.len()))
+                    .id_source("switch_log_header")
+                    .show(ui, |ui| {
+                        egui::ScrollArea::vertical().id_source("switch_log_scroll").max_height(160.0).show(ui, |ui| {
+                            if self.schedule_log_entries.is_empty() {
+                                ui.label(RichText::new(t!("schedule.log_empty")).color(colors.muted));
+                            }
+                            for entry in &self.schedule_log_entries {
+                                let time = chrono::DateTime::parse_from_rfc3339(&entry.timestamp)
+                                    .map(|t| t.format("%Y-%m-%d %H:%M").to_string())
+                                    .unwrap_or_else(|_| entry.timestamp.clone());
+                                let success = entry.result == AuditResult::Success;
+                                let (mark, color) = if success { ("✓", colors.green) } else { ("✗", colors.red) };
+                                ui.horizontal(|ui| {
+                                    ui.label(RichText::new(mark).color(color));
+                                    ui.label(RichText::new(time).color(colors.muted).size(12.0));
+                                    ui.label(RichText::new(&entry.profile).size(12.0));
+                                    if !entry.detail.is_empty() {
+                                        let detail_color = if success { colors.muted } else { colors.red };
+                                        ui.label(RichText::new(&entry.detail).color(detail_color).size(11.0));
+                                    }
+                                });
+                            }
+                        });
+                    });
+
+                ui.add_space(15.0);
+                ui.horizontal(|ui| {
+                    if ui.button(RichText::new(t!("schedule.save")).color(colors.green)).clicked() {
+                        self.schedule = self.schedule_draft.clone();
+                        self.save_schedule();
+                        self.show_toast(t!("toast.schedule_saved"), ToastKind::Success);
+                        self.show_schedule_dialog = false;
+                    }
+                    if ui.button(RichText::new(t!("button.cancel")).color(self.get_button_color("secondary"))).clicked() {
+                        self.show_schedule_dialog = false;
+                    }
+                });
+            });
+    }
+
+    // --- MCP 和 hooks 对话框共用的方法 ---
+    // 默认编辑当前标签页中的配置，没有打开的配置时编辑默认配置
+    fn dialog_profile(&self) -> Option<PathBuf> {
+        self.workspace.active_path().map(PathBuf::from).or_else(|| {
+            let default = self.switcher.default_profile();
+            (!default.is_empty()).then(|| self.switcher.store().path(default))
+        })
+    }
+
+    // 已打开的配置读取编辑器中的内容（可能尚未保存），否则读取文件
+    fn profile_settings(&mut self, path: &Path) -> Option<Value> {
+        let content = match self.workspace.find(path) {
+            Some(document) => document.content.clone(),
+            None => match fs::read_to_string(path) {
+                Ok(content) => content,
+                Err(e) => {
+                    self.show_toast(t!("toast.read_failed", error = e), ToastKind::Error);
+                    return None;
+                }
+            },
+        };
+        match jsonc::from_str(&content) {
+            Ok(settings) => Some(settings),
+            Err(e) => {
+                self.show_toast(t!("error.invalid_json", error = e), ToastKind::Error);
+                None
+            }
+        }
+    }
+
+    // 对话框的结果写入编辑器中的配置，由用户确认后保存；成功时返回配置名称
+    fn edit_profile(&mut self, path: &Path, edit: impl FnOnce(&str, &FormatSettings) -> claude_code_switcher::Result<String>) -> Option<String> {
+        self.open_file(path.to_path_buf());
+        let format = self.switcher.settings().format.clone();
+        let document = self.workspace.find_mut(path)?;
+        match edit(&document.content, &format) {
+            Ok(content) => {
+                if content != document.content {
+                    document.content = content;
+                    document.mark_edited();
+                }
+                Some(document.name())
+            }
+            Err(e) => {
+                self.show_toast(e.to_string(), ToastKind::Error);
+                None
+            }
+        }
+    }
+
+    fn load_project_dir_input(&mut self) {
+        self.project_dir_input = self.switcher.settings().mcp.project_dir.as_ref().map(|d| d.to_string_lossy().to_string()).unwrap_or_default();
+    }
+
+    // 项目目录保存在应用设置中，MCP 的项目级服务器和 hooks 的脚本检查、试运行共用
+    fn save_project_dir(&mut self) {
+        let project_dir = self.project_dir_input.trim();
+        let project_dir = (!project_dir.is_empty()).then(|| PathBuf::from(project_dir));
+        if self.switcher.settings().mcp.project_dir != project_dir {
+            self.switcher.settings_mut().mcp.project_dir = project_dir;
+            if let Err(e) = self.switcher.save_settings() {
+                self.show_toast(t!("warning.settings_save", error = e), ToastKind::Error);
+            }
+        }
+    }
+
+    // 输入框中的项目目录，尚未保存时也用于检查
+    fn project_dir(&self) -> Option<PathBuf> {
+        let dir = self.project_dir_input.trim();
+        (!dir.is_empty()).then(|| PathBuf::from(dir))
+    }
+
+    // 返回新选择的配置
+    fn show_profile_picker(&self, ui: &mut egui::Ui, id: &str, current: &Option<PathBuf>) -> Option<PathBuf> {
+        let selected = current.as_ref().and_then(|p| p.file_name()).map(|n| n.to_string_lossy().to_string());
+        let mut profile = current.clone();
+        ui.horizontal(|ui| {
+            ui.label(t!("mcp.profile"));
+            egui::ComboBox::from_id_source(id)
+                .selected_text(selected.as_deref().unwrap_or(t!("schedule.select_profile")))
+                .width(220.0)
+                .show_ui(ui, |ui| {
+                    for path in &self.config_files {
+                        let name = path.file_name().unwrap_or_default().to_string_lossy();
+                        ui.selectable_value(&mut profile, Some(path.clone()), name);
+                    }
+                });
+        });
+        (&profile != current).then_some(profile).flatten()
+    }
+
+    fn show_project_dir_input(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            ui.label(t!("mcp.project_dir"));
+            ui.add(TextEdit::singleline(&mut self.project_dir_input).desired_width(360.0).hint_text(t!("mcp.project_dir_hint")));
+            if ui.button(t!("settings.pick_folder")).clicked() {
+                if let Some(folder) = rfd::FileDialog::new().set_title(t!("mcp.project_dir")).pick_folder() {
+                    self.project_dir_input = folder.to_string_lossy().to_string();
+                }
+            }
+        });
+    }
+
+    // --- MCP 服务器相关方法 ---
+    fn open_mcp_dialog(&mut self) {
+        self.load_project_dir_input();
+        self.load_mcp_drafts(self.dialog_profile());
+        self.show_mcp_dialog = true;
+    }
+
+    fn load_mcp_drafts(&mut self, profile: Option<PathBuf>) {
+        self.mcp_drafts.clear();
+        self.mcp_profile = profile.clone();
+        if let Some(settings) = profile.and_then(|path| self.profile_settings(&path)) {
+            self.mcp_drafts = mcp::from_profile(&settings).unwrap_or_default().into_iter().map(McpDraft::new).collect();
+        }
+    }
+
+    // 同名的服务器已存在时跳过
+    fn import_mcp_servers(&mut self, path: &Path) {
+        match mcp::read_servers_file(path) {
+            Ok(servers) => {
+                let total = servers.len();
+                let mut imported = 0;
+                for server in servers {
+                    if !self.mcp_drafts.iter().any(|d| d.server.name.trim() == server.name) {
+                        self.mcp_drafts.push(McpDraft::new(server));
+                        imported += 1;
+                    }
+                }
+                self.show_toast(t!("toast.mcp_imported", count = imported, skipped = total - imported), ToastKind::Success);
+            }
+            Err(e) => self.show_toast(e.to_string(), ToastKind::Error),
+        }
+    }
+
+    // 有问题时禁用保存按钮；单个服务器的问题已显示在它下方
+    fn mcp_drafts_error(&self) -> Option<String> {
+        let servers: Vec<McpServer> = self.mcp_drafts.iter().map(McpDraft::to_server).collect();
+        for (index, server) in servers.iter().enumerate() {
+            if server.validate().is_err() {
+                return Some(t!("mcp.invalid.servers").to_string());
+            }
+            if servers[..index].iter().any(|s| s.name == server.name) {
+                return Some(t!("mcp.invalid.duplicate", name = server.name));
+            }
+        }
+        None
+    }
+
+    // 写入编辑器中的配置，由用户确认后保存；保存或应用默认配置时才会写入 Claude 的文件
+    fn save_mcp_drafts(&mut self) {
+        self.save_project_dir();
+        let Some(path) = self.mcp_profile.clone() else { return };
+        let servers: Vec<McpServer> = self.mcp_drafts.iter().map(McpDraft::to_server).collect();
+        if let Some(name) = self.edit_profile(&path, |content, format| mcp::set_in_profile(content, &servers, format)) {
+            self.show_toast(t!("toast.mcp_written", name = name), ToastKind::Success);
+            self.show_mcp_dialog = false;
+        }
+    }
+
+    fn show_mcp_window(&mut self, ctx: &egui::Context) {
+        let colors = self.get_theme_colors();
+        let mono = egui::FontId::monospace(13.0);
+
+        egui::Window::new(t!("mcp.title"))
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+            .default_width(620.0)
+            .show(ctx, |ui| {
+                if let Some(profile) = self.show_profile_picker(ui, "mcp_profile", &self.mcp_profile) {
+                    self.load_mcp_drafts(Some(profile));
+                }
+                self.show_project_dir_input(ui);
+                ui.label(RichText::new(t!("mcp.help")).size(11.0).color(colors.muted));
+                ui.add_space(5.0);
+
+                let mut draft_to_remove = None;
+                egui::ScrollArea::vertical().id_source("mcp_servers_scroll").max_height(340.0).show(ui, |ui| {
+                    if self.mcp_drafts.is_empty() {
+                        ui.label(RichText::new(t!("mcp.empty")).color(colors.muted));
+                    }
+                    for (index, draft) in self.mcp_drafts.iter_mut().enumerate() {
+                        ui.group(|ui| {
+                            let server = &mut draft.server;
+                            ui.horizontal(|ui| {
+                                ui.checkbox(&mut server.enabled, "");
+                                ui.add(TextEdit::singleline(&mut server.name).desired_width(140.0).hint_text(t!("mcp.name")));
+                                egui::ComboBox::from_id_source(("mcp_transport", index))
+                                    .selected_text(server.transport.label())
+                                    .width(70.0)
+                                    .show_ui(ui, |ui| {
+                                        for transport in Transport::ALL {
+                                            ui.selectable_value(&mut server.transport, transport, transport.label());
+                                        }
+                                    });
+                                egui::ComboBox::from_id_source(("mcp_scope", index))
+                                    .selected_text(server.scope.label())
+                                    .width(70.0)
+                                    .show_ui(ui, |ui| {
+                                        for scope in Scope::ALL {
+                                            ui.selectable_value(&mut server.scope, scope, scope.label());
+                                        }
+                                    });
+                                if ui.button(RichText::new(t!("button.delete")).color(colors.red).size(11.0)).clicked() {
+                                    draft_to_remove = Some(index);
+                                }
+                            });
+                            match server.transport {
+                                Transport::Stdio => {
+                                    ui.horizontal(|ui| {
+                                        ui.label(t!("mcp.command"));
+                                        ui.add(TextEdit::singleline(&mut server.command).desired_width(440.0).font(mono.clone()));
+                                    });
+                                    ui.horizontal(|ui| {
+                                        ui.add(TextEdit::multiline(&mut draft.args).desired_rows(2).desired_width(260.0).font(mono.clone()).hint_text(t!("mcp.args_hint")));
+                                        ui.add(TextEdit::multiline(&mut draft.env).desired_rows(2).desired_width(260.0).font(mono.clone()).hint_text(t!("mcp.env_hint")));
+                                    });
+                                }
+                                Transport::Http | Transport::Sse => {
+                                    ui.horizontal(|ui| {
+                                        ui.label(t!("mcp.url"));
+                                        ui.add(TextEdit::singleline(&mut server.url).desired_width(440.0).font(mono.clone()));
+                                    });
+                                    ui.add(TextEdit::multiline(&mut draft.headers).desired_rows(2).desired_width(530.0).font(mono.clone()).hint_text(t!("mcp.headers_hint")));
+                                }
+                            }
+                            if let Err(e) = draft.to_server().validate() {
+                                ui.label(RichText::new(e).size(11.0).color(colors.red));
+                            }
+                        });
+                    }
+                });
+                if let Some(index) = draft_to_remove {
+                    self.mcp_drafts.remove(index);
+                }
+
+                ui.horizontal(|ui| {
+                    if ui.button(RichText::new(t!("mcp.add_stdio")).color(self.get_button_color("add"))).clicked() {
+                        self.mcp_drafts.push(McpDraft::new(McpServer::new("", Transport::Stdio)));
+                    }
+                    if ui.button(RichText::new(t!("mcp.add_http")).color(self.get_button_color("add"))).clicked() {
+                        self.mcp_drafts.push(McpDraft::new(McpServer::new("", Transport::Http)));
+                    }
+                    ui.separator();
+                    if ui.button(t!("mcp.import_claude")).clicked() {
+                        let path = self.switcher.paths().claude_user_config_file();
+                        self.import_mcp_servers(&path);
+                    }
+                    if ui.button(t!("mcp.import_desktop")).clicked() {
+                        let path = self.switcher.paths().claude_desktop_config_file();
+                        self.import_mcp_servers(&path);
+                    }
+                });
+
+                ui.add_space(15.0);
+                let error = self.mcp_drafts_error();
+                if let Some(error) = &error {
+                    ui.label(RichText::new(error).size(11.0).color(colors.red));
+                }
+                ui.horizontal(|ui| {
+                    let can_save = error.is_none() && self.mcp_profile.is_some();
+                    if ui.add_enabled(can_save, egui::Button::new(RichText::new(t!("mcp.save")).color(colors.green))).clicked() {
+                        self.save_mcp_drafts();
+                    }
+                    if ui.button(RichText::new(t!("button.cancel")).color(self.get_button_color("secondary"))).clicked() {
+                        self.show_mcp_dialog = false;
+                    }
+                });
+            });
+    }
+
+    // --- hooks 相关方法 ---
+    fn open_hooks_dialog(&mut self) {
+        self.load_project_dir_input();
+        self.load_hooks_drafts(self.dialog_profile());
+        self.hooks_input.clear();
+        self.show_hooks_dialog = true;
+    }
+
+    fn load_hooks_drafts(&mut self, profile: Option<PathBuf>) {
+        self.hooks_drafts.clear();
+        self.clear_hook_runs();
+        self.hooks_profile = profile.clone();
+        if let Some(settings) = profile.and_then(|path| self.profile_settings(&path)) {
+            self.hooks_drafts = hooks::from_settings(&settings);
+        }
+    }
+
+    // 分组或命令增删后序号会变化，丢弃旧的结果和进行中的试运行
+    fn clear_hook_runs(&mut self) {
+        self.hooks_results.clear();
+        self.hooks_receiver = None;
+    }
+
+    // 在后台线程运行命令；输入不是该事件的示例时换成对应的示例
+    fn run_hook(&mut self, group: usize, command: usize) {
+        let Some(draft) = self.hooks_drafts.get(group) else { return };
+        let Some(hook) = draft.commands.get(command) else { return };
+        let project_dir = self.project_dir();
+        let event = serde_json::from_str::<Value>(&self.hooks_input).ok().and_then(|input| input.get("hook_event_name").and_then(Value::as_str).map(str::to_string));
+        if event.as_deref() != Some(draft.event.as_str()) {
+            let cwd = project_dir.clone().unwrap_or_else(|| self.switcher.paths().home().to_path_buf());
+            self.hooks_input = hooks::sample_input(&draft.event, &draft.matcher, &cwd);
+        }
+        let (text, input, timeout) = (hook.command.clone(), self.hooks_input.clone(), hook.timeout());
+        let (sender, receiver) = mpsc::channel();
+        std::thread::spawn(move || {
+            let _ = sender.send(((group, command), hooks::dry_run(&text, &input, timeout, project_dir.as_deref())));
+        });
+        self.hooks_results.remove(&(group, command));
+        self.hooks_receiver = Some(receiver);
+    }
+
+    fn poll_hook_run(&mut self) {
+        let Some(receiver) = &self.hooks_receiver else { return };
+        let Ok((index, result)) = receiver.try_recv() else { return };
+        self.hooks_receiver = None;
+        self.hooks_results.insert(index, result.map_err(|e| e.to_string()));
+    }
+
+    fn save_hooks_drafts(&mut self) {
+        self.save_project_dir();
+        let Some(path) = self.hooks_profile.clone() else { return };
+        let groups = self.hooks_drafts.clone();
+        if let Some(name) = self.edit_profile(&path, |content, format| hooks::set_in_profile(content, &groups, format)) {
+            self.show_toast(t!("toast.hooks_written", name = name), ToastKind::Success);
+            self.show_hooks_dialog = false;
+        }
+    }
+
+    fn show_hooks_window(&mut self, ctx: &egui::Context) {
+        let colors = self.get_theme_colors();
+        let add_color = self.get_button_color("add");
+        let mono = egui::FontId::monospace(13.0);
+        let home = self.switcher.paths().home().to_path_buf();
+        let project_dir = self.project_dir();
+        let running = self.hooks_receiver.is_some();
+        let mut run = None;
+        let mut structure_changed = false;
+
+        egui::Window::new(t!("hooks.title"))
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+            .default_width(640.0)
+            .show(ctx, |ui| {
+                if let Some(profile) = self.show_profile_picker(ui, "hooks_profile", &self.hooks_profile) {
+                    self.load_hooks_drafts(Some(profile));
+                }
+                self.show_project_dir_input(ui);
+                ui.label(RichText::new(t!("hooks.help")).size(11.0).color(colors.muted));
+                ui.add_space(5.0);
+
+                let mut group_to_remove = None;
+                egui::ScrollArea::vertical().id_source("hooks_groups_scroll").max_height(380.0).show(ui, |ui| {
+                    if self.hooks_drafts.is_empty() {
+                        ui.label(RichText::new(t!("hooks.empty")).color(colors.muted));
+                    }
+                    for (group_index, group) in self.hooks_drafts.iter_mut().enumerate() {
+                        ui.group(|ui| {
+                            ui.horizontal(|ui| {
+                                egui::ComboBox::from_id_source(("hooks_event", group_index))
+                                    .selected_text(group.event.as_str())
+                                    .width(140.0)
+                                    .show_ui(ui, |ui| {
+                                        for event in hooks::EVENTS {
+                                            ui.selectable_value(&mut group.event, event.to_string(), event);
+                                        }
+                                    });
+                                if hooks::uses_matcher(&group.event) {
+                                    ui.label(t!("hooks.matcher"));
+                                    ui.add(TextEdit::singleline(&mut group.matcher).desired_width(200.0).font(mono.clone()).hint_text(t!("hooks.matcher_hint")));
+                                }
+                                if ui.button(RichText::new(t!("button.delete")).color(colors.red).size(11.0)).clicked() {
+                                    group_to_remove = Some(group_index);
+                                }
+                            });
+                            let mut command_to_remove = None;
+                            for (command_index, command) in group.commands.iter_mut().enumerate() {
+                                ui.horizontal(|ui| {
+                                    ui.add(TextEdit::singleline(&mut command.command).desired_width(340.0).font(mono.clone()).hint_text(t!("hooks.command_hint")));
+                                    let mut custom_timeout = command.timeout.is_some();
+                                    if ui.checkbox(&mut custom_timeout, t!("hooks.timeout")).changed() {
+                                        command.timeout = custom_timeout.then_some(hooks::DEFAULT_TIMEOUT_SECS);
+                                    }
+                                    if let Some(timeout) = &mut command.timeout {
+                                        ui.add(egui::DragValue::new(timeout).clamp_range(1..=3600).suffix(" s"));
+                                    }
+                                    let run_button = egui::Button::new(RichText::new("▶").color(colors.green));
+                                    if ui.add_enabled(!running, run_button).on_hover_text(t!("hooks.dry_run")).clicked() {
+                                        run = Some((group_index, command_index));
+                                    }
+                                    if ui.button(RichText::new("✕").color(colors.red)).clicked() {
+                                        command_to_remove = Some(command_index);
+                                    }
+                                });
+                                for issue in hooks::check_command(&command.command, &home, project_dir.as_deref()) {
+                                    ui.label(RichText::new(format!("⚠ {}", issue.message())).size(11.0).color(colors.yellow));
+                                }
+                                if let Some(result) = self.hooks_results.get(&(group_index, command_index)) {
+                                    show_dry_run(ui, result, &colors);
+                                }
+                            }
+                            if let Some(index) = command_to_remove {
+                                group.commands.remove(index);
+                                structure_changed = true;
+                            }
+                            if ui.button(RichText::new(t!("hooks.add_command")).size(11.0).color(add_color)).clicked() {
+                                group.commands.push(HookCommand::new(""));
+                                structure_changed = true;
+                            }
+                        });
+                    }
+                });
+                if let Some(index) = group_to_remove {
+                    self.hooks_drafts.remove(index);
+                    structure_changed = true;
+                }
+
+                ui.horizontal(|ui| {
+                    if ui.button(RichText::new(t!("hooks.add_group")).color(add_color)).clicked() {
+                        self.hooks_drafts.push(HookGroup::new(hooks::EVENTS[0]));
+                    }
+                    if running {
+                        ui.spinner();
+                        ui.label(RichText::new(t!("hooks.running")).size(11.0).color(colors.muted));
+                    }
+                });
+                egui::CollapsingHeader::new(t!("hooks.sample_input")).id_source("hooks_sample_input").show(ui, |ui| {
+                    ui.label(RichText::new(t!("hooks.sample_input_help")).size(11.0).color(colors.muted));
+                    egui::ScrollArea::vertical().id_source("hooks_input_scroll").max_height(160.0).show(ui, |ui| {
+                        ui.add(TextEdit::multiline(&mut self.hooks_input).desired_width(f32::INFINITY).desired_rows(6).font(mono.clone()));
+                    });
+                });
+
+                ui.add_space(15.0);
+                ui.horizontal(|ui| {
+                    if ui.add_enabled(self.hooks_profile.is_some(), egui::Button::new(RichText::new(t!("mcp.save")).color(colors.green))).clicked() {
+                        self.save_hooks_drafts();
+                    }
+                    if ui.button(RichText::new(t!("button.cancel")).color(self.get_button_color("secondary"))).clicked() {
+                        self.show_hooks_dialog = false;
+                    }
+                });
+            });
+
+        if structure_changed {
+            self.clear_hook_runs();
+        }
+        if let Some((group, command)) = run {
+            self.run_hook(group, command);
+        }
+    }
+
+    // --- 权限相关方法 ---
+    fn open_permissions_dialog(&mut self) {
+        self.load_project_dir_input();
+        self.load_permissions_draft(self.dialog_profile());
+        self.show_permissions_dialog = true;
+    }
+
+    fn load_permissions_draft(&mut self, profile: Option<PathBuf>) {
+        self.permissions_profile = profile.clone();
+        self.permissions_draft = profile.and_then(|path| self.profile_settings(&path)).map(|settings| Permissions::from_settings(&settings)).unwrap_or_default();
+    }
+
+    fn save_permissions_draft(&mut self) {
+        self.save_project_dir();
+        let Some(path) = self.permissions_profile.clone() else { return };
+        let value = self.permissions_draft.to_value();
+        if let Some(name) = self.edit_profile(&path, |content, format| outline::replace_member(content, permissions::PROFILE_KEY, value.as_ref(), format)) {
+            self.show_toast(t!("toast.permissions_written", name = name), ToastKind::Success);
+            self.show_permissions_dialog = false;
+        }
+    }
+
+    // 测试调用命中的规则及对应的颜色
+    fn permission_test_result(&self, colors: &ThemeColors) -> Option<(String, Color32)> {
+        let call = self.permissions_test.trim();
+        if call.is_empty() {
+            return None;
+        }
+        if let Err(e) = permissions::Rule::parse(call) {
+            return Some((e, colors.red));
+        }
+        let home = self.switcher.paths().home();
+        let found = self.permissions_draft.evaluate(call, home, self.project_dir().as_deref());
+        Some(match found {
+            Some(found) => {
+                let text = t!("permissions.test.matched", list = found.list.label(), index = found.index + 1, rule = found.rule);
+                let color = match found.list {
+                    RuleList::Allow => colors.green,
+                    RuleList::Ask => colors.yellow,
+                    RuleList::Deny => colors.red,
+                };
+                (text, color)
+            }
+            None => {
+                let mode = self.permissions_draft.default_mode.as_deref().unwrap_or("default");
+                (t!("permissions.test.unmatched", mode = mode), colors.muted)
+            }
+        })
+    }
+
+    fn show_permissions_window(&mut self, ctx: &egui::Context) {
+        let colors = self.get_theme_colors();
+        let add_color = self.get_button_color("add");
+        let mono = egui::FontId::monospace(13.0);
+
+        egui::Window::new(t!("permissions.title"))
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+            .default_width(600.0)
+            .show(ctx, |ui| {
+                if let Some(profile) = self.show_profile_picker(ui, "permissions_profile", &self.permissions_profile) {
+                    self.load_permissions_draft(Some(profile));
+                }
+                self.show_project_dir_input(ui);
+                ui.horizontal(|ui| {
+                    ui.label(t!("permissions.default_mode"));
+                    let draft = &mut self.permissions_draft;
+                    egui::ComboBox::from_id_source("permissions_default_mode")
+                        .selected_text(draft.default_mode.as_deref().unwrap_or(t!("permissions.mode_unset")))
+                        .width(160.0)
+                        .show_ui(ui, |ui| {
+                            ui.selectable_value(&mut draft.default_mode, None, t!("permissions.mode_unset"));
+                            for mode in permissions::DEFAULT_MODES {
+                                ui.selectable_value(&mut draft.default_mode, Some(mode.to_string()), mode);
+                            }
+                        });
+                });
+                ui.label(RichText::new(t!("permissions.help")).size(11.0).color(colors.muted));
+                ui.add_space(5.0);
+
+                egui::ScrollArea::vertical().id_source("permissions_scroll").max_height(380.0).show(ui, |ui| {
+                    for list in RuleList::ALL {
+                        let rules = self.permissions_draft.list_mut(list);
+                        let header = format!("{} · {} ({})", list.label(), list.key(), rules.len());
+                        egui::CollapsingHeader::new(header).id_source(("permissions_list", list)).default_open(true).show(ui, |ui| {
+                            let mut moved = None;
+                            let mut removed = None;
+                            let count = rules.len();
+                            for (index, rule) in rules.iter_mut().enumerate() {
+                                ui.horizontal(|ui| {
+                                    if ui.add_enabled(index > 0, egui::Button::new("⬆").small()).clicked() {
+                                        moved = Some((index, index - 1));
+                                    }
+                                    if ui.add_enabled(index + 1 < count, egui::Button::new("⬇").small()).clicked() {
+                                        moved = Some((index, index + 1));
+                                    }
+                                    ui.add(TextEdit::singleline(rule).desired_width(420.0).font(mono.clone()).hint_text(t!("permissions.rule_hint")));
+                                    if ui.button(RichText::new("✕").color(colors.red)).clicked() {
+                                        removed = Some(index);
+                                    }
+                                });
+                                for (severity, message) in permissions::check_rule(rule) {
+                                    let color = if severity == Severity::Error { colors.red } else { colors.yellow };
+                                    ui.label(RichText::new(format!("⚠ {}", message)).size(11.0).color(color));
+                                }
+                            }
+                            if let Some((from, to)) = moved {
+                                rules.swap(from, to);
+                            }
+                            if let Some(index) = removed {
+                                rules.remove(index);
+                            }
+                            if ui.button(RichText::new(t!("permissions.add_rule")).size(11.0).color(add_color)).clicked() {
+                                rules.push(String::new());
+                            }
+                        });
+                    }
+
+                    let directories = &mut self.permissions_draft.additional_directories;
+                    let header = t!("permissions.additional_directories", count = directories.len());
+                    egui::CollapsingHeader::new(header).id_source("permissions_directories").show(ui, |ui| {
+                        let mut removed = None;
+                        for (index, directory) in directories.iter_mut().enumerate() {
+                            ui.horizontal(|ui| {
+                                ui.add(TextEdit::singleline(directory).desired_width(470.0).font(mono.clone()));
+                                if ui.button(RichText::new("✕").color(colors.red)).clicked() {
+                                    removed = Some(index);
+                                }
+                            });
+                        }
+                        if let Some(index) = removed {
+                            directories.remove(index);
+                        }
+                        if ui.button(RichText::new(t!("permissions.add_directory")).size(11.0).color(add_color)).clicked() {
+                            directories.push(String::new());
+                        }
+                    });
+                });
+
+                for conflict in self.permissions_draft.conflicts() {
+                    ui.label(RichText::new(format!("⚠ {}", conflict.message())).size(11.0).color(colors.yellow));
+                }
+
+                ui.add_space(8.0);
+                ui.horizontal(|ui| {
+                    ui.label(t!("permissions.test"));
+                    ui.add(TextEdit::singleline(&mut self.permissions_test).desired_width(300.0).font(mono.clone()).hint_text("Bash(git push origin main)"));
+                });
+                if let Some((text, color)) = self.permission_test_result(&colors) {
+                    ui.label(RichText::new(text).color(color));
+                }
+
+                ui.add_space(15.0);
+                ui.horizontal(|ui| {
+                    if ui.add_enabled(self.permissions_profile.is_some(), egui::Button::new(RichText::new(t!("mcp.save")).color(colors.green))).clicked() {
+                        self.save_permissions_draft();
+                    }
+                    if ui.button(RichText::new(t!("button.cancel")).color(self.get_button_color("secondary"))).clicked() {
+                        self.show_permissions_dialog = false;
+                    }
+                });
+            });
+    }
+
+    // --- 模型选择相关方法 ---
+    fn open_models_dialog(&mut self) {
+        self.models_checks.clear();
+        self.load_models_draft(self.dialog_profile());
+        self.show_models_dialog = true;
+    }
+
+    // 读取配置中的模型并在后台获取其端点的模型列表
+    fn load_models_draft(&mut self, profile: Option<PathBuf>) {
+        self.models_profile = profile.clone();
+        self.models_draft.clear();
+        self.models_list.clear();
+        self.models_error = None;
+        self.models_receiver = None;
+        let Some(path) = profile else { return };
+        let Some(settings) = self.profile_settings(&path) else { return };
+        // ANTHROPIC_DEFAULT_HAIKU_MODEL 不在面板中编辑，只参与可用性提示
+        for slot in ModelSlot::CHECKED {
+            self.models_draft.insert(slot, slot.get(&settings).unwrap_or_default());
+        }
+        let endpoint = Endpoint::from_settings(&settings);
+        self.models_base_url = endpoint.base_url.clone();
+        let (sender, receiver) = mpsc::channel();
+        std::thread::spawn(move || {
+            let _ = sender.send((path, endpoint.list_models().map_err(|e| e.to_string())));
+        });
+        self.models_receiver = Some(receiver);
+    }
+
+    // 在后台检查全部配置，编辑器中未保存的内容也参与检查；无法解析的配置跳过
+    fn check_all_models(&mut self) {
+        let profiles: Vec<(String, Value)> = self
+            .config_files
+            .iter()
+            .filter_map(|path| {
+                let content = match self.workspace.find(path) {
+                    Some(document) => document.content.clone(),
+                    None => fs::read_to_string(path).ok()?,
+                };
+                let settings = jsonc::from_str(&content).ok()?;
+                Some((path.file_name()?.to_string_lossy().to_string(), settings))
+            })
+            .collect();
+        let (sender, receiver) = mpsc::channel();
+        std::thread::spawn(move || {
+            let _ = sender.send(models::check_profiles(profiles));
+        });
+        self.models_checks.clear();
+        self.models_check_receiver = Some(receiver);
+    }
+
+    fn poll_models(&mut self) {
+        if let Some(receiver) = &self.models_receiver {
+            if let Ok((path, result)) = receiver.try_recv() {
+                self.models_receiver = None;
+                // 等待期间切换了配置时丢弃旧结果
+                if self.models_profile.as_ref() == Some(&path) {
+                    match result {
+                        Ok(list) if !list.is_empty() => self.models_list = list,
+                        Ok(_) => {
+                            self.models_list = models::catalog();
+                            self.models_error = Some(t!("models.empty_list").to_string());
+                        }
+                        Err(e) => {
+                            self.models_list = models::catalog();
+                            self.models_error = Some(e);
+                        }
+                    }
+                }
+            }
+        }
+        if let Some(receiver) = &self.models_check_receiver {
+            if let Ok(checks) = receiver.try_recv() {
+                self.models_check_receiver = None;
+                self.models_checks = checks;
+            }
+        }
+    }
+
+    fn save_models_draft(&mut self) {
+        let Some(path) = self.models_profile.clone() else { return };
+        let draft = self.models_draft.clone();
+        let written = self.edit_profile(&path, |content, format| {
+            ModelSlot::ALL.into_iter().try_fold(content.to_string(), |content, slot| {
+                slot.set(&content, draft.get(&slot).map(String::as_str).unwrap_or_default(), format)
+            })
+        });
+        if let Some(name) = written {
+            self.show_toast(t!("toast.models_written", name = name), ToastKind::Success);
+            self.show_models_dialog = false;
+        }
+    }
+
+    fn show_models_window(&mut self, ctx: &egui::Context) {
+        let colors = self.get_theme_colors();
+        let mono = egui::FontId::monospace(13.0);
+
+        egui::Window::new(t!("models.title"))
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+            .default_width(560.0)
+            .show(ctx, |ui| {
+                if let Some(profile) = self.show_profile_picker(ui, "models_profile", &self.models_profile) {
+                    self.load_models_draft(Some(profile));
+                }
+                if self.models_profile.is_some() {
+                    ui.horizontal(|ui| {
+                        ui.label(t!("models.endpoint"));
+                        ui.label(RichText::new(&self.models_base_url).font(mono.clone()));
+                    });
+                    if self.models_receiver.is_some() {
+                        ui.horizontal(|ui| {
+                            ui.spinner();
+                            ui.label(RichText::new(t!("models.loading")).size(11.0).color(colors.muted));
+                        });
+                    } else if let Some(error) = &self.models_error {
+                        ui.label(RichText::new(t!("models.fallback", error = error)).size(11.0).color(colors.yellow));
+                    } else {
+                        ui.label(RichText::new(t!("models.listed", count = self.models_list.len())).size(11.0).color(colors.muted));
+                    }
+                }
+                ui.add_space(5.0);
+
+                let list = &self.models_list;
+                egui::Grid::new("models_grid").num_columns(3).spacing([8.0, 6.0]).show(ui, |ui| {
+                    for slot in ModelSlot::ALL {
+                        let id = self.models_draft.entry(slot).or_default();
+                        ui.label(slot.label()).on_hover_text(slot.key());
+                        ui.add(TextEdit::singleline(id).desired_width(260.0).font(mono.clone()).hint_text(t!("models.unset")));
+                        let selected = list.iter().find(|model| &model.id == id).map(ModelInfo::label).unwrap_or_else(|| t!("models.choose").to_string());
+                        egui::ComboBox::from_id_source(("models_slot", slot)).selected_text(selected).width(220.0).show_ui(ui, |ui| {
+                            if ui.selectable_label(id.is_empty(), t!("models.unset")).clicked() {
+                                id.clear();
+                            }
+                            for model in list {
+                                let text = format!("{}  ({})", model.label(), model.id);
+                                if ui.selectable_label(&model.id == id, text).clicked() {
+                                    *id = model.id.clone();
+                                }
+                            }
+                        });
+                        ui.end_row();
+                    }
+                });
+
+                // 内置目录只是参考，端点列表获取失败时不提示
+                if self.models_receiver.is_none() && self.models_error.is_none() {
+                    for slot in ModelSlot::CHECKED {
+                        let id = self.models_draft.get(&slot).map(|id| id.trim()).unwrap_or_default();
+                        if !id.is_empty() && !models::is_offered(id, &self.models_list) {
+                            ui.label(RichText::new(format!("⚠ {}", t!("models.not_offered", slot = slot.label(), model = id))).size(11.0).color(colors.yellow));
+                        }
+                    }
+                }
+                ui.label(RichText::new(t!("models.help")).size(11.0).color(colors.muted));
+
+                ui.add_space(8.0);
+                ui.separator();
+                ui.horizontal(|ui| {
+                    let checking = self.models_check_receiver.is_some();
+                    if ui.add_enabled(!checking, egui::Button::new(t!("models.check_all"))).clicked() {
+                        self.check_all_models();
+                    }
+                    if checking {
+                        ui.spinner();
+                    }
+                });
+                if !self.models_checks.is_empty() {
+                    egui::ScrollArea::vertical().id_source("models_checks").max_height(160.0).show(ui, |ui| {
+                        for check in &self.models_checks {
+                            match &check.result {
+                                Ok(missing) if missing.is_empty() => {
+                                    ui.label(RichText::new(format!("✔ {}", check.profile)).color(colors.green));
+                                }
+                                Ok(missing) => {
+                                    for (slot, id) in missing {
+                                        let text = t!("models.profile_not_offered", profile = check.profile, slot = slot.label(), model = id);
+                                        ui.label(RichText::new(format!("⚠ {}", text)).color(colors.yellow));
+                                    }
+                                }
+                                Err(e) => {
+                                    ui.label(RichText::new(format!("✖ {}", t!("models.profile_check_failed", profile = check.profile, error = e))).color(colors.red));
+                                }
+                            }
+                        }
+                    });
+                }
+
+                ui.add_space(15.0);
+                ui.horizontal(|ui| {
+                    if ui.add_enabled(self.models_profile.is_some(), egui::Button::new(RichText::new(t!("mcp.save")).color(colors.green))).clicked() {
+                        self.save_models_draft();
+                    }
+                    if ui.button(RichText::new(t!("button.cancel")).color(self.get_button_color("secondary"))).clicked() {
+                        self.show_models_dialog = false;
+                    }
+                });
+            });
+    }
+
+    // --- 环境变量相关方法 ---
+    fn open_env_dialog(&mut self) {
+        self.env_filter.clear();
+        self.env_new_name.clear();
+        self.load_env_draft(self.dialog_profile());
+        self.show_env_dialog = true;
+    }
+
+    fn load_env_draft(&mut self, profile: Option<PathBuf>) {
+        self.env_profile = profile.clone();
+        let settings = profile.and_then(|path| self.profile_settings(&path));
+        self.env_original = settings.and_then(|settings| settings.get("env").and_then(Value::as_object).cloned()).unwrap_or_default();
+        self.env_draft = self.env_original.clone();
+    }
+
+    // 只写入有变化的变量，env 中的其余内容和注释保持不变
+    fn save_env_draft(&mut self) {
+        let Some(path) = self.env_profile.clone() else { return };
+        let (draft, original) = (&self.env_draft, &self.env_original);
+        let mut changes: Vec<(String, Option<Value>)> = original.keys().filter(|key| !draft.contains_key(*key)).map(|key| (key.clone(), None)).collect();
+        changes.extend(draft.iter().filter(|(key, value)| original.get(*key) != Some(value)).map(|(key, value)| (key.clone(), Some(value.clone()))));
+        let written = self.edit_profile(&path, |content, format| {
+            changes.iter().try_fold(content.to_string(), |content, (key, value)| outline::replace_nested_member(&content, &["env"], key, value.as_ref(), format))
+        });
+        if let Some(name) = written {
+            self.show_toast(t!("toast.env_written", name = name), ToastKind::Success);
+            self.show_env_dialog = false;
+        }
+    }
+
+    fn show_env_window(&mut self, ctx: &egui::Context) {
+        let colors = self.get_theme_colors();
+        let add_color = self.get_button_color("add");
+        let mono = egui::FontId::monospace(13.0);
+
+        egui::Window::new(t!("env_vars.title"))
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+            .default_width(620.0)
+            .show(ctx, |ui| {
+                if let Some(profile) = self.show_profile_picker(ui, "env_profile", &self.env_profile) {
+                    self.load_env_draft(Some(profile));
+                }
+                ui.horizontal(|ui| {
+                    ui.label(t!("env_vars.search"));
+                    ui.add(TextEdit::singleline(&mut self.env_filter).desired_width(240.0).hint_text(t!("env_vars.search_hint")));
+                });
+                ui.add_space(5.0);
+
+                let filter = self.env_filter.trim().to_lowercase();
+                let matches = |var: &EnvVar| filter.is_empty() || var.name.to_lowercase().contains(&filter) || var.description().to_lowercase().contains(&filter);
+                let issues = env_vars::check(&self.env_draft);
+                egui::ScrollArea::vertical().id_source("env_scroll").max_height(440.0).show(ui, |ui| {
+                    for category in Category::ALL {
+                        let vars: Vec<&EnvVar> = env_vars::CATALOG.iter().filter(|var| var.category == category && var.replaced_by.is_none() && matches(var)).collect();
+                        if vars.is_empty() {
+                            continue;
+                        }
+                        let set = vars.iter().filter(|var| self.env_draft.contains_key(var.name)).count();
+                        let header = format!("{} ({}/{})", category.label(), set, vars.len());
+                        egui::CollapsingHeader::new(header).id_source(("env_category", category)).default_open(!filter.is_empty() || set > 0).show(ui, |ui| {
+                            for var in vars {
+                                env_var_control(ui, var, &mut self.env_draft, &colors);
+                                if let Some(Issue::Invalid(_, message)) = issues.iter().find(|issue| issue.name() == var.name) {
+                                    ui.label(RichText::new(format!("⚠ {}", message)).size(11.0).color(colors.yellow));
+                                }
+                                ui.add_space(4.0);
+                            }
+                        });
+                    }
+
+                    // 目录以外以及已弃用的变量按原样编辑
+                    let others: Vec<String> = self.env_draft.keys().filter(|name| env_vars::find(name).is_none_or(|var| var.replaced_by.is_some())).cloned().collect();
+                    let header = t!("env_vars.others", count = others.len());
+                    egui::CollapsingHeader::new(header).id_source("env_others").default_open(true).show(ui, |ui| {
+                        let mut removed = None;
+                        let mut renamed = None;
+                        for name in &others {
+                            ui.horizontal(|ui| {
+                                ui.label(RichText::new(name).font(mono.clone()));
+                                match self.env_draft.get_mut(name) {
+                                    Some(Value::String(value)) => {
+                                        ui.add(TextEdit::singleline(value).desired_width(260.0).font(mono.clone()));
+                                    }
+                                    Some(value) => {
+                                        ui.label(RichText::new(value.to_string()).font(mono.clone()).color(colors.muted));
+                                    }
+                                    None => {}
+                                }
+                                if ui.button(RichText::new("✕").color(colors.red)).clicked() {
+                                    removed = Some(name.clone());
+                                }
+                            });
+                            if let Some(issue) = issues.iter().find(|issue| issue.name() == name) {
+                                ui.horizontal(|ui| {
+                                    ui.label(RichText::new(format!("⚠ {}", issue.message())).size(11.0).color(colors.yellow));
+                                    if let Issue::Deprecated(_, replacement) = issue {
+                                        if ui.small_button(t!("env_vars.rename", replacement = replacement)).clicked() {
+                                            renamed = Some((name.clone(), replacement.to_string()));
+                                        }
+                                    }
+                                });
+                            }
+                        }
+                        if let Some(name) = removed {
+                            self.env_draft.shift_remove(&name);
+                        }
+                        // 新变量已存在时保留它的值
+                        if let Some((from, to)) = renamed {
+                            if let Some(value) = self.env_draft.shift_remove(&from) {
+                                self.env_draft.entry(to).or_insert(value);
+                            }
+                        }
+                        ui.horizontal(|ui| {
+                            ui.add(TextEdit::singleline(&mut self.env_new_name).desired_width(260.0).font(mono.clone()).hint_text(t!("env_vars.new_name_hint")));
+                            let name = self.env_new_name.trim().to_string();
+                            let valid = !name.is_empty() && !self.env_draft.contains_key(&name);
+                            if ui.add_enabled(valid, egui::Button::new(RichText::new(t!("env_vars.add")).size(11.0).color(add_color))).clicked() {
+                                self.env_draft.insert(name, Value::String(String::new()));
+                                self.env_new_name.clear();
+                            }
+                        });
+                    });
+                });
+
+                ui.add_space(15.0);
+                ui.horizontal(|ui| {
+                    if ui.add_enabled(self.env_profile.is_some(), egui::Button::new(RichText::new(t!("mcp.save")).color(colors.green))).clicked() {
+                        self.save_env_draft();
+                    }
+                    if ui.button(RichText::new(t!("button.cancel")).color(self.get_button_color("secondary"))).clicked() {
+                        self.show_env_dialog = false;
+                    }
+                });
+            });
+    }
+
+    // --- 其他工具目标相关方法 ---
+    fn open_targets_dialog(&mut self) {
+        self.targets_draft = self.switcher.settings().targets.clone();
+        self.refresh_targets_status();
+        self.show_targets_dialog = true;
+    }
+
+    // 按默认配置（磁盘上的内容）对比各目标文件
+    fn refresh_targets_status(&mut self) {
+        let default = self.switcher.default_profile().to_string();
+        let profile = (!default.is_empty())
+            .then(|| self.switcher.store().read(&default).ok())
+            .flatten()
+            .and_then(|content| jsonc::from_str::<Value>(&content).ok())
+            .unwrap_or_default();
+        let home = self.switcher.paths().home().to_path_buf();
+        self.targets_status = self.targets_draft.iter().map(|target| target.status(&home, &profile)).collect();
+    }
+
+    fn save_targets_draft(&mut self) {
+        let mut names = Vec::new();
+        for target in &self.targets_draft {
+            let problem = target.validate().or_else(|| names.contains(&target.name.trim()).then(|| t!("targets.invalid.duplicate", name = target.name)));
+            if let Some(problem) = problem {
+                self.show_toast(problem, ToastKind::Error);
+                return;
+            }
+            names.push(target.name.trim());
+        }
+        let mut targets = self.targets_draft.clone();
+        for target in &mut targets {
+            target.name = target.name.trim().to_string();
+        }
+        self.switcher.settings_mut().targets = targets;
+        if let Err(e) = self.switcher.save_settings() {
+            self.show_toast(t!("warning.settings_save", error = e), ToastKind::Error);
+            return;
+        }
+        self.show_toast(t!("toast.targets_saved").to_string(), ToastKind::Success);
+        self.show_targets_dialog = false;
+    }
+
+    fn show_targets_window(&mut self, ctx: &egui::Context) {
+        let colors = self.get_theme_colors();
+        let add_color = self.get_button_color("add");
+        let mono = egui::FontId::monospace(13.0);
+
+        egui::Window::new(t!("targets.title"))
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+            .default_width(640.0)
+            .show(ctx, |ui| {
+                ui.label(RichText::new(t!("targets.help")).size(11.0).color(colors.muted));
+                ui.add_space(5.0);
+                // Claude Code 的活动配置是固定的目标
+                ui.horizontal(|ui| {
+                    ui.label(RichText::new("Claude Code").strong());
+                    ui.label(RichText::new(self.switcher.target().path().display().to_string()).font(mono.clone()).color(colors.muted));
+                });
+                ui.separator();
+
+                let reports = self.switcher.target_reports().to_vec();
+                let mut removed = None;
+                egui::ScrollArea::vertical().id_source("targets_scroll").max_height(420.0).show(ui, |ui| {
+                    for (index, target) in self.targets_draft.iter_mut().enumerate() {
+                        let status = self.targets_status.get(index);
+                        let color = match status {
+                            Some(TargetStatus::InSync) => colors.green,
+                            Some(TargetStatus::Differs(_)) | Some(TargetStatus::Missing) => colors.yellow,
+                            Some(TargetStatus::Unreadable(_)) => colors.red,
+                            _ => colors.muted,
+                        };
+                        let name = if target.name.is_empty() { t!("targets.unnamed") } else { target.name.as_str() };
+                        let header = RichText::new(format!("{} · {}", name, target.format.label())).color(color);
+                        egui::CollapsingHeader::new(header).id_source(("target", index)).default_open(target.name.is_empty()).show(ui, |ui| {
+                            if let Some(status) = status {
+                                ui.label(RichText::new(status.label()).size(11.0).color(color));
+                            }
+                            if let Some(report) = reports.iter().find(|report| report.name == target.name) {
+                                let (text, color) = match &report.result {
+                                    Ok(count) => (t!("targets.last_apply_ok", count = count), colors.green),
+                                    Err(e) => (t!("targets.last_apply_failed", error = e), colors.red),
+                                };
+                                ui.label(RichText::new(text).size(11.0).color(color));
+                            }
+                            egui::Grid::new(("target_grid", index)).num_columns(2).show(ui, |ui| {
+                                ui.label(t!("targets.name"));
+                                ui.horizontal(|ui| {
+                                    ui.add(TextEdit::singleline(&mut target.name).desired_width(200.0));
+                                    ui.checkbox(&mut target.enabled, t!("targets.enabled"));
+                                });
+                                ui.end_row();
+                                ui.label(t!("targets.path"));
+                                ui.horizontal(|ui| {
+                                    let mut path = target.path.to_string_lossy().to_string();
+                                    if ui.add(TextEdit::singleline(&mut path).desired_width(380.0).font(mono.clone()).hint_text("~/.tool/config.toml")).changed() {
+                                        target.path = PathBuf::from(path);
+                                        target.format = TargetFormat::from_path(&target.path);
+                                    }
+                                    if ui.button(t!("targets.pick_file")).clicked() {
+                                        if let Some(file) = rfd::FileDialog::new().set_title(t!("targets.path")).pick_file() {
+                                            target.format = TargetFormat::from_path(&file);
+                                            target.path = file;
+                                        }
+                                    }
+                                });
+                                ui.end_row();
+                                ui.label(t!("targets.format"));
+                                egui::ComboBox::from_id_source(("target_format", index))
+                                    .selected_text(target.format.label())
+                                    .show_ui(ui, |ui| {
+                                        for format in TargetFormat::ALL {
+                                            ui.selectable_value(&mut target.format, format, format.label());
+                                        }
+                                    });
+                                ui.end_row();
+                            });
+
+                            ui.label(RichText::new(t!("targets.mappings")).strong());
+                            let mut removed_mapping = None;
+                            for (row, mapping) in target.mappings.iter_mut().enumerate() {
+                                ui.horizontal(|ui| {
+                                    ui.add(TextEdit::singleline(&mut mapping.source).desired_width(250.0).font(mono.clone()).hint_text("/env/ANTHROPIC_BASE_URL"));
+                                    ui.label("→");
+                                    ui.add(TextEdit::singleline(&mut mapping.key).desired_width(220.0).font(mono.clone()).hint_text(t!("targets.key_hint")));
+                                    if ui.button(RichText::new("✕").color(colors.red)).clicked() {
+                                        removed_mapping = Some(row);
+                                    }
+                                });
+                            }
+                            if let Some(row) = removed_mapping {
+                                target.mappings.remove(row);
+                            }
+                            ui.horizontal(|ui| {
+                                if ui.button(RichText::new(t!("targets.add_mapping")).size(11.0).color(add_color)).clicked() {
+                                    target.mappings.push(Mapping::default());
+                                }
+                                if ui.button(RichText::new(t!("targets.remove")).size(11.0).color(colors.red)).clicked() {
+                                    removed = Some(index);
+                                }
+                            });
+                        });
+                    }
+                });
+                if let Some(index) = removed {
+                    self.targets_draft.remove(index);
+                    self.refresh_targets_status();
+                }
+
+                ui.add_space(8.0);
+                ui.horizontal(|ui| {
+                    if ui.button(RichText::new(t!("targets.add")).color(add_color)).clicked() {
+                        self.targets_draft.push(ToolTarget::default());
+                        self.refresh_targets_status();
+                    }
+                    if ui.button(t!("targets.refresh")).clicked() {
+                        self.refresh_targets_status();
+                    }
+                });
+
+                ui.add_space(15.0);
+                ui.horizontal(|ui| {
+                    if ui.button(RichText::new(t!("mcp.save")).color(colors.green)).clicked() {
+                        self.save_targets_draft();
+                    }
+                    if ui.button(RichText::new(t!("button.cancel")).color(self.get_button_color("secondary"))).clicked() {
+                        self.show_targets_dialog = false;
+                    }
+                });
+            });
+    }
+
+    // --- 用量统计相关方法 ---
+    fn open_usage_dialog(&mut self) {
+        let pricing_path = self.switcher.paths().pricing_file();
+        self.price_table = PriceTable::load(&pricing_path);
+        if !pricing_path.exists() {
+            // 首次打开时写出默认价格表，方便用户修改
+            if let Err(e) = self.price_table.save(&pricing_path) {
+                self.show_toast(t!("toast.pricing_save_failed", error = e), ToastKind::Warning);
+            }
+        }
+        self.show_usage_dialog = true;
+        self.reload_usage();
+    }
+
+    // 在后台线程扫描会话记录，避免阻塞界面
+    fn reload_usage(&mut self) {
+        let projects_dir = self.switcher.paths().claude_projects_dir();
+        let (sender, receiver) = mpsc::channel();
+        std::thread::spawn(move || {
+            let _ = sender.send(usage::scan_transcripts(&projects_dir));
+        });
+        self.usage_receiver = Some(receiver);
+    }
+
+    fn poll_usage(&mut self) {
+        if let Some(receiver) = &self.usage_receiver {
+            if let Ok(records) = receiver.try_recv() {
+                self.usage_records = records;
+                self.usage_receiver = None;
+                self.refresh_usage_rows();
+            }
+        }
+    }
+
+    fn refresh_usage_rows(&mut self) {
+        let history = audit::SwitchHistory::load(&self.switcher.audit_log_path());
+        let since = self.usage_range_days.map(|days| chrono::Utc::now() - chrono::Duration::days(days));
+        self.usage_rows = usage::aggregate(&self.usage_records, &history, &self.price_table, self.usage_group_by, since);
+    }
+
+    fn export_usage_csv(&mut self) {
+        let default_name = format!("claude-usage-{}.csv", Local::now().format("%Y%m%d"));
+        if let Some(path) = rfd::FileDialog::new()
+            .set_title(t!("usage.export_title"))
+            .set_file_name(default_name)
+            .add_filter("CSV", &["csv"])
+            .save_file() {
+            match fs::write(&path, usage::to_csv(&self.usage_rows, self.usage_group_by)) {
+                Ok(_) => self.show_toast(t!("toast.exported", path = path.to_string_lossy()), ToastKind::Success),
+                Err(e) => self.show_toast(t!("toast.csv_export_failed", error = e), ToastKind::Error),
+            }
+        }
+    }
+
+    fn show_usage_window(&mut self, ctx: &egui::Context) {
+        let colors = self.get_theme_colors();
+        let mut rows_changed = false;
+
+        egui::Window::new(t!("usage.title"))
+            .collapsible(false)
+            .resizable(true)
+            .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+            .default_size([720.0, 520.0])
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    ui.label(t!("usage.group_by"));
+                    for group_by in [GroupBy::Profile, GroupBy::Day, GroupBy::Project] {
+                        if ui.selectable_label(self.usage_group_by == group_by, group_by.label()).clicked() {
+                            self.usage_group_by = group_by;
+                            rows_changed = true;
+                        }
+                    }
+                    ui.separator();
+                    ui.label(t!("usage.range"));
+                    for (days, label) in [(Some(7), t!("usage.range.7")), (Some(30), t!("usage.range.30")), (Some(90), t!("usage.range.90")), (None, t!("common.all"))] {
+                        if ui.selectable_label(self.usage_range_days == days, label).clicked() {
+                            self.usage_range_days = days;
+                            rows_changed = true;
+                        }
+                    }
+                    ui.separator();
+                    ui.checkbox(&mut self.usage_chart_tokens, t!("usage.chart_tokens"));
+                });
+
+                ui.horizontal(|ui| {
+                    if ui.button(RichText::new(t!("usage.rescan")).color(self.get_button_color("refresh"))).clicked() {
+                        self.reload_usage();
+                    }
+                    if ui.button(RichText::new(t!("usage.export_csv")).color(self.get_button_color("save"))).clicked() {
+                        self.export_usage_csv();
+                    }
+                    if ui.button(RichText::new(t!("usage.prices")).color(self.get_button_color("settings"))).clicked() {
+                        self.show_price_editor = !self.show_price_editor;
+                    }
+                    if self.usage_receiver.is_some() {
+                        ui.spinner();
+                        ui.label(RichText::new(t!("usage.scanning")).color(colors.muted));
+                    }
+                });
+
+                if self.show_price_editor {
+                    ui.group(|ui| {
+                        ui.label(RichText::new(t!("usage.prices_help")).size(12.0).color(colors.muted));
+                        let mut remove = None;
+                        egui::Grid::new("price_table_grid").striped(true).show(ui, |ui| {
+                            for header in [t!("usage.col.pattern"), t!("usage.col.input"), t!("usage.col.output"), t!("usage.col.cache_write"), t!("usage.col.cache_read"), ""] {
+                                ui.label(RichText::new(header).strong());
+                            }
+                            ui.end_row();
+                            for (index, price) in self.price_table.models.iter_mut().enumerate() {
+                                ui.add(TextEdit::singleline(&mut price.pattern).desired_width(100.0));
+                                for value in [&mut price.input, &mut price.output, &mut price.cache_write, &mut price.cache_read] {
+                                    ui.add(egui::DragValue::new(value).speed(0.01).clamp_range(0.0..=1000.0));
+                                }
+                                if ui.button(RichText::new("×").color(colors.red)).clicked() {
+                                    remove = Some(index);
+                                }
+                                ui.end_row();
+                            }
+                        });
+                        if let Some(index) = remove {
+                            self.price_table.models.remove(index);
+                        }
+                        ui.horizontal(|ui| {
+                            if ui.button(RichText::new(t!("usage.add_model")).color(self.get_button_color("add"))).clicked() {
+                                self.price_table.models.push(usage::ModelPrice {
+                                    pattern: String::new(), input: 0.0, output: 0.0, cache_write: 0.0, cache_read: 0.0,
+                                });
+                            }
+                            if ui.button(RichText::new(t!("usage.save_prices")).color(colors.green)).clicked() {
+                                match self.price_table.save(&self.switcher.paths().pricing_file()) {
+                                    Ok(_) => self.show_toast(t!("toast.pricing_saved"), ToastKind::Success),
+                                    Err(e) => self.show_toast(t!("toast.pricing_save_failed", error = e), ToastKind::Error),
+                                }
+                                rows_changed = true;
+                            }
+                        });
+                    });
+                }
+
+                ui.add_space(5.0);
+                let total = self.usage_rows.iter().fold(UsageRow::default(), |mut total, row| {
+                    total.sessions += row.sessions;
+                    total.input += row.input;
+                    total.output += row.output;
+                    total.cache_write += row.cache_write;
+                    total.cache_read += row.cache_read;
+                    total.cost += row.cost;
+                    total
+                });
+                ui.label(RichText::new(t!(
+                    "usage.total",
+                    input = usage::format_tokens(total.input),
+                    output = usage::format_tokens(total.output),
+                    cache_write = usage::format_tokens(total.cache_write),
+                    cache_read = usage::format_tokens(total.cache_read),
+                    cost = format!("{:.2}", total.cost),
+                )).strong());
+                ui.add_space(5.0);
+
+                egui::ScrollArea::vertical().id_source("usage_scroll").auto_shrink([false; 2]).show(ui, |ui| {
+                    self.draw_usage_chart(ui);
+                    ui.add_space(10.0);
+                    egui::Grid::new("usage_grid").striped(true).min_col_width(60.0).show(ui, |ui| {
+                        for header in [self.usage_group_by.label(), t!("usage.col.sessions"), t!("usage.col.input"), t!("usage.col.output"), t!("usage.col.cache_write"), t!("usage.col.cache_read"), t!("usage.col.cost")] {
+                            ui.label(RichText::new(header).strong());
+                        }
+                        ui.end_row();
+                        for row in &self.usage_rows {
+                            ui.label(&row.key);
+                            ui.label(row.sessions.to_string());
+                            ui.label(usage::format_tokens(row.input));
+                            ui.label(usage::format_tokens(row.output));
+                            ui.label(usage::format_tokens(row.cache_write));
+                            ui.label(usage::format_tokens(row.cache_read));
+                            ui.label(format!("${:.2}", row.cost));
+                            ui.end_row();
+                        }
+                    });
+                });
+
+                ui.add_space(10.0);
+                if ui.button(RichText::new(t!("button.close")).color(self.get_button_color("secondary"))).clicked() {
+                    self.show_usage_dialog = false;
+                }
+            });
+
+        if rows_changed {
+            self.refresh_usage_rows();
+        }
+    }
+
+    // 一个编辑器窗格：行号和带 JSON 语法高亮的编辑区
+    fn show_editor_pane(&mut self, ui: &mut egui::Ui, pane: Pane) {
+        let palette = self.palette();
+        let (colors, syntax) = (palette.colors, palette.syntax);
+        let Some(document) = self.workspace.get_mut(pane) else {
+            ui.centered_and_justified(|ui| {
+                ui.label(RichText::new(t!("editor.placeholder")).size(16.0).color(colors.text.linear_multiply(0.7)));
+            });
+            return;
+        };
+        let editor_id = document.editor_id(pane);
+        let mut focused = false;
+        let mut document_edited = false;
+
+        // 行号栏放在滚动区域内，与文本一起滚动；只绘制可见的行
+        let Document { content, highlighter, cursor: last_cursor, reveal, .. } = document;
+        highlighter.update(content);
+        let digits = highlighter.line_count().to_string().len().max(3);
+        let gutter_width = digits as f32 * 8.0 + 30.0;
+
+        // 输入右括号或引号时，如果光标后正好是同一个字符就跳过它
+        let mut typed = None;
+        if ui.memory(|m| m.has_focus(editor_id)) {
+            typed = ui.input(|i| {
+                i.events.iter().find_map(|event| match event {
+                    egui::Event::Text(text) if text.chars().count() == 1 => text.chars().next(),
+                    _ => None,
+                })
+            });
+            if let (Some(ch), Some(mut state)) = (typed, egui::text_edit::TextEditState::load(ui.ctx(), editor_id)) {
+                let cursor = state.cursor.char_range().filter(|range| range.primary == range.secondary).map(|range| range.primary.index);
+                if let Some(cursor) = cursor.filter(|&cursor| highlight::skips_closing(content, cursor, ch)) {
+                    ui.input_mut(|i| i.events.retain(|event| !matches!(event, egui::Event::Text(_))));
+                    state.cursor.set_char_range(Some(egui::text::CCursorRange::one(egui::text::CCursor::new(cursor + 1))));
+                    state.store(ui.ctx(), editor_id);
+                    typed = None;
+                }
+            }
+        }
+
+        egui::ScrollArea::vertical()
+            .auto_shrink([false; 2])
+            .id_source(("editor_scroll", pane))
+            .show(ui, |ui| {
+                ui.add_space(10.0);
+                ui.horizontal_top(|ui| {
+                    ui.spacing_mut().item_spacing.x = 0.0;
+                    let (gutter, _) = ui.allocate_exact_size(egui::vec2(gutter_width, 0.0), egui::Sense::hover());
+
+                    let mut layouter = |ui: &egui::Ui, string: &str, wrap_width: f32| {
+                        highlighter.layout(ui, string, wrap_width, pane, syntax, colors.text)
+                    };
+                    let output = TextEdit::multiline(content)
+                        .id(editor_id)
+                        .font(egui::FontId::monospace(highlight::FONT_SIZE))
+                        .code_editor()
+                        .desired_width(f32::INFINITY)
+                        .frame(false)
+                        .layouter(&mut layouter)
+                        .show(ui);
+
+                    let cursor = output.cursor_range.filter(|_| output.response.has_focus()).map(|range| range.primary.ccursor.index);
+                    if output.response.changed() {
+                        document_edited = true;
+                        // 输入左括号或引号后补上右半部分，光标留在中间
+                        if let (Some(ch), Some(cursor)) = (typed, cursor) {
+                            if highlight::auto_close(content, cursor, ch) {
+                                ui.ctx().request_repaint();
+                            }
+                        }
+                    }
+                    // 分屏时点击或输入的窗格获得焦点
+                    focused = output.response.has_focus() || output.response.clicked();
+                    if cursor.is_some() {
+                        *last_cursor = cursor;
+                    }
+
+                    // 从大纲或路径导航跳转：移动光标并滚动到目标位置
+                    if let Some(offset) = reveal.take() {
+                        let index = highlighter.byte_to_char(offset);
+                        highlighter.unfold_line(highlighter.line_of_char(index));
+                        let mut state = output.state.clone();
+                        state.cursor.set_char_range(Some(egui::text::CCursorRange::one(egui::text::CCursor::new(index))));
+                        state.store(ui.ctx(), editor_id);
+                        ui.memory_mut(|m| m.request_focus(editor_id));
+                        let target = output.galley.pos_from_ccursor(egui::text::CCursor::new(index)).translate(output.galley_pos.to_vec2());
+                        ui.scroll_to_rect(target.expand(20.0), Some(Align::Center));
+                        *last_cursor = Some(index);
+                        ui.ctx().request_repaint();
+                    }
+
+                    // 光标移入折叠的内容时展开
+                    if let Some(cursor) = cursor {
+                        let line = highlighter.line_of_char(cursor);
+                        if highlighter.is_hidden(line) {
+                            highlighter.unfold_line(line);
+                            ui.ctx().request_repaint();
+                        }
+                    }
+
+                    // 可见的行及其在屏幕上占的区域，从第一个可见的行开始，折叠隐藏的行不包含在内
+                    let visible = ui.clip_rect();
+                    let offset = output.galley_pos.to_vec2();
+                    let visible_lines: Vec<(usize, egui::Rect)> = highlighter
+                        .visible_lines(pane, visible.top() - offset.y, visible.bottom() - offset.y)
+                        .into_iter()
+                        .map(|(line, rect)| (line, rect.translate(offset)))
+                        .collect();
+
+                    let painter = ui.painter().clone();
+                    let space_width = ui.fonts(|f| f.glyph_width(&egui::FontId::monospace(highlight::FONT_SIZE), ' '));
+                    let rect_of = |line: usize| visible_lines.binary_search_by_key(&line, |(l, _)| *l).ok().map(|i| visible_lines[i].1);
+
+                    // 缩进参考线：画在区域起始行的缩进位置，贯穿区域内部的行
+                    if let (Some(&(first, _)), Some(&(last, _))) = (visible_lines.first(), visible_lines.last()) {
+                        for (&start, &end) in highlighter.regions().range(..=last) {
+                            if end < first || end <= start + 1 || highlighter.is_folded(start) {
+                                continue;
+                            }
+                            let x = output.galley_pos.x + highlighter.indent_columns(start) as f32 * space_width + 0.5;
+                            let top = rect_of(start).map_or(visible.top(), |r| r.bottom());
+                            let bottom = rect_of(end).map_or(visible.bottom(), |r| r.top());
+                            if bottom > top {
+                                painter.line_segment([egui::pos2(x, top), egui::pos2(x, bottom)], egui::Stroke::new(1.0, colors.border.linear_multiply(0.6)));
+                            }
+                        }
+                    }
+
+                    let mut toggled = None;
+                    for &(line, rect) in &visible_lines {
+                        painter.text(
+                            egui::pos2(gutter.right() - 18.0, rect.top()),
+                            egui::Align2::RIGHT_TOP,
+                            (line + 1).to_string(),
+                            egui::FontId::monospace(13.0),
+                            Color32::from_rgb(120, 120, 120),
+                        );
+                        if !highlighter.regions().contains_key(&line) {
+                            continue;
+                        }
+                        // 折叠按钮；折叠后在行尾显示省略标记，点击同样展开
+                        let folded = highlighter.is_folded(line);
+                        let toggle_rect = egui::Rect::from_min_size(egui::pos2(gutter.right() - 16.0, rect.top()), egui::vec2(14.0, rect.height()));
+                        let toggle = ui.interact(toggle_rect, editor_id.with(("fold", line)), egui::Sense::click());
+                        let toggle_color = if toggle.hovered() { colors.text } else { colors.muted };
+                        painter.text(toggle_rect.left_top(), egui::Align2::LEFT_TOP, if folded { "▸" } else { "▾" }, egui::FontId::proportional(12.0), toggle_color);
+                        if toggle.on_hover_text(if folded { t!("editor.unfold") } else { t!("editor.fold") }).clicked() {
+                            toggled = Some(line);
+                        }
+                        if folded {
+                            let badge = egui::Rect::from_min_size(egui::pos2(rect.right() + 6.0, rect.top() + 1.0), egui::vec2(22.0, rect.height() - 2.0));
+                            painter.rect_filled(badge, 3.0, colors.surface1);
+                            painter.text(badge.center(), egui::Align2::CENTER_CENTER, "⋯", egui::FontId::monospace(12.0), colors.muted);
+                            if ui.interact(badge, editor_id.with(("fold_badge", line)), egui::Sense::click()).clicked() {
+                                toggled = Some(line);
+                            }
+                        }
+                    }
+                    if let Some(line) = toggled {
+                        highlighter.toggle_fold(line);
+                    }
+
+                    // 光标旁括号及与之匹配的括号加框
+                    if let Some((a, b)) = cursor.and_then(|cursor| highlighter.matching_bracket(cursor)) {
+                        for index in [a, b] {
+                            let pos = output.galley.pos_from_ccursor(egui::text::CCursor::new(index));
+                            if pos.height() < 1.0 {
+                                continue;
+                            }
+                            let rect = egui::Rect::from_min_size(output.galley_pos + pos.min.to_vec2(), egui::vec2(space_width, pos.height()));
+                            painter.rect_stroke(rect, 2.0, egui::Stroke::new(1.0, colors.accent));
+                        }
+                    }
+                });
+            });
+
+        if document_edited {
+            if let Some(document) = self.workspace.get_mut(pane) {
+                document.mark_edited();
+            }
+        }
+        if focused {
+            self.workspace.set_focus(pane);
+        }
+    }
+
+    // 光标处的 JSON 路径，点击其中一段跳到对应节点
+    fn show_breadcrumb(&mut self, ui: &mut egui::Ui) {
+        let colors = self.get_theme_colors();
+        let Some(document) = self.workspace.active_mut() else { return };
+        document.refresh_outline();
+        let path = document.cursor_path();
+        if path.is_empty() {
+            return;
+        }
+        ui.separator();
+        let mut target = None;
+        for (depth, segment) in path.iter().enumerate() {
+            if depth > 0 {
+                ui.label(RichText::new("›").color(colors.muted));
+            }
+            let label = egui::Label::new(RichText::new(segment.to_string()).monospace().size(12.0)).sense(egui::Sense::click());
+            if ui.add(label).on_hover_text(outline::path_string(&path[..=depth])).clicked() {
+                target = document.outline.as_ref().and_then(|o| o.node(&path[..=depth])).map(|node| node.range.start);
+            }
+        }
+        if target.is_some() {
+            document.reveal = target;
+        }
+    }
+
+    // 大纲树：点击节点跳到对应位置，右键菜单可以删除、复制节点或新增同级节点
+    fn show_outline_panel(&mut self, ui: &mut egui::Ui) {
+        let colors = self.get_theme_colors();
+        let Some(document) = self.workspace.active_mut() else { return };
+        document.refresh_outline();
+        ui.label(RichText::new(t!("outline.title")).strong());
+        if document.outline_stale {
+            ui.label(RichText::new(t!("outline.stale")).size(11.0).color(colors.yellow));
+        }
+        ui.separator();
+        let Some(outline) = &document.outline else {
+            ui.label(RichText::new(t!("outline.invalid")).color(colors.muted));
+            return;
+        };
+
+        let current = document.cursor_path();
+        let mut request = None;
+        egui::ScrollArea::both().id_source("outline_scroll").auto_shrink([false; 2]).show(ui, |ui| {
+            let mut path = Vec::new();
+            for child in &outline.root.children {
+                outline_node(ui, child, &mut path, &current, !document.outline_stale, &colors, &mut request);
+            }
+        });
+
+        match request {
+            Some(OutlineRequest::Reveal(offset)) => document.reveal = Some(offset),
+            Some(OutlineRequest::Edit(path, edit)) => {
+                if let Some((content, offset)) = outline.edit(&document.content, &path, edit) {
+                    document.content = content;
+                    document.mark_edited();
+                    document.reveal = Some(offset);
+                }
+            }
+            None => {}
+        }
+    }
+
+    // 当前配置的检查结果，错误在前，每条可能附带修复按钮
+    fn show_lint_panel(&mut self, ui: &mut egui::Ui) {
+        let colors = self.get_theme_colors();
+        let Some(document) = self.workspace.active() else { return };
+        let mut chosen_fix: Option<Fix> = None;
+        egui::ScrollArea::vertical().id_source("lint_panel_scroll").max_height(110.0).show(ui, |ui| {
+            for finding in &document.findings {
+                ui.horizontal(|ui| {
+                    let color = match finding.severity {
+                        Severity::Error => colors.red,
+                        Severity::Warning => colors.yellow,
+                    };
+                    ui.label(RichText::new(finding.severity.label()).color(color).strong().size(12.0));
+                    if let Some(line) = finding.line {
+                        ui.label(RichText::new(t!("lint.line", line = line)).color(colors.muted).size(12.0));
+                    }
+                    ui.label(RichText::new(&finding.message).size(12.0));
+                    for fix in &finding.fixes {
+                        if ui.small_button(fix.label()).clicked() {
+                            chosen_fix = Some(fix.clone());
+                        }
+                    }
+                });
+            }
+        });
+        let Some(fix) = chosen_fix else { return };
+        let Some(document) = self.workspace.active_mut() else { return };
+        match lint::apply_fix(&document.content, &fix, &self.switcher.settings().format) {
+            Ok(content) => {
+                document.content = content;
+                document.mark_edited();
+                document.relint();
+            }
+            Err(e) => self.show_toast(e.to_string(), ToastKind::Error),
+        }
+    }
+
+    fn show_lint_override_window(&mut self, ctx: &egui::Context) {
+        let colors = self.get_theme_colors();
+        let Some((path, findings)) = &self.lint_override else { return };
+        let name = path.file_name().unwrap_or_default().to_string_lossy().to_string();
+        let mut force = false;
+        let mut cancel = false;
+        egui::Window::new(t!("lint.override_title"))
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+            .show(ctx, |ui| {
+                ui.label(t!("lint.override_prompt", name = name));
+                ui.add_space(5.0);
+                for finding in findings.iter().filter(|f| f.severity == Severity::Error) {
+                    ui.label(RichText::new(format!("• {}", finding.message)).color(colors.red));
+                }
+                ui.add_space(10.0);
+                ui.horizontal(|ui| {
+                    if ui.button(RichText::new(t!("lint.apply_anyway")).color(colors.red)).clicked() {
+                        force = true;
+                    }
+                    if ui.button(RichText::new(t!("button.cancel")).color(self.get_button_color("secondary"))).clicked() {
+                        cancel = true;
+                    }
+                });
+            });
+        if force {
+            if let Some((path, _)) = self.lint_override.take() {
+                let _ = self.set_as_default(path, true);
+            }
+        } else if cancel {
+            self.lint_override = None;
+        }
+    }
+
+    // 分屏时窗格上方显示文档名，获得焦点的窗格高亮
+    fn show_pane_header(&mut self, ui: &mut egui::Ui, pane: Pane) {
+        let colors = self.get_theme_colors();
+        let is_focused = self.workspace.focus() == pane;
+        let name = match self.workspace.get(pane) {
+            Some(document) if document.is_modified() => t!("title.unsaved", name = document.name()),
+            Some(document) => format!("◇ {}", document.name()),
+            None => return,
+        };
+        let color = if is_focused { colors.lavender } else { colors.muted };
+        if ui.add(egui::Label::new(RichText::new(name).size(12.5).color(color).strong()).sense(egui::Sense::click())).clicked() {
+            self.workspace.set_focus(pane);
+        }
+        ui.add_space(4.0);
+    }
+
+    // 标签页栏：点击切换到获得焦点的窗格，× 关闭
+    fn show_tab_bar(&mut self, ui: &mut egui::Ui) {
+        let colors = self.get_theme_colors();
+        let active = self.workspace.active().map(|d| d.id);
+        let mut activate = None;
+        let mut close = None;
+        egui::ScrollArea::horizontal().id_source("editor_tabs_scroll").show(ui, |ui| {
+            ui.horizontal(|ui| {
+                for document in self.workspace.documents() {
+                    let label = if document.is_modified() { format!("● {}", document.name()) } else { document.name() };
+                    let text = RichText::new(label).size(12.5).color(if document.is_modified() { self.get_button_color("warning") } else { colors.text });
+                    if ui.selectable_label(active == Some(document.id), text).clicked() {
+                        activate = Some(document.path.clone());
+                    }
+                    if ui.small_button(RichText::new("×").color(colors.muted)).on_hover_text(t!("tab.close")).clicked() {
+                        close = Some(document.path.clone());
+                    }
+                    ui.add_space(6.0);
+                }
+            });
+        });
+        if let Some(path) = activate {
+            self.workspace.activate(&path);
+        }
+        if let Some(path) = close {
+            self.guard_unsaved(vec![path.clone()], PendingAction::CloseTab(path), ui.ctx());
+        }
+    }
+
+    // 水平条形图：按费用或 Token 总量
+    fn draw_usage_chart(&self, ui: &mut egui::Ui) {
+        let colors = self.get_theme_colors();
+        let value = |row: &UsageRow| if self.usage_chart_tokens { row.total_tokens() as f64 } else { row.cost };
+        let max_value = self.usage_rows.iter().map(value).fold(0.0, f64::max);
+        if max_value <= 0.0 {
+            ui.label(RichText::new(t!("usage.empty")).color(colors.muted));
+            return;
+        }
+
+        let label_width = 180.0;
+        let bar_height = 16.0;
+        let width = ui.available_width();
+        for row in &self.usage_rows {
+            let (rect, response) = ui.allocate_exact_size(egui::vec2(width, bar_height + 4.0), egui::Sense::hover());
+            let painter = ui.painter();
+            let label = if row.key.chars().count() > 24 {
+                format!("…{}", row.key.chars().rev().take(23).collect::<Vec<_>>().into_iter().rev().collect::<String>())
+            } else {
+                row.key.clone()
+            };
+            painter.text(rect.left_center(), egui::Align2::LEFT_CENTER, label, egui::FontId::proportional(12.0), colors.text);
+
+            let bar_max = (width - label_width - 90.0).max(10.0);
+            let bar_width = (value(row) / max_value) as f32 * bar_max;
+            let bar_rect = egui::Rect::from_min_size(
+                egui::pos2(rect.left() + label_width, rect.center().y - bar_height / 2.0),
+                egui::vec2(bar_width.max(1.0), bar_height),
+            );
+            painter.rect_filled(bar_rect, egui::Rounding::same(3.0), colors.accent);
+            let text = if self.usage_chart_tokens { usage::format_tokens(row.total_tokens()) } else { format!("${:.2}", row.cost) };
+            painter.text(egui::pos2(bar_rect.right() + 6.0, rect.center().y), egui::Align2::LEFT_CENTER, text, egui::FontId::proportional(12.0), colors.muted);
+            response.on_hover_text(&row.key);
+        }
+    }
+}
+
+// 大纲面板中点击节点或菜单项产生的请求，在绘制完大纲后处理
+enum OutlineRequest {
+    Reveal(usize),
+    Edit(Vec<Segment>, Edit),
+}
+
+// 大纲中的一个节点，对象和数组可以展开；光标所在的节点高亮
+fn outline_node(ui: &mut egui::Ui, node: &Node, path: &mut Vec<Segment>, current: &[Segment], editable: bool, colors: &theme::ThemeColors, request: &mut Option<OutlineRequest>) {
+    path.extend(node.segment.clone());
+    let selected = path.as_slice() == current;
+    let mut text = egui::text::LayoutJob::default();
+    let name = node.segment.as_ref().map(Segment::to_string).unwrap_or_default();
+    text.append(&name, 0.0, egui::TextFormat { font_id: egui::FontId::monospace(12.0), color: colors.text, ..Default::default() });
+    let (summary, color) = match node.kind {
+        Kind::Object => (format!(" {{{}}}", node.children.len()), colors.muted),
+        Kind::Array => (format!(" [{}]", node.children.len()), colors.muted),
+        _ => {
+            let preview: String = node.preview.chars().take(40).collect();
+            let ellipsis = if preview.len() < node.preview.len() { "…" } else { "" };
+            (format!(": {}{}", preview, ellipsis), colors.muted)
+        }
+    };
+    text.append(&summary, 0.0, egui::TextFormat { font_id: egui::FontId::monospace(12.0), color, ..Default::default() });
+
+    let mut label = |ui: &mut egui::Ui| {
+        let response = ui.selectable_label(selected, text.clone());
+        if response.clicked() {
+            *request = Some(OutlineRequest::Reveal(node.range.start));
+        }
+        response.context_menu(|ui| {
+            for (edit, label) in [(Edit::Duplicate, t!("outline.duplicate")), (Edit::AddSibling, t!("outline.add_sibling")), (Edit::Delete, t!("outline.delete"))] {
+                if ui.add_enabled(editable, egui::Button::new(label)).clicked() {
+                    *request = Some(OutlineRequest::Edit(path.clone(), edit));
+                    ui.close_menu();
+                }
+            }
+        });
+    };
+
+    if node.children.is_empty() {
+        ui.horizontal(|ui| {
+            ui.add_space(18.0);
+            label(ui);
+        });
+    } else {
+        let id = ui.make_persistent_id(("outline", path.as_slice()));
+        egui::collapsing_header::CollapsingState::load_with_default_open(ui.ctx(), id, path.len() == 1)
+            .show_header(ui, |ui| label(ui))
+            .body(|ui| {
+                for child in &node.children {
+                    outline_node(ui, child, path, current, editable, colors, request);
+                }
+            });
+    }
+    path.truncate(path.len() - usize::from(node.segment.is_some()));
+}
+
+// 按变量类型显示控件，直接修改 env 草稿；关闭、清空或选择“未设置”时删除该变量
+fn env_var_control(ui: &mut egui::Ui, var: &EnvVar, env: &mut serde_json::Map<String, Value>, colors: &ThemeColors) {
+    let current = env.get(var.name).and_then(Value::as_str).map(str::to_string);
+    let mut update = None;
+    ui.horizontal(|ui| {
+        match var.kind {
+            env_vars::Kind::Flag => {
+                let mut on = current.as_deref().is_some_and(env_vars::is_truthy);
+                if ui.checkbox(&mut on, RichText::new(var.name).monospace()).changed() {
+                    update = Some(on.then(|| "1".to_string()));
+                }
+            }
+            env_vars::Kind::Integer { min, max } => {
+                let mut set = current.is_some();
+                let fallback = var.default.and_then(|d| d.parse().ok()).unwrap_or(min);
+                let mut number: i64 = current.as_deref().and_then(|v| v.trim().parse().ok()).unwrap_or(fallback);
+                let toggled = ui.checkbox(&mut set, RichText::new(var.name).monospace()).changed();
+                let edited = ui.add_enabled(set, egui::DragValue::new(&mut number).clamp_range(min..=max).speed(((max - min) as f64 / 1000.0).max(1.0))).changed();
+                if toggled || edited {
+                    update = Some(set.then(|| number.to_string()));
+                }
+            }
+            env_vars::Kind::Choice(options) => {
+                ui.label(RichText::new(var.name).monospace());
+                let mut selected = current.clone();
+                egui::ComboBox::from_id_source(("env_choice", var.name))
+                    .selected_text(selected.as_deref().unwrap_or(t!("env_vars.unset")))
+                    .width(100.0)
+                    .show_ui(ui, |ui| {
+                        ui.selectable_value(&mut selected, None, t!("env_vars.unset"));
+                        for option in options {
+                            ui.selectable_value(&mut selected, Some(option.to_string()), *option);
+                        }
+                    });
+                if selected != current {
+                    update = Some(selected);
+                }
+            }
+            env_vars::Kind::Text => {
+                ui.label(RichText::new(var.name).monospace());
+                let mut text = current.clone().unwrap_or_default();
+                let secret = var.name.ends_with("_KEY") || var.name.ends_with("_TOKEN") || var.name.ends_with("_BEDROCK");
+                if ui.add(TextEdit::singleline(&mut text).desired_width(280.0).password(secret).hint_text(t!("env_vars.unset"))).changed() {
+                    update = Some((!text.is_empty()).then_some(text));
+                }
+            }
+        }
+        if let Some(default) = var.default {
+            ui.label(RichText::new(t!("env_vars.default", value = default)).size(11.0).color(colors.muted));
+        }
+    });
+    ui.label(RichText::new(var.description()).size(11.0).color(colors.muted));
+    match update {
+        Some(Some(value)) => {
+            env.insert(var.name.to_string(), Value::String(value));
+        }
+        Some(None) => {
+            env.shift_remove(var.name);
+        }
+        None => {}
+    }
+}
+
+// 试运行结果：退出码 0 表示通过，2 表示阻止（Claude Code 会把 stderr 反馈给模型）
+fn show_dry_run(ui: &mut egui::Ui, result: &Result<DryRun, String>, colors: &ThemeColors) {
+    let run = match result {
+        Ok(run) => run,
+        Err(e) => {
+            ui.label(RichText::new(e).size(11.0).color(colors.red));
+            return;
+        }
+    };
+    let ms = run.duration.as_millis();
+    let (status, color) = match run.exit_code {
+        _ if run.timed_out => (t!("hooks.result.timed_out", ms = ms), colors.red),
+        Some(0) => (t!("hooks.result.success", ms = ms), colors.green),
+        Some(2) => (t!("hooks.result.blocking", ms = ms), colors.red),
+        Some(code) => (t!("hooks.result.exit_code", code = code, ms = ms), colors.yellow),
+        None => (t!("hooks.result.killed", ms = ms), colors.red),
+    };
+    ui.label(RichText::new(status).size(11.0).color(color));
+    for (label, output) in [("stdout", &run.stdout), ("stderr", &run.stderr)] {
+        if output.trim().is_empty() {
+            continue;
+        }
+        // 输出很长时只显示开头
+        let text: String = output.chars().take(2000).collect();
+        ui.label(RichText::new(label).size(11.0).color(colors.muted));
+        ui.label(RichText::new(text.trim_end()).font(egui::FontId::monospace(12.0)));
+    }
+}
+
+// 无界面模式：按定时规则切换配置，直到进程退出
+fn run_headless() {
+    let mut app = ConfigManagerApp::new_headless();
+    println!("{}", t!("headless.started", count = app.schedule.rules.len()));
+    if let Some(server) = &app.control_server {
+        println!("{}", t!("headless.control_socket", path = server.socket_path().to_string_lossy()));
+    }
+    let mut last_next_switch = None;
+    let schedule_file = app.switcher.paths().schedule_file();
+    let mut schedule_modified = fs::metadata(&schedule_file).and_then(|m| m.modified()).ok();
+    loop {
+        // 运行期间修改了定时规则文件时重新加载
+        let modified = fs::metadata(&schedule_file).and_then(|m| m.modified()).ok();
+        if modified != schedule_modified {
+            schedule_modified = modified;
+            app.reload_schedule();
+            println!("{}", t!("headless.schedule_reloaded", count = app.schedule.rules.len()));
+        }
+        app.tick_schedule();
+        print_headless_toasts(&mut app);
+        let next_switch = app.next_switch_text();
+        if next_switch != last_next_switch {
+            if let Some(text) = &next_switch {
+                println!("{}", text);
+            }
+            last_next_switch = next_switch;
+        }
+        // 等到下一分钟开始后一秒，期间处理控制接口请求
+        let seconds = 61 - Local::now().second().min(59) as u64;
+        let deadline = Instant::now() + Duration::from_secs(seconds);
+        while let Some(remaining) = deadline.checked_duration_since(Instant::now()) {
+            match &app.control_server {
+                Some(server) => {
+                    if let Some(request) = server.recv_timeout(remaining) {
+                        app.handle_control_request(request);
+                        print_headless_toasts(&mut app);
+                    }
+                }
+                None => std::thread::sleep(remaining),
+            }
+        }
+    }
+}
+
+// 无界面模式下把提示消息输出到标准输出
+fn print_headless_toasts(app: &mut ConfigManagerApp) {
+    app.poll_core_events();
+    while let Some(toast) = app.toasts.pop_front() {
+        let label = match toast.kind {
+            ToastKind::Success => t!("toast.label.success"),
+            ToastKind::Error => t!("toast.label.error"),
+            ToastKind::Warning => t!("toast.label.warning"),
+        };
+        println!("{} {} {}", Local::now().format("%Y-%m-%d %H:%M:%S"), label, toast.content);
+    }
+}
+
+impl App for ConfigManagerApp {
+    fn update(&mut self, ctx: &egui::Context, _frame: &mut Frame) {
+        self.poll_themes(ctx);
+        let palette = self.palette();
+        let colors = palette.colors;
+        let alt_row = palette.alt_row;
+
+        self.tick_schedule();
+        self.tick_autosave();
+        self.poll_usage();
+        self.poll_control_requests();
+        self.poll_core_events();
+        self.poll_connection_test();
+        self.poll_hook_run();
+        self.poll_models();
+
+        // 关闭窗口前确认未保存的修改
+        if ctx.input(|i| i.viewport().close_requested()) && !self.close_confirmed {
+            let modified = self.workspace.modified_paths();
+            if !modified.is_empty() {
+                ctx.send_viewport_cmd(egui::ViewportCommand::CancelClose);
+                self.unsaved_guard = Some(UnsavedGuard { paths: modified, action: PendingAction::CloseWindow });
+            }
+        }
+
+        // 处理快捷键
+        if ctx.input(|i| i.modifiers.command && (i.key_pressed(egui::Key::K) || i.key_pressed(egui::Key::P))) {
+            self.command_palette.toggle();
+        }
+        if ctx.input(|i| i.key_pressed(egui::Key::S) && i.modifiers.command) {
+            if self.workspace.active().is_some() {
+                self.save_current_file();
+            }
+        }
+
+        egui::CentralPanel::default().frame(egui::Frame::default().fill(colors.base)).show(ctx, |ui| {
+            egui::TopBottomPanel::bottom("status_bar").frame(egui::Frame::default().inner_margin(egui::Margin::symmetric(10.0, 5.0)).fill(colors.crust)).show(ui.ctx(), |ui| {
+                ui.horizontal(|ui| {
+                    ui.label(&self.status_text);
+                    self.show_breadcrumb(ui);
+                    ui.with_layout(Layout::right_to_left(Align::Center), |ui| {
+                        ui.label(t!("status.char_count", count = self.workspace.active().map_or(0, |d| d.content.chars().count())));
+                        ui.separator();
+                        let target = self.switcher.target().path();
+                        ui.label(RichText::new(format!("→ {}", target.display())).color(colors.muted))
+                            .on_hover_text(t!("status.claude_target", source = self.switcher.paths().claude_dir_source().label()));
+                        if let Some(text) = self.next_switch_text() {
+                            ui.separator();
+                            ui.label(RichText::new(format!("⏱ {}", text)).color(colors.lavender));
+                        }
+                    });
+                });
+            });
+            SidePanel::left("file_list_panel")
+                .frame(egui::Frame::default()
+                    .fill(colors.crust)
+                    .stroke(egui::Stroke::new(1.0, colors.surface0))
                 )
                 .exact_width(200.0)
                 .resizable(false)
@@ -830,7 +3563,7 @@ impl App for ConfigManagerApp {
                 egui::TopBottomPanel::top("side_panel_title").frame(egui::Frame::default().inner_margin(egui::Margin::symmetric(12.0, 8.0)).outer_margin(egui::Margin::ZERO).fill(colors.crust).stroke(egui::Stroke::NONE)).show_inside(ui, |ui| {
                     ui.horizontal(|ui| {
                         ui.with_layout(Layout::left_to_right(Align::Center), |ui| {
-                            ui.label(RichText::new("◈ 配置文件").size(15.0).color(colors.text).strong());
+                            ui.label(RichText::new(t!("sidebar.title")).size(15.0).color(colors.text).strong());
                         });
                         ui.with_layout(Layout::right_to_left(Align::Center), |ui| {
                             // 添加透明占位按钮，确保与右侧面板高度一致
@@ -842,8 +3575,6 @@ impl App for ConfigManagerApp {
                 });
 
                 // 文件列表区域（包含按钮）
-                let mut selection_changed = false;
-                let mut selected_path = self.selected_file.clone();
                 let mut actions_to_perform = Vec::new();
                 
                 egui::ScrollArea::vertical().auto_shrink([false; 2]).show(ui, |ui| {
@@ -862,7 +3593,7 @@ impl App for ConfigManagerApp {
                                     let button_width = (ui.available_width() - 8.0) / 2.0;
                                     
                                     if ui.add_sized([button_width, 28.0], 
-                                        egui::Button::new(RichText::new("+ 新建").color(self.get_button_color("add")).size(13.0))
+                                        egui::Button::new(RichText::new(t!("button.new")).color(self.get_button_color("add")).size(13.0))
                                     ).clicked() {
                                         self.add_new_config();
                                     }
@@ -870,11 +3601,11 @@ impl App for ConfigManagerApp {
                                     ui.add_space(8.0);
                                     
                                     if ui.add_sized([button_width, 28.0], 
-                                        egui::Button::new(RichText::new("↻ 刷新").color(self.get_button_color("refresh")).size(13.0))
+                                        egui::Button::new(RichText::new(t!("button.refresh")).color(self.get_button_color("refresh")).size(13.0))
                                     ).clicked() {
                                         self.refresh_file_list();
                                         self.sync_with_claude_config();
-                                        self.show_toast("文件列表已刷新", ToastKind::Success);
+                                        self.show_toast(t!("toast.list_refreshed"), ToastKind::Success);
                                     }
                                 });
                             });
@@ -886,17 +3617,10 @@ impl App for ConfigManagerApp {
                         ui.add_space(8.0);
                         for (index, path) in self.config_files.iter().map(|p| p.as_path()).enumerate() {
                             let file_name = path.file_name().unwrap().to_str().unwrap();
-                            let is_default_file = self.app_settings.default_config_file == file_name;
+                            let is_default_file = self.switcher.is_default(file_name);
 
                             // 隔行背景色 - 更明显的区分
-                            let bg_color = if index % 2 == 0 {
-                                colors.crust
-                            } else {
-                                match self.current_theme {
-                                    Theme::Dark => Color32::from_rgb(32, 35, 42),  // 更深的对比色
-                                    Theme::Light => Color32::from_rgb(241, 245, 249), // 更明显的浅色
-                                }
-                            };
+                            let bg_color = if index % 2 == 0 { colors.crust } else { alt_row };
 
                             // 文件项容器
                             egui::Frame::default()
@@ -906,14 +3630,13 @@ impl App for ConfigManagerApp {
                                     ui.vertical(|ui| {
                                         // 文件名部分
                                         let file_text = if is_default_file {
-                                            RichText::new(format!("★ {} (默认)", file_name)).color(self.get_button_color("default")).strong().size(13.5)
+                                            RichText::new(t!("sidebar.default_item", name = file_name)).color(self.get_button_color("default")).strong().size(13.5)
                                         } else {
                                             RichText::new(file_name).size(13.0).color(colors.text)
                                         };
 
-                                        if ui.selectable_label(self.selected_file.as_deref() == Some(path), file_text).clicked() {
-                                            selected_path = Some(path.to_path_buf());
-                                            selection_changed = true;
+                                        if ui.selectable_label(self.workspace.active_path() == Some(path), file_text).clicked() {
+                                            actions_to_perform.push(Action::Open(path.to_path_buf()));
                                         }
 
                                         // 操作按钮区域 - 居中对齐
@@ -929,16 +3652,16 @@ impl App for ConfigManagerApp {
                                                     ui.add_space(padding);
                                                 }
                                                 
-                                                if ui.button(RichText::new("✎ 重命名").color(self.get_button_color("rename")).size(11.0)).clicked() {
-                                                    actions_to_perform.push(('r', index));
+                                                if ui.button(RichText::new(t!("button.rename")).color(self.get_button_color("rename")).size(11.0)).clicked() {
+                                                    actions_to_perform.push(Action::Rename(path.to_path_buf()));
                                                 }
                                                 ui.add_space(6.0);
-                                                if ui.button(RichText::new("× 删除").color(self.get_button_color("delete")).size(11.0)).clicked() {
-                                                    actions_to_perform.push(('d', index));
+                                                if ui.button(RichText::new(t!("button.delete")).color(self.get_button_color("delete")).size(11.0)).clicked() {
+                                                    actions_to_perform.push(Action::Delete(path.to_path_buf()));
                                                 }
                                                 ui.add_space(6.0);
-                                                if ui.add_enabled(!is_default_file, egui::Button::new(RichText::new("★ 设为默认").color(self.get_button_color("default")).size(11.0))).clicked() {
-                                                    actions_to_perform.push(('s', index));
+                                                if ui.add_enabled(!is_default_file, egui::Button::new(RichText::new(t!("button.set_default")).color(self.get_button_color("default")).size(11.0))).clicked() {
+                                                    actions_to_perform.push(Action::Apply(path.to_path_buf()));
                                                 }
                                             });
                                         });
@@ -951,29 +3674,8 @@ impl App for ConfigManagerApp {
                 });
                 
                 // 处理收集的操作
-                for (action_type, index) in actions_to_perform {
-                    if let Some(path) = self.config_files.get(index) {
-                        match action_type {
-                            'd' => {
-                                self.selected_file = Some(path.clone());
-                                self.show_delete_confirmation = true;
-                            }
-                            'r' => {
-                                self.selected_file = Some(path.clone());
-                                self.new_file_name = path.file_name().unwrap().to_str().unwrap().to_string();
-                                self.show_rename_dialog = true;
-                            }
-                            's' => {
-                                self.set_as_default(path.clone());
-                            }
-                            _ => {}
-                        }
-                    }
-                }
-                
-                if selection_changed {
-                    self.selected_file = selected_path;
-                    self.load_file_content();
+                for action in actions_to_perform {
+                    self.perform_action(action, ctx);
                 }
 
 
@@ -989,259 +3691,114 @@ impl App for ConfigManagerApp {
                     .show_inside(ui, |ui| {
                     ui.horizontal(|ui| {
                         ui.with_layout(Layout::left_to_right(Align::Center), |ui| {
-                            if let Some(path) = &self.selected_file {
-                                let file_name = path.file_name().unwrap().to_str().unwrap();
-                                let display_text = if self.is_content_modified {
-                                    format!("◉ {} (未保存)", file_name)
+                            if let Some(document) = self.workspace.active() {
+                                let file_name = document.name();
+                                let display_text = if document.is_modified() {
+                                    t!("title.unsaved", name = file_name)
                                 } else {
                                     format!("◇ {}", file_name)
                                 };
-                                let text_color = if self.is_content_modified {
+                                let text_color = if document.is_modified() {
                                     self.get_button_color("warning")
                                 } else {
                                     colors.text
                                 };
                                 ui.label(RichText::new(display_text).size(15.0).color(text_color).strong());
+                                if let Some((state, color)) = self.save_state_text(document) {
+                                    ui.label(RichText::new(state).size(12.0).color(color));
+                                }
                             } else {
-                                ui.label(RichText::new("请选择文件").size(14.0).color(colors.text));
+                                ui.label(RichText::new(t!("title.no_file")).size(14.0).color(colors.text));
                             }
                         });
                         ui.with_layout(Layout::right_to_left(Align::Center), |ui| {
                             // 添加设置按钮
-                            if ui.button(RichText::new("⚙ 设置").size(12.0).color(self.get_button_color("settings"))).clicked() {
+                            if ui.button(RichText::new(t!("button.settings")).size(12.0).color(self.get_button_color("settings"))).clicked() {
                                 self.show_settings_dialog = true;
                             }
+                            if ui.button(RichText::new(t!("button.targets")).size(12.0).color(self.get_button_color("toggle"))).clicked() {
+                                self.open_targets_dialog();
+                            }
+                            if ui.button(RichText::new(t!("button.schedule")).size(12.0).color(self.get_button_color("toggle"))).clicked() {
+                                self.open_schedule_dialog();
+                            }
+                            if ui.button(RichText::new(t!("button.env_vars")).size(12.0).color(self.get_button_color("toggle"))).clicked() {
+                                self.open_env_dialog();
+                            }
+                            if ui.button(RichText::new(t!("button.models")).size(12.0).color(self.get_button_color("toggle"))).clicked() {
+                                self.open_models_dialog();
+                            }
+                            if ui.button(RichText::new(t!("button.permissions")).size(12.0).color(self.get_button_color("toggle"))).clicked() {
+                                self.open_permissions_dialog();
+                            }
+                            if ui.button(RichText::new(t!("button.hooks")).size(12.0).color(self.get_button_color("toggle"))).clicked() {
+                                self.open_hooks_dialog();
+                            }
+                            if ui.button(RichText::new(t!("button.mcp")).size(12.0).color(self.get_button_color("toggle"))).clicked() {
+                                self.open_mcp_dialog();
+                            }
+                            if ui.button(RichText::new(t!("button.usage")).size(12.0).color(self.get_button_color("primary"))).clicked() {
+                                self.open_usage_dialog();
+                            }
+                            if ui.button(RichText::new(t!("button.audit")).size(12.0).color(self.get_button_color("secondary"))).clicked() {
+                                self.open_audit_dialog();
+                            }
                             ui.separator();
-                            if ui.add_enabled(self.selected_file.is_some(), egui::Button::new(RichText::new("💾 保存").color(self.get_button_color("save")).size(12.0))).clicked() {
+                            let has_document = self.workspace.active().is_some();
+                            if ui.add_enabled(has_document, egui::Button::new(RichText::new(t!("button.save")).color(self.get_button_color("save")).size(12.0))).clicked() {
                                 self.save_current_file();
                             }
-                            if ui.add_enabled(self.selected_file.is_some(), egui::Button::new(RichText::new("◊ 格式化").color(self.get_button_color("format")).size(12.0))).clicked() {
+                            if ui.add_enabled(has_document, egui::Button::new(RichText::new(t!("button.format")).color(self.get_button_color("format")).size(12.0))).clicked() {
                                 self.format_json();
                             }
+                            if ui.add_enabled(has_document, egui::SelectableLabel::new(self.show_outline, RichText::new(t!("button.outline")).size(12.0))).clicked() {
+                                self.show_outline = !self.show_outline;
+                            }
+                            let split_text = if self.workspace.is_split() { t!("button.unsplit") } else { t!("button.split") };
+                            if ui.add_enabled(has_document, egui::Button::new(RichText::new(split_text).color(self.get_button_color("toggle")).size(12.0))).clicked() {
+                                self.workspace.toggle_split();
+                            }
                         });
 
                     });
                 });
 
-                // 编辑器内容区域
-                egui::CentralPanel::default().frame(egui::Frame::default().fill(colors.mantle).inner_margin(egui::Margin { left: 12.0, right: 12.0, top: 10.0, bottom: 10.0 })).show_inside(ui, |ui| {
-                    if self.selected_file.is_some() {
-                        // 使用水平布局来分离行号和编辑器
-                        ui.horizontal_top(|ui| {
-                            // 计算行数和行号宽度
-                            let line_count = self.editor_content.lines().count().max(1);
-                            let line_number_width = 48.0;  // 调整为原来的80% (60 * 0.8 = 48)
-
-                            // 行号区域 - 固定宽度
-                            ui.allocate_ui_with_layout(
-                                egui::Vec2::new(line_number_width, ui.available_height()),
-                                egui::Layout::top_down(egui::Align::RIGHT),
-                                |ui| {
-                                    // 行号背景 - 与代码区域背景一致
-                                    let rect = ui.available_rect_before_wrap();
-                                    ui.painter().rect_filled(rect, egui::Rounding::ZERO, colors.mantle);
-
-                                    // 简单的行号显示，不使用滚动区域
-                                    ui.add_space(10.0);
-                                    for line_num in 1..=line_count {
-                                        ui.with_layout(egui::Layout::right_to_left(egui::Align::TOP), |ui| {
-                                            ui.add_space(8.0);
-                                            ui.label(
-                                                RichText::new(format!("{}", line_num))
-                                                    .font(egui::FontId::monospace(13.0))
-                                                    .color(Color32::from_rgb(120, 120, 120))
-                                            );
-                                        });
-                                    }
-                                }
-                            );
-
-                            // 编辑器区域
-                            ui.allocate_ui_with_layout(
-                                egui::Vec2::new(ui.available_width(), ui.available_height()),
-                                egui::Layout::top_down(egui::Align::LEFT),
-                                |ui| {
-                                    let scroll_area = egui::ScrollArea::vertical()
-                                        .auto_shrink([false; 2])
-                                        .id_source("main_editor_scroll");
-
-                                    scroll_area.show(ui, |ui| {
-
-                                        // 自定义JSON语法高亮（不包含行号）
-                                        let mut layouter = |ui: &egui::Ui, string: &str, wrap_width: f32| {
-                                            let mut job = egui::text::LayoutJob::default();
-                                            job.wrap.max_width = wrap_width;
-
-                                            // 定义颜色
-                                            let (key_color, string_color, number_color, keyword_color, punctuation_color) =
-                                                if matches!(self.current_theme, Theme::Dark) {
-                                                    (
-                                                        Color32::from_rgb(156, 220, 254), // 浅蓝色 - 键名
-                                                        Color32::from_rgb(206, 145, 120), // 橙色 - 字符串值
-                                                        Color32::from_rgb(181, 206, 168), // 浅绿色 - 数字
-                                                        Color32::from_rgb(197, 134, 192), // 紫色 - 关键字
-                                                        Color32::from_rgb(212, 212, 212), // 浅灰色 - 标点
-                                                    )
-                                                } else {
-                                                    (
-                                                        Color32::from_rgb(0, 92, 197),    // 深蓝色 - 键名
-                                                        Color32::from_rgb(163, 21, 21),   // 深红色 - 字符串值
-                                                        Color32::from_rgb(9, 134, 88),    // 深绿色 - 数字
-                                                        Color32::from_rgb(111, 66, 193),  // 深紫色 - 关键字
-                                                        Color32::from_rgb(80, 80, 80),    // 深灰色 - 标点
-                                                    )
-                                                };
-
-                                            let mut chars = string.char_indices().peekable();
-                                            let mut in_string = false;
-                                            let mut in_key = false;
-                                            let mut current_start = 0;
-                                            let mut current_color = colors.text;
-
-                                            while let Some((i, ch)) = chars.next() {
-                                                match ch {
-                                                    '"' => {
-                                                        // 添加当前段落
-                                                        if i > current_start {
-                                                            job.append(&string[current_start..i], 0.0, egui::TextFormat {
-                                                                color: current_color,
-                                                                font_id: egui::FontId::monospace(14.0),
-                                                                ..Default::default()
-                                                            });
-                                                        }
-
-                                                        if !in_string {
-                                                            // 开始字符串，检查是否是键名
-                                                            in_string = true;
-                                                            // 向前查找冒号来判断是否是键名
-                                                            let rest_of_string = &string[i..];
-                                                            if let Some(quote_end) = rest_of_string[1..].find('"') {
-                                                                let after_quote = &rest_of_string[quote_end + 2..];
-                                                                in_key = after_quote.trim_start().starts_with(':');
-                                                            }
-                                                            current_color = if in_key { key_color } else { string_color };
-                                                        } else {
-                                                            // 结束字符串
-                                                            in_string = false;
-                                                            in_key = false;
-                                                            current_color = colors.text;
-                                                        }
-
-                                                        // 添加引号
-                                                        job.append(&ch.to_string(), 0.0, egui::TextFormat {
-                                                            color: current_color,
-                                                            font_id: egui::FontId::monospace(14.0),
-                                                            ..Default::default()
-                                                        });
-
-                                                        current_start = i + ch.len_utf8();
-                                                    }
-                                                    '{' | '}' | '[' | ']' | ',' | ':' if !in_string => {
-                                                        // 添加当前段落
-                                                        if i > current_start {
-                                                            job.append(&string[current_start..i], 0.0, egui::TextFormat {
-                                                                color: current_color,
-                                                                font_id: egui::FontId::monospace(14.0),
-                                                                ..Default::default()
-                                                            });
-                                                        }
-
-                                                        // 添加标点符号
-                                                        job.append(&ch.to_string(), 0.0, egui::TextFormat {
-                                                            color: punctuation_color,
-                                                            font_id: egui::FontId::monospace(14.0),
-                                                            ..Default::default()
-                                                        });
-
-                                                        current_start = i + ch.len_utf8();
-                                                        current_color = colors.text;
-                                                    }
-                                                    _ if !in_string && (ch.is_ascii_digit() || ch == '-') => {
-                                                        // 数字开始
-                                                        if current_color != number_color {
-                                                            // 添加当前段落
-                                                            if i > current_start {
-                                                                job.append(&string[current_start..i], 0.0, egui::TextFormat {
-                                                                    color: current_color,
-                                                                    font_id: egui::FontId::monospace(14.0),
-                                                                    ..Default::default()
-                                                                });
-                                                            }
-                                                            current_start = i;
-                                                            current_color = number_color;
-                                                        }
-                                                    }
-                                                    _ if !in_string && ch.is_ascii_alphabetic() => {
-                                                        // 检查关键字 (true, false, null)
-                                                        let word_start = i;
-                                                        let mut word_end = i + ch.len_utf8();
-
-                                                        // 找到单词结束
-                                                        let remaining = &string[word_end..];
-                                                        for (_offset, next_ch) in remaining.char_indices() {
-                                                            if next_ch.is_ascii_alphanumeric() {
-                                                                word_end += next_ch.len_utf8();
-                                                            } else {
-                                                                break;
-                                                            }
-                                                        }
-
-                                                        let word = &string[word_start..word_end];
-                                                        let is_keyword = matches!(word, "true" | "false" | "null");
-
-                                                        if is_keyword && current_color != keyword_color {
-                                                            // 添加当前段落
-                                                            if i > current_start {
-                                                                job.append(&string[current_start..i], 0.0, egui::TextFormat {
-                                                                    color: current_color,
-                                                                    font_id: egui::FontId::monospace(14.0),
-                                                                    ..Default::default()
-                                                                });
-                                                            }
-                                                            current_start = i;
-                                                            current_color = keyword_color;
-                                                        }
-                                                    }
-                                                    _ => {}
-                                                }
-                                            }
-
-                                            // 添加剩余部分
-                                            if current_start < string.len() {
-                                                job.append(&string[current_start..], 0.0, egui::TextFormat {
-                                                    color: current_color,
-                                                    font_id: egui::FontId::monospace(14.0),
-                                                    ..Default::default()
-                                                });
-                                            }
+                if !self.workspace.documents().is_empty() {
+                    egui::TopBottomPanel::top("editor_tabs")
+                        .frame(egui::Frame::default().inner_margin(egui::Margin::symmetric(12.0, 4.0)).fill(colors.crust).stroke(egui::Stroke::NONE))
+                        .show_inside(ui, |ui| self.show_tab_bar(ui));
+                }
 
-                                            ui.fonts(|f| f.layout_job(job))
-                                        };
+                let has_findings = self.workspace.active().is_some_and(|d| !d.findings.is_empty());
+                if has_findings {
+                    egui::TopBottomPanel::bottom("lint_panel")
+                        .frame(egui::Frame::default().inner_margin(egui::Margin::symmetric(12.0, 6.0)).fill(colors.crust).stroke(egui::Stroke::NONE))
+                        .show_inside(ui, |ui| self.show_lint_panel(ui));
+                }
 
-                                        ui.add_space(10.0);
-                                        let editor = TextEdit::multiline(&mut self.editor_content)
-                                            .id(Id::new("main_editor"))
-                                            .font(egui::FontId::monospace(14.0))
-                                            .code_editor()
-                                            .desired_width(f32::INFINITY)
-                                            .frame(false)
-                                            .layouter(&mut layouter);
-                                        let response = ui.add(editor);
-
-                                        // 检测内容是否修改
-                                        if response.changed() {
-                                            self.is_content_modified = self.editor_content != self.original_content;
-                                        }
-                                    });
-                                }
-                            );
-                        });
+                if self.show_outline && self.workspace.active().is_some() {
+                    egui::SidePanel::right("outline_panel")
+                        .frame(egui::Frame::default().inner_margin(egui::Margin::symmetric(8.0, 6.0)).fill(colors.crust).stroke(egui::Stroke::NONE))
+                        .default_width(240.0)
+                        .width_range(160.0..=480.0)
+                        .show_inside(ui, |ui| self.show_outline_panel(ui));
+                }
 
-                        self.char_count = self.editor_content.chars().count();
-                    } else {
+                // 编辑器内容区域
+                egui::CentralPanel::default().frame(egui::Frame::default().fill(colors.mantle).inner_margin(egui::Margin { left: 12.0, right: 12.0, top: 10.0, bottom: 10.0 })).show_inside(ui, |ui| {
+                    if self.workspace.documents().is_empty() {
                         ui.centered_and_justified(|ui| {
-                            ui.label(RichText::new("请选择一个配置文件进行编辑").size(16.0).color(colors.text.linear_multiply(0.7)));
+                            ui.label(RichText::new(t!("editor.placeholder")).size(16.0).color(colors.text.linear_multiply(0.7)));
+                        });
+                    } else if self.workspace.is_split() {
+                        ui.columns(2, |columns| {
+                            for (ui, pane) in columns.iter_mut().zip([Pane::Primary, Pane::Secondary]) {
+                                self.show_pane_header(ui, pane);
+                                self.show_editor_pane(ui, pane);
+                            }
                         });
+                    } else {
+                        self.show_editor_pane(ui, Pane::Primary);
                     }
                 });
             });
@@ -1250,9 +3807,9 @@ impl App for ConfigManagerApp {
                 self.toasts.retain(|toast| toast.spawn_time.elapsed().as_secs_f32() < toast.duration_secs);
                 for toast in self.toasts.iter() {
                     let (label_text, color) = match toast.kind {
-                        ToastKind::Success => ("[成功]", colors.green),
-                        ToastKind::Error => ("[错误]", colors.red),
-                        ToastKind::Warning => ("[警告]", colors.yellow),
+                        ToastKind::Success => (t!("toast.label.success"), colors.green),
+                        ToastKind::Error => (t!("toast.label.error"), colors.red),
+                        ToastKind::Warning => (t!("toast.label.warning"), colors.yellow),
                     };
                     let frame = egui::Frame::default().inner_margin(8.0).rounding(6.0).fill(colors.crust).stroke(egui::Stroke::new(1.0, color));
                     frame.show(ui, |ui| {
@@ -1265,11 +3822,11 @@ impl App for ConfigManagerApp {
                     ui.add_space(5.0);
                 }
             });
-            ctx.request_repaint_after(Duration::from_millis(100));
+            self.schedule_repaint(ctx);
         });
 
         // 模态弹框 - 简单有效的方案
-        if self.show_rename_dialog || self.show_delete_confirmation || self.show_settings_dialog {
+        if self.show_rename_dialog || self.show_delete_confirmation || self.show_settings_dialog || self.show_schedule_dialog || self.show_mcp_dialog || self.show_hooks_dialog || self.show_permissions_dialog || self.show_models_dialog || self.show_env_dialog || self.show_targets_dialog || self.show_usage_dialog || self.show_audit_dialog {
             // 全屏半透明背景
             ctx.layer_painter(egui::LayerId::background()).rect_filled(
                 ctx.screen_rect(),
@@ -1279,38 +3836,38 @@ impl App for ConfigManagerApp {
         }
 
         if self.show_rename_dialog {
-            egui::Window::new("重命名文件")
+            egui::Window::new(t!("rename.title"))
                 .collapsible(false)
                 .resizable(false)
                 .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
                 .show(ctx, |ui| {
-                    ui.label("请输入新的文件名:");
+                    ui.label(t!("rename.prompt"));
                     ui.text_edit_singleline(&mut self.new_file_name);
                     ui.add_space(10.0);
                     ui.horizontal(|ui| {
-                        if ui.button(RichText::new("✓ 确认").color(self.get_button_color("primary"))).clicked() { self.rename_selected_file(); }
-                        if ui.button(RichText::new("✗ 取消").color(self.get_button_color("secondary"))).clicked() { self.show_rename_dialog = false; }
+                        if ui.button(RichText::new(t!("button.confirm")).color(self.get_button_color("primary"))).clicked() { self.rename_selected_file(); }
+                        if ui.button(RichText::new(t!("button.cancel")).color(self.get_button_color("secondary"))).clicked() { self.show_rename_dialog = false; }
                     });
                 });
         }
 
         if self.show_delete_confirmation {
-            egui::Window::new("确认删除")
+            egui::Window::new(t!("delete.title"))
                 .collapsible(false)
                 .resizable(false)
                 .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
                 .show(ctx, |ui| {
-                    ui.label("您确定要删除这个配置文件吗？此操作无法撤销。");
+                    ui.label(t!("delete.prompt"));
                     ui.add_space(10.0);
                     ui.horizontal(|ui| {
-                        if ui.button(RichText::new("× 确认删除").color(colors.red)).clicked() { self.delete_selected_file(); self.show_delete_confirmation = false; }
-                        if ui.button(RichText::new("✗ 取消").color(self.get_button_color("secondary"))).clicked() { self.show_delete_confirmation = false; }
+                        if ui.button(RichText::new(t!("delete.confirm")).color(colors.red)).clicked() { self.delete_selected_file(); self.show_delete_confirmation = false; }
+                        if ui.button(RichText::new(t!("button.cancel")).color(self.get_button_color("secondary"))).clicked() { self.show_delete_confirmation = false; }
                     });
                 });
         }
 
         if self.show_settings_dialog {
-            egui::Window::new("应用设置")
+            egui::Window::new(t!("settings.title"))
                 .collapsible(false)
                 .resizable(false)
                 .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
@@ -1322,44 +3879,209 @@ impl App for ConfigManagerApp {
                         // 配置目录设置
                         ui.group(|ui| {
                             ui.vertical(|ui| {
-                                ui.label(RichText::new("配置文件目录").strong());
+                                ui.label(RichText::new(t!("settings.config_dir")).strong());
                                 ui.add_space(5.0);
 
                                 ui.horizontal(|ui| {
-                                    ui.label("当前目录:");
-                                    ui.label(RichText::new(self.config_dir.to_string_lossy()).color(colors.lavender));
+                                    ui.label(t!("settings.current_dir"));
+                                    ui.label(RichText::new(self.switcher.settings().config_directory.to_string_lossy()).color(colors.lavender));
                                 });
 
                                 ui.add_space(5.0);
-                                ui.label("新目录路径:");
+                                ui.label(t!("settings.new_dir"));
                                 ui.text_edit_singleline(&mut self.new_config_dir_input);
 
                                 ui.add_space(5.0);
                                 ui.horizontal(|ui| {
-                                    if ui.button(RichText::new("□ 选择文件夹").color(colors.green)).clicked() {
+                                    if ui.button(RichText::new(t!("settings.pick_folder")).color(colors.green)).clicked() {
                                         // 使用rfd打开文件夹选择对话框
-                                        let current_dir = if self.config_dir.exists() {
-                                            Some(self.config_dir.clone())
-                                        } else if let Some(user_dirs) = UserDirs::new() {
-                                            Some(user_dirs.home_dir().to_path_buf())
+                                        let config_dir = &self.switcher.settings().config_directory;
+                                        let current_dir = if config_dir.exists() {
+                                            config_dir.clone()
                                         } else {
-                                            None
+                                            self.switcher.paths().home().to_path_buf()
                                         };
 
                                         if let Some(folder) = rfd::FileDialog::new()
-                                            .set_title("选择配置文件目录")
-                                            .set_directory(current_dir.unwrap_or_else(|| PathBuf::from(".")))
+                                            .set_title(t!("settings.pick_folder_title"))
+                                            .set_directory(current_dir)
                                             .pick_folder() {
                                             self.new_config_dir_input = folder.to_string_lossy().to_string();
                                         }
                                     }
 
-                                    if ui.button(RichText::new("↻ 重置").color(self.get_button_color("reset"))).clicked() {
-                                        if let Some(user_dirs) = UserDirs::new() {
-                                            self.new_config_dir_input = user_dirs.home_dir().join(APP_DIR_NAME).to_string_lossy().to_string();
+                                    if ui.button(RichText::new(t!("button.reset")).color(self.get_button_color("reset"))).clicked() {
+                                        self.new_config_dir_input = self.switcher.paths().app_dir().to_string_lossy().to_string();
+                                    }
+                                });
+                            });
+                        });
+
+                        ui.add_space(10.0);
+
+                        // Claude 配置目录
+                        ui.group(|ui| {
+                            ui.vertical(|ui| {
+                                ui.label(RichText::new(t!("settings.claude_dir")).strong());
+                                ui.add_space(5.0);
+                                let paths = self.switcher.paths();
+                                ui.horizontal(|ui| {
+                                    ui.label(t!("settings.claude_settings_file"));
+                                    ui.label(RichText::new(self.switcher.target().path().display().to_string()).color(colors.lavender));
+                                });
+                                ui.label(RichText::new(paths.claude_dir_source().label()).size(11.0).color(colors.muted));
+                                ui.add_space(5.0);
+                                ui.horizontal(|ui| {
+                                    ui.add(TextEdit::singleline(&mut self.claude_dir_input).desired_width(260.0).hint_text(t!("settings.claude_dir_auto")));
+                                    if ui.button(t!("settings.pick_folder")).clicked() {
+                                        if let Some(folder) = rfd::FileDialog::new().set_title(t!("settings.claude_dir")).pick_folder() {
+                                            self.claude_dir_input = folder.to_string_lossy().to_string();
+                                        }
+                                    }
+                                    if ui.button(RichText::new(t!("button.reset")).color(self.get_button_color("reset"))).clicked() {
+                                        self.claude_dir_input.clear();
+                                    }
+                                });
+                                ui.label(RichText::new(t!("settings.claude_dir_hint")).size(11.0).color(colors.muted));
+                            });
+                        });
+
+                        ui.add_space(10.0);
+
+                        // 同时写入的其他 Claude 配置目录
+                        ui.group(|ui| {
+                            ui.vertical(|ui| {
+                                ui.label(RichText::new(t!("settings.claude_roots")).strong());
+                                ui.label(RichText::new(t!("settings.claude_roots_hint")).size(11.0).color(colors.muted));
+                                ui.add_space(5.0);
+                                let reports = self.switcher.root_reports().to_vec();
+                                let paths = self.switcher.paths().clone();
+                                let mut removed = None;
+                                for (index, root) in self.claude_roots_draft.iter_mut().enumerate() {
+                                    ui.horizontal(|ui| {
+                                        ui.checkbox(&mut root.enabled, "");
+                                        ui.add(TextEdit::singleline(&mut root.name).desired_width(90.0).hint_text(t!("settings.claude_root_name")));
+                                        let mut dir = root.dir.to_string_lossy().to_string();
+                                        if ui.add(TextEdit::singleline(&mut dir).desired_width(200.0).hint_text("~/sandbox/.claude")).changed() {
+                                            root.dir = PathBuf::from(dir);
+                                        }
+                                        if ui.button("📁").clicked() {
+                                            if let Some(folder) = rfd::FileDialog::new().set_title(t!("settings.claude_roots")).pick_folder() {
+                                                root.dir = folder;
+                                            }
+                                        }
+                                        if ui.button(RichText::new("✕").color(colors.red)).clicked() {
+                                            removed = Some(index);
+                                        }
+                                    });
+                                    let path = ActiveSettingsTarget::new(paths.expand_home(&root.dir)).path();
+                                    if let Some(report) = reports.iter().find(|report| report.path == path) {
+                                        let color = if report.status.is_ok() { colors.green } else { colors.red };
+                                        ui.label(RichText::new(report.status.label()).size(11.0).color(color));
+                                    }
+                                }
+                                if let Some(index) = removed {
+                                    self.claude_roots_draft.remove(index);
+                                }
+                                if ui.button(RichText::new(t!("settings.add_claude_root")).size(11.0).color(self.get_button_color("add"))).clicked() {
+                                    self.claude_roots_draft.push(ClaudeRoot::default());
+                                }
+                            });
+                        });
+
+                        ui.add_space(10.0);
+
+                        // 界面语言
+                        ui.group(|ui| {
+                            ui.vertical(|ui| {
+                                ui.label(RichText::new(t!("settings.language")).strong());
+                                ui.add_space(5.0);
+                                let current = self.switcher.settings().language;
+                                let mut selected = current;
+                                egui::ComboBox::from_id_source("language_select")
+                                    .selected_text(current.native_name())
+                                    .show_ui(ui, |ui| {
+                                        for language in Language::ALL {
+                                            ui.selectable_value(&mut selected, language, language.native_name());
                                         }
+                                    });
+                                if selected != current {
+                                    self.set_language(selected);
+                                }
+                            });
+                        });
+
+                        ui.add_space(10.0);
+
+                        // 自动保存
+                        ui.group(|ui| {
+                            ui.vertical(|ui| {
+                                ui.label(RichText::new(t!("settings.autosave")).strong());
+                                ui.add_space(5.0);
+                                let mut autosave = self.switcher.settings().autosave.clone();
+                                ui.checkbox(&mut autosave.enabled, t!("settings.autosave_enabled"));
+                                let delay = ui.add_enabled_ui(autosave.enabled, |ui| {
+                                    let delay = ui.horizontal(|ui| {
+                                        ui.label(t!("settings.autosave_delay"));
+                                        ui.add(egui::DragValue::new(&mut autosave.delay_secs).clamp_range(1..=60).suffix(t!("settings.seconds_suffix")))
+                                    }).inner;
+                                    ui.checkbox(&mut autosave.sync_default, t!("settings.autosave_sync_default"));
+                                    delay
+                                }).inner;
+                                // 拖动或输入延迟时只更新内存中的设置，结束后再写入文件
+                                let editing = delay.dragged() || delay.has_focus();
+                                let finished = delay.drag_released() || delay.lost_focus();
+                                if autosave != self.switcher.settings().autosave {
+                                    self.switcher.settings_mut().autosave = autosave;
+                                    if !editing {
+                                        self.save_app_settings();
                                     }
+                                } else if finished {
+                                    self.save_app_settings();
+                                }
+                            });
+                        });
+
+                        ui.add_space(10.0);
+
+                        // 格式化
+                        ui.group(|ui| {
+                            ui.vertical(|ui| {
+                                ui.label(RichText::new(t!("settings.format")).strong());
+                                ui.add_space(5.0);
+                                let mut format = self.switcher.settings().format.clone();
+                                egui::Grid::new("format_settings_grid").num_columns(2).show(ui, |ui| {
+                                    ui.label(t!("settings.format_indent"));
+                                    ui.horizontal(|ui| {
+                                        egui::ComboBox::from_id_source("indent_style_select")
+                                            .selected_text(format.indent_style.label())
+                                            .show_ui(ui, |ui| {
+                                                for style in IndentStyle::ALL {
+                                                    ui.selectable_value(&mut format.indent_style, style, style.label());
+                                                }
+                                            });
+                                        ui.add_enabled(
+                                            format.indent_style == IndentStyle::Spaces,
+                                            egui::DragValue::new(&mut format.indent_width).clamp_range(1..=8),
+                                        );
+                                    });
+                                    ui.end_row();
+                                    ui.label(t!("settings.format_key_order"));
+                                    egui::ComboBox::from_id_source("key_order_select")
+                                        .selected_text(format.key_order.label())
+                                        .show_ui(ui, |ui| {
+                                            for order in KeyOrder::ALL {
+                                                ui.selectable_value(&mut format.key_order, order, order.label());
+                                            }
+                                        });
+                                    ui.end_row();
                                 });
+                                ui.checkbox(&mut format.final_newline, t!("settings.format_final_newline"));
+                                ui.label(RichText::new(t!("settings.format_hint")).size(11.0).color(colors.muted));
+                                if format != self.switcher.settings().format {
+                                    self.switcher.settings_mut().format = format;
+                                    self.save_app_settings();
+                                }
                             });
                         });
 
@@ -1368,49 +4090,147 @@ impl App for ConfigManagerApp {
                         // 主题设置
                         ui.group(|ui| {
                             ui.vertical(|ui| {
-                                ui.label(RichText::new("主题设置").strong());
+                                ui.label(RichText::new(t!("settings.theme")).strong());
                                 ui.add_space(5.0);
 
                                 ui.horizontal(|ui| {
-                                    ui.label("当前主题:");
-                                    let theme_name = match self.current_theme {
-                                        Theme::Dark => "深色主题",
-                                        Theme::Light => "浅色主题",
-                                    };
-                                    ui.label(RichText::new(theme_name).color(colors.lavender));
+                                    ui.label(t!("settings.current_theme"));
+                                    let mut selected = self.current_theme.clone();
+                                    egui::ComboBox::from_id_source("theme_select")
+                                        .selected_text(theme_display_name(&selected))
+                                        .show_ui(ui, |ui| {
+                                            ui.selectable_value(&mut selected, Theme::Dark, t!("theme.dark"));
+                                            ui.selectable_value(&mut selected, Theme::Light, t!("theme.light"));
+                                            for name in self.themes.names() {
+                                                ui.selectable_value(&mut selected, Theme::Custom(name.clone()), name.as_str());
+                                            }
+                                        });
+                                    if selected != self.current_theme {
+                                        self.set_theme(selected, ctx);
+                                    }
                                 });
 
-                                ui.add_space(5.0);
-                                if ui.button(RichText::new("◐ 切换主题").color(self.get_button_color("toggle"))).clicked() {
-                                    self.toggle_theme(ctx);
+                                if let Theme::Custom(name) = &self.current_theme {
+                                    if self.themes.get(name).is_none() {
+                                        ui.label(RichText::new(t!("settings.theme_missing", name = name)).size(11.0).color(colors.yellow));
+                                    }
                                 }
+                                ui.label(RichText::new(t!("settings.themes_dir", path = self.themes.dir().display())).size(11.0).color(colors.muted));
+
+                                ui.add_space(5.0);
+                                ui.horizontal(|ui| {
+                                    if ui.button(RichText::new(t!("settings.toggle_theme")).color(self.get_button_color("toggle"))).clicked() {
+                                        self.toggle_theme(ctx);
+                                    }
+                                    if ui.button(RichText::new(t!("settings.export_theme")).color(self.get_button_color("save"))).clicked() {
+                                        self.export_current_theme();
+                                    }
+                                });
                             });
                         });
 
+                        if let Some(server) = &self.control_server {
+                            ui.add_space(10.0);
+                            ui.group(|ui| {
+                                ui.vertical(|ui| {
+                                    ui.label(RichText::new(t!("settings.control_socket")).strong());
+                                    ui.add_space(5.0);
+                                    ui.label(RichText::new(server.socket_path().to_string_lossy()).color(colors.lavender));
+                                });
+                            });
+                        }
+
                         ui.add_space(15.0);
 
                         // 按钮区域
                         ui.horizontal(|ui| {
-                            if ui.button(RichText::new("✓ 应用更改").color(colors.green)).clicked() {
+                            if ui.button(RichText::new(t!("settings.apply")).color(colors.green)).clicked() {
                                 let new_path = PathBuf::from(&self.new_config_dir_input);
-                                if new_path != self.config_dir {
-                                    self.change_config_directory(new_path);
+                                if new_path != self.switcher.settings().config_directory {
+                                    // 更换目录会关闭所有标签页
+                                    self.guard_unsaved(self.workspace.modified_paths(), PendingAction::ChangeDirectory(new_path), ctx);
                                 }
+                                // 先保存其他目录：更换主目录时会重新载入两者的草稿
+                                self.apply_claude_roots();
+                                self.apply_claude_dir();
                                 self.show_settings_dialog = false;
                             }
 
-                            if ui.button(RichText::new("✗ 取消").color(self.get_button_color("secondary"))).clicked() {
-                                self.new_config_dir_input = self.config_dir.to_string_lossy().to_string();
+                            if ui.button(RichText::new(t!("button.cancel")).color(self.get_button_color("secondary"))).clicked() {
+                                self.new_config_dir_input = self.switcher.settings().config_directory.to_string_lossy().to_string();
+                                self.load_claude_dir_input();
                                 self.show_settings_dialog = false;
                             }
                         });
                     });
                 });
         }
+
+        if self.show_schedule_dialog {
+            self.show_schedule_window(ctx);
+        }
+
+        if self.show_mcp_dialog {
+            self.show_mcp_window(ctx);
+        }
+
+        if self.show_hooks_dialog {
+            self.show_hooks_window(ctx);
+        }
+
+        if self.show_permissions_dialog {
+            self.show_permissions_window(ctx);
+        }
+
+        if self.show_models_dialog {
+            self.show_models_window(ctx);
+        }
+
+        if self.show_env_dialog {
+            self.show_env_window(ctx);
+        }
+
+        if self.show_targets_dialog {
+            self.show_targets_window(ctx);
+        }
+
+        if self.show_usage_dialog {
+            self.show_usage_window(ctx);
+        }
+
+        if self.show_audit_dialog {
+            self.show_audit_window(ctx);
+        }
+
+        if self.command_palette.open {
+            self.show_command_palette(ctx);
+        }
+
+        if self.unsaved_guard.is_some() {
+            self.show_unsaved_window(ctx);
+        }
+
+        if self.lint_override.is_some() {
+            self.show_lint_override_window(ctx);
+        }
+    }
+}
+
+fn theme_display_name(theme: &Theme) -> &str {
+    match theme {
+        Theme::Dark => t!("theme.dark"),
+        Theme::Light => t!("theme.light"),
+        Theme::Custom(name) => name,
     }
 }
 
 fn main() -> Result<(), eframe::Error> {
+    // --headless：不启动界面，只运行定时切换规则
+    if std::env::args().any(|arg| arg == "--headless") {
+        run_headless();
+        return Ok(());
+    }
+
     // 加载应用图标
     let icon_data = include_bytes!("../resources/icons/icon.svg");
     let icon_image = load_icon_from_svg(icon_data);
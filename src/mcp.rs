@@ -0,0 +1,377 @@
+// MCP 服务器：配置文件可以在 mcpServers 中携带一组服务器，应用配置时合并到 Claude 读取的文件中
+// （用户级写入 ~/.claude.json，项目级写入项目目录下的 .mcp.json）。
+// 服务器的写法与 Claude 相同，另外用 disabled 和 scope 两个字段控制是否写入以及写入哪个文件
+
+use crate::error::{Error, Result};
+use crate::files;
+use crate::format::FormatSettings;
+use crate::jsonc;
+use crate::outline;
+use crate::t;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+pub const PROFILE_KEY: &str = "mcpServers";
+pub const PROJECT_FILE_NAME: &str = ".mcp.json";
+
+// 只在配置文件中使用，写入 Claude 的文件时去掉
+const DISABLED_KEY: &str = "disabled";
+const SCOPE_KEY: &str = "scope";
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Transport {
+    Stdio,
+    Http,
+    Sse,
+}
+
+impl Transport {
+    pub const ALL: [Transport; 3] = [Transport::Stdio, Transport::Http, Transport::Sse];
+
+    pub fn key(self) -> &'static str {
+        match self {
+            Transport::Stdio => "stdio",
+            Transport::Http => "http",
+            Transport::Sse => "sse",
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Transport::Stdio => t!("mcp.transport.stdio"),
+            Transport::Http => t!("mcp.transport.http"),
+            Transport::Sse => t!("mcp.transport.sse"),
+        }
+    }
+
+    fn from_key(key: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|t| t.key() == key)
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Scope {
+    User,
+    Project,
+}
+
+impl Scope {
+    pub const ALL: [Scope; 2] = [Scope::User, Scope::Project];
+
+    pub fn key(self) -> &'static str {
+        match self {
+            Scope::User => "user",
+            Scope::Project => "project",
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Scope::User => t!("mcp.scope.user"),
+            Scope::Project => t!("mcp.scope.project"),
+        }
+    }
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct McpServer {
+    pub name: String,
+    pub transport: Transport,
+    // stdio
+    pub command: String,
+    pub args: Vec<String>,
+    pub env: Vec<(String, String)>,
+    // http 和 sse
+    pub url: String,
+    pub headers: Vec<(String, String)>,
+    pub scope: Scope,
+    pub enabled: bool,
+    // 其余字段原样保留
+    pub extra: Map<String, Value>,
+}
+
+impl McpServer {
+    pub fn new(name: impl Into<String>, transport: Transport) -> Self {
+        Self {
+            name: name.into(),
+            transport,
+            command: String::new(),
+            args: Vec::new(),
+            env: Vec::new(),
+            url: String::new(),
+            headers: Vec::new(),
+            scope: Scope::User,
+            enabled: true,
+            extra: Map::new(),
+        }
+    }
+
+    // 没有 type 字段时（Claude Desktop 的写法）按有无 url 判断
+    pub fn from_value(name: &str, value: &Value) -> Option<Self> {
+        let object = value.as_object()?;
+        let text = |key: &str| object.get(key).and_then(Value::as_str).unwrap_or_default().to_string();
+        let transport = match object.get("type").and_then(Value::as_str) {
+            Some(key) => Transport::from_key(key)?,
+            None if object.contains_key("url") => Transport::Http,
+            None => Transport::Stdio,
+        };
+        let args = object.get("args").and_then(Value::as_array).map_or_else(Vec::new, |args| args.iter().map(value_text).collect());
+        let extra = object
+            .iter()
+            .filter(|(key, _)| !matches!(key.as_str(), "type" | "command" | "args" | "env" | "url" | "headers" | DISABLED_KEY | SCOPE_KEY))
+            .map(|(key, value)| (key.clone(), value.clone()))
+            .collect();
+        Some(Self {
+            name: name.to_string(),
+            transport,
+            command: text("command"),
+            args,
+            env: pairs(object.get("env")),
+            url: text("url"),
+            headers: pairs(object.get("headers")),
+            scope: if text(SCOPE_KEY) == Scope::Project.key() { Scope::Project } else { Scope::User },
+            enabled: !object.get(DISABLED_KEY).and_then(Value::as_bool).unwrap_or(false),
+            extra,
+        })
+    }
+
+    // 写入 Claude 文件的内容
+    pub fn to_claude_value(&self) -> Value {
+        let mut object = Map::new();
+        object.insert("type".to_string(), self.transport.key().into());
+        match self.transport {
+            Transport::Stdio => {
+                object.insert("command".to_string(), self.command.clone().into());
+                object.insert("args".to_string(), self.args.clone().into());
+                if !self.env.is_empty() {
+                    object.insert("env".to_string(), pairs_to_value(&self.env));
+                }
+            }
+            Transport::Http | Transport::Sse => {
+                object.insert("url".to_string(), self.url.clone().into());
+                if !self.headers.is_empty() {
+                    object.insert("headers".to_string(), pairs_to_value(&self.headers));
+                }
+            }
+        }
+        object.extend(self.extra.clone());
+        Value::Object(object)
+    }
+
+    // 保存在配置文件中的内容：默认值（启用、用户级）不写出
+    pub fn to_profile_value(&self) -> Value {
+        let mut value = self.to_claude_value();
+        let object = value.as_object_mut().expect("to_claude_value 返回对象");
+        if self.scope != Scope::User {
+            object.insert(SCOPE_KEY.to_string(), self.scope.key().into());
+        }
+        if !self.enabled {
+            object.insert(DISABLED_KEY.to_string(), true.into());
+        }
+        value
+    }
+
+    // 列表中显示的命令行或地址
+    pub fn summary(&self) -> String {
+        match self.transport {
+            Transport::Stdio => std::iter::once(self.command.as_str()).chain(self.args.iter().map(String::as_str)).collect::<Vec<_>>().join(" "),
+            Transport::Http | Transport::Sse => self.url.clone(),
+        }
+    }
+
+    // 返回第一个问题的说明
+    pub fn validate(&self) -> std::result::Result<(), String> {
+        if self.name.trim().is_empty() {
+            return Err(t!("mcp.invalid.name").to_string());
+        }
+        match self.transport {
+            Transport::Stdio if self.command.trim().is_empty() => Err(t!("mcp.invalid.command").to_string()),
+            Transport::Http | Transport::Sse if !self.url.starts_with("http://") && !self.url.starts_with("https://") => {
+                Err(t!("mcp.invalid.url").to_string())
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+fn value_text(value: &Value) -> String {
+    match value {
+        Value::String(text) => text.clone(),
+        other => other.to_string(),
+    }
+}
+
+fn pairs(value: Option<&Value>) -> Vec<(String, String)> {
+    value
+        .and_then(Value::as_object)
+        .map_or_else(Vec::new, |object| object.iter().map(|(key, value)| (key.clone(), value_text(value))).collect())
+}
+
+fn pairs_to_value(pairs: &[(String, String)]) -> Value {
+    Value::Object(pairs.iter().map(|(key, value)| (key.clone(), Value::String(value.clone()))).collect())
+}
+
+// 每行一项，按第一个分隔符拆开；没有分隔符的行忽略
+pub fn parse_pairs(text: &str, separator: char) -> Vec<(String, String)> {
+    text.lines()
+        .filter_map(|line| line.split_once(separator))
+        .map(|(key, value)| (key.trim().to_string(), value.trim().to_string()))
+        .filter(|(key, _)| !key.is_empty())
+        .collect()
+}
+
+pub fn format_pairs(pairs: &[(String, String)], separator: &str) -> String {
+    pairs.iter().map(|(key, value)| format!("{}{}{}", key, separator, value)).collect::<Vec<_>>().join("\n")
+}
+
+// 配置中的服务器；没有 mcpServers 时返回 None，应用时只移除之前写入的服务器
+pub fn from_profile(settings: &Value) -> Option<Vec<McpServer>> {
+    let servers = settings.get(PROFILE_KEY)?;
+    Some(servers.as_object().map_or_else(Vec::new, |servers| {
+        servers.iter().filter_map(|(name, value)| McpServer::from_value(name, value)).collect()
+    }))
+}
+
+// 把服务器写入配置的 mcpServers，只替换这一个成员，其余内容和注释保持不变
+pub fn set_in_profile(content: &str, servers: &[McpServer], format: &FormatSettings) -> Result<String> {
+    let servers: Map<String, Value> = servers.iter().map(|s| (s.name.clone(), s.to_profile_value())).collect();
+    outline::replace_member(content, PROFILE_KEY, Some(&Value::Object(servers)), format)
+}
+
+// Claude 的 settings.json 不读取 mcpServers，写入活动配置前去掉
+pub fn strip_from_settings(content: &str) -> String {
+    outline::replace_member(content, PROFILE_KEY, None, &FormatSettings::default()).unwrap_or_else(|_| content.to_string())
+}
+
+// 读取 ~/.claude.json 或 Claude Desktop 配置中的服务器，用于导入
+pub fn read_servers_file(path: &Path) -> Result<Vec<McpServer>> {
+    let content = fs::read_to_string(path).map_err(|source| Error::Read { path: path.to_path_buf(), source })?;
+    let value: Value = jsonc::from_str(&content).map_err(Error::InvalidJson)?;
+    Ok(from_profile(&value).unwrap_or_default())
+}
+
+// merge_into 的结果
+#[derive(Debug, Default, PartialEq)]
+pub struct Merged {
+    // 这次写入的服务器名称
+    pub names: Vec<String>,
+    // 与用户自己添加的服务器同名而跳过的服务器名称
+    pub skipped: Vec<String>,
+}
+
+// 合并到 Claude 的文件：先移除上次写入的服务器，再写入这次启用的服务器，用户自己添加的服务器和文件中的其他内容保持不变。
+// 与用户自己添加的服务器同名时跳过，不覆盖也不接管
+pub fn merge_into(path: &Path, servers: &[McpServer], previous: &[String]) -> Result<Merged> {
+    let mut root = match fs::read_to_string(path) {
+        Ok(content) => jsonc::from_str(&content).map_err(Error::InvalidJson)?,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Value::Object(Map::new()),
+        Err(source) => return Err(Error::Read { path: path.to_path_buf(), source }),
+    };
+    let original = root.clone();
+    let object = root.as_object_mut().ok_or(Error::NotAnObject)?;
+    if !object.contains_key(PROFILE_KEY) && servers.is_empty() {
+        return Ok(Merged::default());
+    }
+    let entry = object.entry(PROFILE_KEY).or_insert_with(|| Value::Object(Map::new()));
+    let managed = entry.as_object_mut().ok_or(Error::NotAnObject)?;
+    // 保持用户自己添加的服务器的顺序
+    for name in previous {
+        managed.shift_remove(name);
+    }
+    let mut merged = Merged::default();
+    for server in servers {
+        if managed.contains_key(&server.name) {
+            merged.skipped.push(server.name.clone());
+            continue;
+        }
+        managed.insert(server.name.clone(), server.to_claude_value());
+        merged.names.push(server.name.clone());
+    }
+    if root != original {
+        // Claude 运行时也会写 ~/.claude.json
+        files::write_atomically(path, &serde_json::to_string_pretty(&root).map_err(Error::InvalidJson)?)?;
+    }
+    Ok(merged)
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Default, Debug)]
+#[serde(default)]
+pub struct McpSettings {
+    // 项目级服务器写入该目录下的 .mcp.json
+    pub project_dir: Option<PathBuf>,
+    // 上次应用时写入各文件的服务器，下次应用时先移除
+    pub managed: Vec<ManagedServers>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct ManagedServers {
+    pub file: PathBuf,
+    pub names: Vec<String>,
+}
+
+impl McpSettings {
+    pub fn managed_names(&self, file: &Path) -> &[String] {
+        self.managed.iter().find(|m| m.file == file).map_or(&[], |m| &m.names)
+    }
+
+    pub fn set_managed_names(&mut self, file: &Path, names: Vec<String>) {
+        self.managed.retain(|m| m.file != file);
+        if !names.is_empty() {
+            self.managed.push(ManagedServers { file: file.to_path_buf(), names });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn missing_key_is_none_and_empty_object_is_empty() {
+        assert!(from_profile(&json!({"env": {}})).is_none());
+        assert_eq!(from_profile(&json!({"mcpServers": {}})), Some(Vec::new()));
+        let servers = from_profile(&json!({"mcpServers": {"docs": {"url": "https://x"}, "fs": {"command": "npx", "disabled": true}}})).unwrap();
+        assert_eq!(servers[0].transport, Transport::Http);
+        assert!(servers[1].transport == Transport::Stdio && !servers[1].enabled);
+    }
+
+    #[test]
+    fn merge_replaces_only_managed_servers_in_order() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join(".claude.json");
+        fs::write(&file, r#"{"theme":"dark","mcpServers":{"a":{"command":"a"},"old":{"command":"old"},"b":{"command":"b"}}}"#).unwrap();
+        let servers = from_profile(&json!({"mcpServers": {"new": {"command": "new"}}})).unwrap();
+
+        let names = merge_into(&file, &servers, &["old".to_string()]).unwrap().names;
+        assert_eq!(names, ["new"]);
+        let root: Value = serde_json::from_str(&fs::read_to_string(&file).unwrap()).unwrap();
+        assert_eq!(root["theme"], "dark");
+        assert_eq!(root[PROFILE_KEY].as_object().unwrap().keys().collect::<Vec<_>>(), ["a", "b", "new"]);
+
+        merge_into(&file, &[], &names).unwrap();
+        let root: Value = serde_json::from_str(&fs::read_to_string(&file).unwrap()).unwrap();
+        assert_eq!(root[PROFILE_KEY].as_object().unwrap().keys().collect::<Vec<_>>(), ["a", "b"]);
+    }
+
+    #[test]
+    fn merge_skips_servers_named_like_user_servers() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join(".claude.json");
+        fs::write(&file, r#"{"mcpServers":{"docs":{"command":"mine"}}}"#).unwrap();
+        let servers = from_profile(&json!({"mcpServers": {"docs": {"url": "https://x"}, "fs": {"command": "npx"}}})).unwrap();
+
+        let merged = merge_into(&file, &servers, &[]).unwrap();
+        assert_eq!(merged, Merged { names: vec!["fs".to_string()], skipped: vec!["docs".to_string()] });
+        let root: Value = serde_json::from_str(&fs::read_to_string(&file).unwrap()).unwrap();
+        assert_eq!(root[PROFILE_KEY]["docs"], json!({"command": "mine"}));
+
+        // 切换到其他配置时只移除写入过的服务器，用户的同名服务器保留
+        merge_into(&file, &[], &merged.names).unwrap();
+        let root: Value = serde_json::from_str(&fs::read_to_string(&file).unwrap()).unwrap();
+        assert_eq!(root[PROFILE_KEY], json!({"docs": {"command": "mine"}}));
+    }
+}
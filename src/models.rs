@@ -0,0 +1,204 @@
+// 模型选择：配置中指定模型的三个位置、内置的模型目录，以及检查所选模型是否由端点提供
+
+use crate::endpoint::Endpoint;
+use crate::error::Result;
+use crate::format::FormatSettings;
+use crate::outline;
+use crate::t;
+use serde_json::Value;
+use std::collections::HashMap;
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct ModelInfo {
+    pub id: String,
+    pub display_name: String,
+    // 上下文窗口（Token）；模型列表接口不提供，按内置目录补充
+    pub context_window: Option<u64>,
+}
+
+impl ModelInfo {
+    pub fn new(id: &str, display_name: &str, context_window: Option<u64>) -> Self {
+        Self { id: id.to_string(), display_name: display_name.to_string(), context_window }
+    }
+
+    // 形如 Claude Sonnet 4.5 · 200K
+    pub fn label(&self) -> String {
+        match self.context_window {
+            Some(tokens) if tokens >= 1_000_000 => format!("{} · {}M", self.display_name, tokens / 1_000_000),
+            Some(tokens) => format!("{} · {}K", self.display_name, tokens / 1000),
+            None => self.display_name.clone(),
+        }
+    }
+}
+
+// 端点无法提供模型列表时使用
+pub fn catalog() -> Vec<ModelInfo> {
+    let model = |id: &str, name: &str| ModelInfo::new(id, name, Some(200_000));
+    vec![
+        model("claude-sonnet-4-5-20250929", "Claude Sonnet 4.5"),
+        model("claude-haiku-4-5-20251001", "Claude Haiku 4.5"),
+        model("claude-opus-4-1-20250805", "Claude Opus 4.1"),
+        model("claude-opus-4-20250514", "Claude Opus 4"),
+        model("claude-sonnet-4-20250514", "Claude Sonnet 4"),
+        model("claude-3-7-sonnet-20250219", "Claude Sonnet 3.7"),
+        model("claude-3-5-haiku-20241022", "Claude Haiku 3.5"),
+    ]
+}
+
+// Claude Code 自己解析的别名，不需要由端点提供
+pub const ALIASES: [&str; 6] = ["default", "sonnet", "opus", "haiku", "sonnet[1m]", "opusplan"];
+
+// 用接口返回的列表补充内置目录中的上下文窗口
+pub fn with_context_windows(models: Vec<ModelInfo>) -> Vec<ModelInfo> {
+    let catalog = catalog();
+    models
+        .into_iter()
+        .map(|mut model| {
+            if model.context_window.is_none() {
+                model.context_window = catalog.iter().find(|known| known.id == model.id).and_then(|known| known.context_window);
+            }
+            model
+        })
+        .collect()
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum ModelSlot {
+    // 顶层的 model
+    Model,
+    // env.ANTHROPIC_MODEL
+    EnvModel,
+    // env.ANTHROPIC_SMALL_FAST_MODEL，用于后台任务的小模型
+    SmallFast,
+    // env.ANTHROPIC_DEFAULT_HAIKU_MODEL，取代 ANTHROPIC_SMALL_FAST_MODEL；
+    // 面板中不单独编辑，只检查是否可用，改名由配置检查提示
+    Haiku,
+}
+
+impl ModelSlot {
+    // 面板中编辑的位置
+    pub const ALL: [ModelSlot; 3] = [ModelSlot::Model, ModelSlot::EnvModel, ModelSlot::SmallFast];
+    // 检查是否可用的位置
+    pub const CHECKED: [ModelSlot; 4] = [ModelSlot::Model, ModelSlot::EnvModel, ModelSlot::SmallFast, ModelSlot::Haiku];
+
+    pub fn key(self) -> &'static str {
+        match self {
+            ModelSlot::Model => "model",
+            ModelSlot::EnvModel => "ANTHROPIC_MODEL",
+            ModelSlot::SmallFast => "ANTHROPIC_SMALL_FAST_MODEL",
+            ModelSlot::Haiku => "ANTHROPIC_DEFAULT_HAIKU_MODEL",
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            ModelSlot::Model => t!("models.slot.model"),
+            ModelSlot::EnvModel => t!("models.slot.env_model"),
+            ModelSlot::SmallFast => t!("models.slot.small_fast"),
+            ModelSlot::Haiku => t!("models.slot.haiku"),
+        }
+    }
+
+    fn parent(self) -> &'static [&'static str] {
+        match self {
+            ModelSlot::Model => &[],
+            ModelSlot::EnvModel | ModelSlot::SmallFast | ModelSlot::Haiku => &["env"],
+        }
+    }
+
+    pub fn get(self, settings: &Value) -> Option<String> {
+        self.parent()
+            .iter()
+            .try_fold(settings, |value, key| value.get(key))
+            .and_then(|parent| parent.get(self.key()))
+            .and_then(Value::as_str)
+            .map(str::trim)
+            .filter(|id| !id.is_empty())
+            .map(str::to_string)
+    }
+
+    // 空字符串删除该键；其余内容和注释保持不变
+    pub fn set(self, content: &str, id: &str, format: &FormatSettings) -> Result<String> {
+        let id = id.trim();
+        let value = (!id.is_empty()).then(|| Value::from(id));
+        outline::replace_nested_member(content, self.parent(), self.key(), value.as_ref(), format)
+    }
+}
+
+// 别名总是可用；[1m] 后缀（百万上下文）忽略
+pub fn is_offered(id: &str, models: &[ModelInfo]) -> bool {
+    let base = id.strip_suffix("[1m]").unwrap_or(id);
+    ALIASES.contains(&id) || models.iter().any(|model| model.id == base)
+}
+
+// 配置选择了但端点没有提供的模型
+pub fn unavailable(settings: &Value, models: &[ModelInfo]) -> Vec<(ModelSlot, String)> {
+    ModelSlot::CHECKED
+        .into_iter()
+        .filter_map(|slot| slot.get(settings).map(|id| (slot, id)))
+        .filter(|(_, id)| !is_offered(id, models))
+        .collect()
+}
+
+pub struct ProfileCheck {
+    pub profile: String,
+    // 端点不可用时为错误信息
+    pub result: std::result::Result<Vec<(ModelSlot, String)>, String>,
+}
+
+// 检查多个配置（名称, 内容）；同一端点只请求一次，没有指定模型的配置不检查
+pub fn check_profiles(profiles: Vec<(String, Value)>) -> Vec<ProfileCheck> {
+    let mut listed: HashMap<Endpoint, std::result::Result<Vec<ModelInfo>, String>> = HashMap::new();
+    profiles
+        .into_iter()
+        .filter(|(_, settings)| ModelSlot::CHECKED.iter().any(|slot| slot.get(settings).is_some()))
+        .map(|(profile, settings)| {
+            let endpoint = Endpoint::from_settings(&settings);
+            let models = listed.entry(endpoint).or_insert_with_key(|endpoint| endpoint.list_models().map_err(|e| e.to_string()));
+            let result = models.as_ref().map(|models| unavailable(&settings, models)).map_err(Clone::clone);
+            ProfileCheck { profile, result }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lint::{self, Fix};
+    use serde_json::json;
+
+    #[test]
+    fn small_fast_slot_writes_its_own_key() {
+        let content = "{\n  \"env\": {\n    \"A\": \"1\"\n  }\n}\n";
+        let written = ModelSlot::SmallFast.set(content, "claude-haiku-4-5", &FormatSettings::default()).unwrap();
+        let value: Value = serde_json::from_str(&written).unwrap();
+        assert_eq!(value["env"]["ANTHROPIC_SMALL_FAST_MODEL"], "claude-haiku-4-5");
+        assert_eq!(value["env"]["A"], "1");
+        let cleared = ModelSlot::SmallFast.set(&written, " ", &FormatSettings::default()).unwrap();
+        assert_eq!(serde_json::from_str::<Value>(&cleared).unwrap(), json!({"env": {"A": "1"}}));
+    }
+
+    #[test]
+    fn checks_both_background_model_keys() {
+        let models = vec![ModelInfo::new("claude-sonnet-4-5", "Sonnet", None)];
+        let settings = json!({
+            "model": "sonnet",
+            "env": {
+                "ANTHROPIC_MODEL": "claude-sonnet-4-5[1m]",
+                "ANTHROPIC_SMALL_FAST_MODEL": "old-small",
+                "ANTHROPIC_DEFAULT_HAIKU_MODEL": "new-haiku",
+            }
+        });
+        assert_eq!(
+            unavailable(&settings, &models),
+            [(ModelSlot::SmallFast, "old-small".to_string()), (ModelSlot::Haiku, "new-haiku".to_string())]
+        );
+    }
+
+    #[test]
+    fn deprecated_small_fast_key_suggests_rename() {
+        let findings = lint::lint(r#"{"env": {"ANTHROPIC_SMALL_FAST_MODEL": "claude-haiku-4-5"}}"#);
+        let finding = findings.iter().find(|f| f.rule == "deprecated_env").unwrap();
+        assert_eq!(finding.fixes, [Fix::RenameEnv("ANTHROPIC_SMALL_FAST_MODEL".to_string(), "ANTHROPIC_DEFAULT_HAIKU_MODEL".to_string())]);
+    }
+}
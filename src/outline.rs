@@ -0,0 +1,398 @@
+// 配置的大纲：解析出每个节点在原文中的位置，用于大纲树、光标处的 JSON 路径和按节点编辑。
+// 编辑直接修改原文，其余部分的格式和注释保持不变
+
+use crate::error::{Error, Result};
+use crate::format::FormatSettings;
+use crate::jsonc;
+use serde::de::IgnoredAny;
+use std::fmt;
+use std::ops::Range;
+
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub enum Segment {
+    Key(String),
+    Index(usize),
+}
+
+impl fmt::Display for Segment {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Segment::Key(key) => f.write_str(key),
+            Segment::Index(index) => write!(f, "[{}]", index),
+        }
+    }
+}
+
+// 形如 permissions.allow[0]；含特殊字符的键名写成 ["a.b"]
+pub fn path_string(path: &[Segment]) -> String {
+    let mut text = String::new();
+    for segment in path {
+        match segment {
+            Segment::Key(key) if !key.is_empty() && key.chars().all(|c| c.is_alphanumeric() || c == '_' || c == '-') => {
+                if !text.is_empty() {
+                    text.push('.');
+                }
+                text.push_str(key);
+            }
+            Segment::Key(key) => text.push_str(&format!("[{}]", serde_json::Value::from(key.as_str()))),
+            Segment::Index(index) => text.push_str(&format!("[{}]", index)),
+        }
+    }
+    text
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Kind {
+    Object,
+    Array,
+    String,
+    Number,
+    Bool,
+    Null,
+}
+
+impl Kind {
+    // 新增同级节点时使用的空值
+    fn empty_value(self) -> &'static str {
+        match self {
+            Kind::Object => "{}",
+            Kind::Array => "[]",
+            Kind::String => "\"\"",
+            Kind::Number => "0",
+            Kind::Bool => "false",
+            Kind::Null => "null",
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Node {
+    // 根节点为 None
+    pub segment: Option<Segment>,
+    pub kind: Kind,
+    // 标量的原文；对象和数组为空
+    pub preview: String,
+    // 对象成员从键名开始，其余节点就是值本身
+    pub range: Range<usize>,
+    pub key_range: Option<Range<usize>>,
+    pub value_range: Range<usize>,
+    pub children: Vec<Node>,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Edit {
+    Delete,
+    Duplicate,
+    AddSibling,
+}
+
+pub struct Outline {
+    pub root: Node,
+}
+
+impl Outline {
+    pub fn parse(content: &str) -> Result<Self> {
+        let stripped = jsonc::strip(content);
+        serde_json::from_str::<IgnoredAny>(&stripped).map_err(Error::InvalidJson)?;
+        let mut parser = Parser { text: &stripped, pos: 0 };
+        let root = parser.value(None, None).expect("已校验的 JSON 必定能解析");
+        Ok(Self { root })
+    }
+
+    pub fn node(&self, path: &[Segment]) -> Option<&Node> {
+        path.iter().try_fold(&self.root, |node, segment| node.children.iter().find(|c| c.segment.as_ref() == Some(segment)))
+    }
+
+    // 包含该字节偏移的最深节点的路径
+    pub fn path_at(&self, offset: usize) -> Vec<Segment> {
+        let mut path = Vec::new();
+        let mut node = &self.root;
+        while let Some(child) = node.children.iter().find(|c| c.range.start <= offset && offset <= c.range.end) {
+            path.extend(child.segment.clone());
+            node = child;
+        }
+        path
+    }
+
+    // 设置 parent 对象的成员：已存在时替换它的值，否则追加为最后一个成员。
+    // value 是排版好的 JSON，多行时按成员所在行的缩进对齐；unit 是空对象中新增成员时的一级缩进
+    pub fn set_member(&self, content: &str, parent: &[Segment], key: &str, value: &str, unit: &str) -> Option<String> {
+        let parent = self.node(parent).filter(|node| node.kind == Kind::Object)?;
+        let segment = Segment::Key(key.to_string());
+        if let Some(node) = parent.children.iter().find(|c| c.segment.as_ref() == Some(&segment)) {
+            let value = indent_lines(value, line_indent(content, node.range.start).unwrap_or_default());
+            return Some(format!("{}{}{}", &content[..node.value_range.start], value, &content[node.value_range.end..]));
+        }
+        let key = serde_json::Value::from(key);
+        match parent.children.last() {
+            Some(last) => {
+                let indent = line_indent(content, last.range.start).unwrap_or_default();
+                let member = format!(",\n{}{}: {}", indent, key, indent_lines(value, indent));
+                Some(format!("{}{}{}", &content[..last.range.end], member, &content[last.range.end..]))
+            }
+            None => {
+                let range = &parent.value_range;
+                let line_start = content[..range.start].rfind('\n').map_or(0, |i| i + 1);
+                let outer: String = content[line_start..range.start].chars().take_while(|c| c.is_whitespace()).collect();
+                let indent = format!("{}{}", outer, unit);
+                let member = format!("{{\n{}{}: {}\n{}}}", indent, key, indent_lines(value, &indent), outer);
+                Some(format!("{}{}{}", &content[..range.start], member, &content[range.end..]))
+            }
+        }
+    }
+
+    // 返回修改后的内容，以及光标应移动到的位置（新节点的开头或被删除节点原来的位置）
+    pub fn edit(&self, content: &str, path: &[Segment], edit: Edit) -> Option<(String, usize)> {
+        let (last, parent_path) = path.split_last()?;
+        let parent = self.node(parent_path)?;
+        let index = parent.children.iter().position(|c| c.segment.as_ref() == Some(last))?;
+        let node = &parent.children[index];
+        match edit {
+            Edit::Delete => {
+                let (start, end) = if let Some(next) = parent.children.get(index + 1) {
+                    (node.range.start, next.range.start)
+                } else if index > 0 {
+                    // 最后一个元素：连同前面的逗号以及 JSONC 中可能的末尾逗号一起删除
+                    let rest = &content[node.range.end..];
+                    let trailing = rest.len() - rest.trim_start().len();
+                    let end = if rest.trim_start().starts_with(',') { node.range.end + trailing + 1 } else { node.range.end };
+                    (parent.children[index - 1].range.end, end)
+                } else {
+                    (parent.value_range.start + 1, parent.value_range.end - 1)
+                };
+                Some((format!("{}{}", &content[..start], &content[end..]), start))
+            }
+            Edit::Duplicate | Edit::AddSibling => {
+                let text = match (&node.key_range, edit) {
+                    (Some(key_range), Edit::Duplicate) => {
+                        let key = match last {
+                            Segment::Key(key) => unique_key(parent, &format!("{}_copy", key)),
+                            Segment::Index(_) => return None,
+                        };
+                        format!("{}{}", serde_json::Value::from(key), &content[key_range.end..node.range.end])
+                    }
+                    (None, Edit::Duplicate) => content[node.range.clone()].to_string(),
+                    (Some(_), _) => format!("{}: {}", serde_json::Value::from(unique_key(parent, "new_key")), node.kind.empty_value()),
+                    (None, _) => node.kind.empty_value().to_string(),
+                };
+                // 节点独占一行时新节点另起一行并使用相同缩进
+                let separator = match line_indent(content, node.range.start) {
+                    Some(indent) => format!(",\n{}", indent),
+                    None => ", ".to_string(),
+                };
+                let end = node.range.end;
+                Some((format!("{}{}{}{}", &content[..end], separator, text, &content[end..]), end + separator.len()))
+            }
+        }
+    }
+}
+
+// 按格式化设置输出 value 并替换配置根对象中的 key 成员；value 为 None 时删除该成员。
+// 供 MCP、hooks 等结构化编辑器写回配置，其余内容和注释保持不变
+pub fn replace_member(content: &str, key: &str, value: Option<&serde_json::Value>, format: &FormatSettings) -> Result<String> {
+    replace_nested_member(content, &[], key, value, format)
+}
+
+// 与 replace_member 相同，但作用于 parent 路径上的对象（如 env）；路径不存在时一并创建
+pub fn replace_nested_member(content: &str, parent: &[&str], key: &str, value: Option<&serde_json::Value>, format: &FormatSettings) -> Result<String> {
+    let outline = Outline::parse(content)?;
+    let path: Vec<Segment> = parent.iter().chain([&key]).map(|k| Segment::Key(k.to_string())).collect();
+    let Some(value) = value else {
+        return Ok(outline.edit(content, &path, Edit::Delete).map_or_else(|| content.to_string(), |(content, _)| content));
+    };
+    // 最深的已存在的节点必须是对象，缺少的各层包装到值中；不覆盖路径上已有的其他类型的值
+    let depth = (0..path.len()).rev().find(|&depth| outline.node(&path[..depth]).is_some()).unwrap_or_default();
+    if outline.node(&path[..depth]).is_none_or(|node| node.kind != Kind::Object) {
+        return Err(Error::NotAnObject);
+    }
+    let value = path[depth + 1..].iter().rev().fold(value.clone(), |value, segment| serde_json::json!({ segment.to_string(): value }));
+    let format = FormatSettings { final_newline: false, ..format.clone() };
+    let Segment::Key(key) = &path[depth] else { unreachable!("路径只包含键名") };
+    outline.set_member(content, &path[..depth], key, &format.format_value(&value)?, &format.indent_unit()).ok_or(Error::NotAnObject)
+}
+
+// 只改写 parent 路径上对象中 key 成员的键名，成员不存在时原样返回
+pub fn rename_nested_member(content: &str, parent: &[&str], key: &str, new_key: &str) -> Result<String> {
+    let outline = Outline::parse(content)?;
+    let path: Vec<Segment> = parent.iter().chain([&key]).map(|k| Segment::Key(k.to_string())).collect();
+    let Some(range) = outline.node(&path).and_then(|node| node.key_range.clone()) else { return Ok(content.to_string()) };
+    Ok(format!("{}{}{}", &content[..range.start], serde_json::Value::from(new_key), &content[range.end..]))
+}
+
+// 位置前面只有空白时返回这段缩进
+fn line_indent(content: &str, offset: usize) -> Option<&str> {
+    let line_start = content[..offset].rfind('\n').map_or(0, |i| i + 1);
+    let indent = &content[line_start..offset];
+    indent.trim().is_empty().then_some(indent)
+}
+
+fn indent_lines(text: &str, indent: &str) -> String {
+    text.lines().collect::<Vec<_>>().join(&format!("\n{}", indent))
+}
+
+fn unique_key(parent: &Node, base: &str) -> String {
+    let exists = |key: &str| parent.children.iter().any(|c| c.segment == Some(Segment::Key(key.to_string())));
+    let mut key = base.to_string();
+    let mut n = 2;
+    while exists(&key) {
+        key = format!("{}{}", base, n);
+        n += 1;
+    }
+    key
+}
+
+// 解析已去掉注释和末尾逗号的 JSON，字节偏移与原文一致
+struct Parser<'a> {
+    text: &'a str,
+    pos: usize,
+}
+
+impl Parser<'_> {
+    fn peek(&self) -> Option<u8> {
+        self.text.as_bytes().get(self.pos).copied()
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.peek().is_some_and(|b| b.is_ascii_whitespace()) {
+            self.pos += 1;
+        }
+    }
+
+    fn string(&mut self) -> Option<Range<usize>> {
+        let start = self.pos;
+        self.pos += 1;
+        loop {
+            match self.peek()? {
+                b'\\' => self.pos += 2,
+                b'"' => break,
+                _ => self.pos += 1,
+            }
+        }
+        self.pos += 1;
+        Some(start..self.pos)
+    }
+
+    fn value(&mut self, segment: Option<Segment>, key_range: Option<Range<usize>>) -> Option<Node> {
+        self.skip_whitespace();
+        let start = self.pos;
+        let mut children = Vec::new();
+        let kind = match self.peek()? {
+            b'{' => {
+                self.pos += 1;
+                loop {
+                    self.skip_whitespace();
+                    if self.peek()? == b'}' {
+                        break;
+                    }
+                    let key_range = self.string()?;
+                    let key: String = serde_json::from_str(&self.text[key_range.clone()]).ok()?;
+                    self.skip_whitespace();
+                    self.pos += 1;
+                    children.push(self.value(Some(Segment::Key(key)), Some(key_range))?);
+                    self.skip_whitespace();
+                    if self.peek()? == b',' {
+                        self.pos += 1;
+                    }
+                }
+                self.pos += 1;
+                Kind::Object
+            }
+            b'[' => {
+                self.pos += 1;
+                loop {
+                    self.skip_whitespace();
+                    if self.peek()? == b']' {
+                        break;
+                    }
+                    children.push(self.value(Some(Segment::Index(children.len())), None)?);
+                    self.skip_whitespace();
+                    if self.peek()? == b',' {
+                        self.pos += 1;
+                    }
+                }
+                self.pos += 1;
+                Kind::Array
+            }
+            b'"' => {
+                self.string()?;
+                Kind::String
+            }
+            b => {
+                while self.peek().is_some_and(|b| b.is_ascii_alphanumeric() || matches!(b, b'-' | b'+' | b'.')) {
+                    self.pos += 1;
+                }
+                match b {
+                    b't' | b'f' => Kind::Bool,
+                    b'n' => Kind::Null,
+                    _ => Kind::Number,
+                }
+            }
+        };
+        let value_range = start..self.pos;
+        let preview = if matches!(kind, Kind::Object | Kind::Array) { String::new() } else { self.text[value_range.clone()].to_string() };
+        Some(Node {
+            segment,
+            kind,
+            preview,
+            range: key_range.as_ref().map_or(start, |r| r.start)..self.pos,
+            key_range,
+            value_range,
+            children,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    const CONTENT: &str = "{\n  // 注释\n  \"env\": {\n    \"A\": \"1\",\n    \"B\": \"2\"\n  },\n  \"list\": [1, 2]\n}";
+
+    fn key(key: &str) -> Segment {
+        Segment::Key(key.to_string())
+    }
+
+    fn edit(path: &[Segment], edit: Edit) -> String {
+        Outline::parse(CONTENT).unwrap().edit(CONTENT, path, edit).unwrap().0
+    }
+
+    #[test]
+    fn path_at_and_path_string() {
+        let outline = Outline::parse(CONTENT).unwrap();
+        let offset = CONTENT.find("\"2\"").unwrap();
+        assert_eq!(outline.path_at(offset), [key("env"), key("B")]);
+        assert_eq!(path_string(&outline.path_at(CONTENT.rfind('2').unwrap())), "list[1]");
+        assert_eq!(path_string(&[key("a.b"), Segment::Index(0)]), "[\"a.b\"][0]");
+    }
+
+    #[test]
+    fn edit_deletes_members_and_keeps_comments() {
+        assert_eq!(edit(&[key("env"), key("A")], Edit::Delete), CONTENT.replace("\"A\": \"1\",\n    ", ""));
+        assert_eq!(edit(&[key("env"), key("B")], Edit::Delete), CONTENT.replace(",\n    \"B\": \"2\"", ""));
+        assert_eq!(edit(&[key("list"), Segment::Index(1)], Edit::Delete), CONTENT.replace("[1, 2]", "[1]"));
+        // JSONC 中最后一个成员后的末尾逗号一起删除
+        let content = "{\"a\": 1, \"b\": 2,}";
+        let outline = Outline::parse(content).unwrap();
+        assert_eq!(outline.edit(content, &[key("b")], Edit::Delete).unwrap().0, "{\"a\": 1}");
+    }
+
+    #[test]
+    fn edit_duplicates_and_adds_siblings() {
+        assert_eq!(edit(&[key("env"), key("B")], Edit::Duplicate), CONTENT.replace("\"B\": \"2\"", "\"B\": \"2\",\n    \"B_copy\": \"2\""));
+        assert_eq!(edit(&[key("env"), key("A")], Edit::AddSibling), CONTENT.replace("\"A\": \"1\",", "\"A\": \"1\",\n    \"new_key\": \"\","));
+        assert_eq!(edit(&[key("list"), Segment::Index(0)], Edit::Duplicate), CONTENT.replace("[1, 2]", "[1, 1, 2]"));
+    }
+
+    #[test]
+    fn replace_nested_member_creates_missing_objects() {
+        let format = FormatSettings::default();
+        let replaced = replace_nested_member(CONTENT, &["env"], "A", Some(&json!("x")), &format).unwrap();
+        assert_eq!(replaced, CONTENT.replace("\"A\": \"1\"", "\"A\": \"x\""));
+        let added = replace_nested_member(CONTENT, &["permissions"], "allow", Some(&json!(["Bash"])), &format).unwrap();
+        assert!(added.ends_with("\"list\": [1, 2],\n  \"permissions\": {\n    \"allow\": [\n      \"Bash\"\n    ]\n  }\n}"), "{}", added);
+        let removed = replace_nested_member(CONTENT, &["env"], "missing", None, &format).unwrap();
+        assert_eq!(removed, CONTENT);
+        assert!(matches!(replace_nested_member(CONTENT, &["list"], "a", Some(&json!(1)), &format), Err(Error::NotAnObject)));
+    }
+}
@@ -0,0 +1,166 @@
+// 目录布局：所有路径都从可注入的主目录推导；Claude 配置目录可以由 CLAUDE_CONFIG_DIR 或应用设置指定
+
+use crate::t;
+use directories::UserDirs;
+use std::path::{Path, PathBuf};
+
+pub const DEFAULT_CONFIG_DIR_NAME: &str = ".claude";  // Claude AI 配置目录
+pub const ACTIVE_CONFIG_NAME: &str = "settings.json";
+pub const APP_SETTINGS_NAME: &str = "app_settings.json";
+pub const APP_DIR_NAME: &str = ".claude-code-switcher";  // 应用程序目录
+pub const SETTINGS_SUBDIR: &str = "settings";  // 配置文件子目录
+pub const CLAUDE_CONFIG_DIR_VAR: &str = "CLAUDE_CONFIG_DIR";
+
+// Claude 配置目录的来源，按优先级排列
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ClaudeDirSource {
+    Settings,
+    Environment,
+    Default,
+}
+
+impl ClaudeDirSource {
+    pub fn label(self) -> &'static str {
+        match self {
+            ClaudeDirSource::Settings => t!("claude_dir.source.settings"),
+            ClaudeDirSource::Environment => t!("claude_dir.source.environment"),
+            ClaudeDirSource::Default => t!("claude_dir.source.default"),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Paths {
+    home: PathBuf,
+    // CLAUDE_CONFIG_DIR 环境变量
+    env_claude_dir: Option<PathBuf>,
+    // 应用设置中指定的目录，优先于环境变量
+    claude_dir_override: Option<PathBuf>,
+}
+
+impl Paths {
+    pub fn new(home: impl Into<PathBuf>) -> Self {
+        Self { home: home.into(), env_claude_dir: None, claude_dir_override: None }
+    }
+
+    // 当前用户的主目录，无法获取时使用相对路径
+    pub fn from_env() -> Self {
+        let paths = match UserDirs::new() {
+            Some(user_dirs) => Self::new(user_dirs.home_dir()),
+            None => Self::new(PathBuf::new()),
+        };
+        let dir = std::env::var_os(CLAUDE_CONFIG_DIR_VAR).map(PathBuf::from).filter(|dir| !dir.as_os_str().is_empty());
+        paths.with_env_claude_dir(dir)
+    }
+
+    pub fn with_env_claude_dir(mut self, dir: Option<PathBuf>) -> Self {
+        self.env_claude_dir = dir.map(|dir| self.expand_home(&dir));
+        self
+    }
+
+    pub fn set_claude_dir_override(&mut self, dir: Option<PathBuf>) {
+        self.claude_dir_override = dir.filter(|dir| !dir.as_os_str().is_empty()).map(|dir| self.expand_home(&dir));
+    }
+
+    // 以 ~ 开头的路径相对于主目录
+    pub fn expand_home(&self, path: &Path) -> PathBuf {
+        match path.strip_prefix("~") {
+            Ok(rest) => self.home.join(rest),
+            Err(_) => path.to_path_buf(),
+        }
+    }
+
+    pub fn home(&self) -> &Path {
+        &self.home
+    }
+
+    pub fn app_dir(&self) -> PathBuf {
+        self.home.join(APP_DIR_NAME)
+    }
+
+    pub fn app_settings_file(&self) -> PathBuf {
+        self.app_dir().join(APP_SETTINGS_NAME)
+    }
+
+    // Claude 活动配置、会话记录等所在的目录；所有 Claude 路径都从这里推导
+    pub fn claude_dir(&self) -> PathBuf {
+        match (&self.claude_dir_override, &self.env_claude_dir) {
+            (Some(dir), _) | (None, Some(dir)) => dir.clone(),
+            (None, None) => self.home.join(DEFAULT_CONFIG_DIR_NAME),
+        }
+    }
+
+    pub fn claude_dir_source(&self) -> ClaudeDirSource {
+        if self.claude_dir_override.is_some() {
+            ClaudeDirSource::Settings
+        } else if self.env_claude_dir.is_some() {
+            ClaudeDirSource::Environment
+        } else {
+            ClaudeDirSource::Default
+        }
+    }
+
+    // Claude Code 的用户级配置，用户级 MCP 服务器保存在这里；指定了配置目录时位于该目录中
+    pub fn claude_user_config_file(&self) -> PathBuf {
+        match self.claude_dir_source() {
+            ClaudeDirSource::Default => self.home.join(".claude.json"),
+            _ => self.claude_dir().join(".claude.json"),
+        }
+    }
+
+    pub fn claude_desktop_config_file(&self) -> PathBuf {
+        let dir = if cfg!(target_os = "macos") {
+            self.home.join("Library").join("Application Support")
+        } else if cfg!(windows) {
+            self.home.join("AppData").join("Roaming")
+        } else {
+            self.home.join(".config")
+        };
+        dir.join("Claude").join("claude_desktop_config.json")
+    }
+
+    pub fn claude_projects_dir(&self) -> PathBuf {
+        self.claude_dir().join("projects")
+    }
+
+    pub fn schedule_file(&self) -> PathBuf {
+        self.app_dir().join(crate::schedule::SCHEDULE_FILE_NAME)
+    }
+
+    pub fn audit_log_file(&self) -> PathBuf {
+        self.app_dir().join(crate::audit::AUDIT_LOG_NAME)
+    }
+
+    pub fn pricing_file(&self) -> PathBuf {
+        self.app_dir().join(crate::usage::PRICING_FILE_NAME)
+    }
+
+    pub fn control_run_dir(&self) -> PathBuf {
+        self.app_dir().join("run")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn claude_dir_prefers_override_then_env_then_default() {
+        let mut paths = Paths::new("/home/u");
+        assert_eq!(paths.claude_dir(), PathBuf::from("/home/u/.claude"));
+        assert_eq!(paths.claude_user_config_file(), PathBuf::from("/home/u/.claude.json"));
+
+        paths = paths.with_env_claude_dir(Some(PathBuf::from("~/env")));
+        assert_eq!(paths.claude_dir(), PathBuf::from("/home/u/env"));
+        assert_eq!(paths.claude_dir_source(), ClaudeDirSource::Environment);
+        assert_eq!(paths.claude_user_config_file(), PathBuf::from("/home/u/env/.claude.json"));
+
+        paths.set_claude_dir_override(Some(PathBuf::from("/srv/claude")));
+        assert_eq!(paths.claude_dir(), PathBuf::from("/srv/claude"));
+        assert_eq!(paths.claude_dir_source(), ClaudeDirSource::Settings);
+
+        // 空路径视为未设置
+        paths.set_claude_dir_override(Some(PathBuf::new()));
+        assert_eq!(paths.claude_dir_source(), ClaudeDirSource::Environment);
+    }
+}
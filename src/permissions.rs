@@ -0,0 +1,483 @@
+// Claude Code 的权限规则：permissions 中的 allow / ask / deny 列表、附加目录和默认模式。
+// 提供规则的语法检查、列表之间的冲突检测，以及按 Claude Code 的优先级（deny > ask > allow）判断一次工具调用命中哪条规则
+
+use crate::lint::Severity;
+use crate::t;
+use serde_json::{Map, Value};
+use std::path::Path;
+
+pub const PROFILE_KEY: &str = "permissions";
+
+pub const DEFAULT_MODES: [&str; 4] = ["default", "acceptEdits", "plan", "bypassPermissions"];
+
+// 内置工具；其余名称（MCP 工具除外）给出警告，因为可能是拼写错误
+pub const TOOLS: [&str; 15] = [
+    "Bash",
+    "Read",
+    "Edit",
+    "Write",
+    "MultiEdit",
+    "Glob",
+    "Grep",
+    "LS",
+    "WebFetch",
+    "WebSearch",
+    "NotebookRead",
+    "NotebookEdit",
+    "Task",
+    "TodoWrite",
+    "ExitPlanMode",
+];
+
+// Edit 规则作用于所有修改文件的工具，Read 规则作用于所有读取文件的工具
+const EDIT_TOOLS: [&str; 4] = ["Edit", "Write", "MultiEdit", "NotebookEdit"];
+const READ_TOOLS: [&str; 5] = ["Read", "Glob", "Grep", "LS", "NotebookRead"];
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum RuleList {
+    Allow,
+    Ask,
+    Deny,
+}
+
+impl RuleList {
+    pub const ALL: [RuleList; 3] = [RuleList::Allow, RuleList::Ask, RuleList::Deny];
+
+    pub fn key(self) -> &'static str {
+        match self {
+            RuleList::Allow => "allow",
+            RuleList::Ask => "ask",
+            RuleList::Deny => "deny",
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            RuleList::Allow => t!("permissions.list.allow"),
+            RuleList::Ask => t!("permissions.list.ask"),
+            RuleList::Deny => t!("permissions.list.deny"),
+        }
+    }
+}
+
+// Tool 或 Tool(specifier)；工具调用也用同样的写法表示，如 Bash(git push)
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Rule {
+    pub tool: String,
+    pub specifier: Option<String>,
+}
+
+impl Rule {
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let text = text.trim();
+        if text.is_empty() {
+            return Err(t!("permissions.syntax.empty").to_string());
+        }
+        let (tool, specifier) = match text.find('(') {
+            Some(open) => {
+                let Some(inner) = text[open + 1..].strip_suffix(')') else {
+                    return Err(t!("permissions.syntax.unclosed").to_string());
+                };
+                (&text[..open], Some(inner.to_string()))
+            }
+            None if text.contains(')') => return Err(t!("permissions.syntax.unopened").to_string()),
+            None => (text, None),
+        };
+        if tool.is_empty() || !tool.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-' || c == '*') {
+            return Err(t!("permissions.syntax.tool_name", tool = tool));
+        }
+        if specifier.as_deref().is_some_and(|s| s.trim().is_empty()) {
+            return Err(t!("permissions.syntax.empty_specifier").to_string());
+        }
+        Ok(Self { tool: tool.to_string(), specifier })
+    }
+
+    fn is_mcp(&self) -> bool {
+        self.tool.starts_with("mcp__")
+    }
+}
+
+impl std::fmt::Display for Rule {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.specifier {
+            Some(specifier) => write!(f, "{}({})", self.tool, specifier),
+            None => f.write_str(&self.tool),
+        }
+    }
+}
+
+// 语法错误之外，可能写错但 Claude Code 仍会接受的写法给出警告
+pub fn check_rule(text: &str) -> Vec<(Severity, String)> {
+    let rule = match Rule::parse(text) {
+        Ok(rule) => rule,
+        Err(e) => return vec![(Severity::Error, e)],
+    };
+    let mut problems = Vec::new();
+    if rule.is_mcp() {
+        if rule.specifier.is_some() {
+            problems.push((Severity::Error, t!("permissions.syntax.mcp_specifier").to_string()));
+        }
+        return problems;
+    }
+    if !TOOLS.contains(&rule.tool.as_str()) {
+        problems.push((Severity::Warning, t!("permissions.syntax.unknown_tool", tool = rule.tool)));
+    }
+    let Some(specifier) = &rule.specifier else { return problems };
+    match rule.tool.as_str() {
+        "Bash" => {
+            if let Some(index) = specifier.find(":*") {
+                if index + 2 != specifier.len() {
+                    problems.push((Severity::Error, t!("permissions.syntax.bash_prefix").to_string()));
+                }
+            }
+        }
+        "WebFetch" if !specifier.starts_with("domain:") => {
+            problems.push((Severity::Error, t!("permissions.syntax.webfetch_domain").to_string()));
+        }
+        "WebSearch" | "Task" | "TodoWrite" | "ExitPlanMode" => {
+            problems.push((Severity::Warning, t!("permissions.syntax.no_specifier", tool = rule.tool)));
+        }
+        _ => {}
+    }
+    problems
+}
+
+#[derive(Clone, PartialEq, Debug, Default)]
+pub struct Permissions {
+    pub allow: Vec<String>,
+    pub ask: Vec<String>,
+    pub deny: Vec<String>,
+    pub additional_directories: Vec<String>,
+    pub default_mode: Option<String>,
+    // 其余字段原样保留
+    pub extra: Map<String, Value>,
+}
+
+// 同一条规则出现在两个列表中，或在同一列表中重复
+#[derive(Clone, PartialEq, Debug)]
+pub struct Conflict {
+    pub rule: String,
+    pub first: RuleList,
+    pub second: RuleList,
+}
+
+impl Conflict {
+    pub fn message(&self) -> String {
+        if self.first == self.second {
+            t!("permissions.conflict.duplicate", rule = self.rule, list = self.first.key())
+        } else {
+            // 列表按优先级从低到高检查，后出现的列表生效
+            t!("permissions.conflict.lists", rule = self.rule, first = self.first.key(), second = self.second.key())
+        }
+    }
+}
+
+// 工具调用命中的规则；列表决定结果
+#[derive(Clone, PartialEq, Debug)]
+pub struct RuleMatch {
+    pub list: RuleList,
+    pub index: usize,
+    pub rule: String,
+}
+
+impl Permissions {
+    pub fn from_settings(settings: &Value) -> Self {
+        let Some(object) = settings.get(PROFILE_KEY).and_then(Value::as_object) else { return Self::default() };
+        let strings = |key: &str| {
+            object
+                .get(key)
+                .and_then(Value::as_array)
+                .map_or_else(Vec::new, |items| items.iter().filter_map(Value::as_str).map(str::to_string).collect())
+        };
+        let extra = object
+            .iter()
+            .filter(|(key, _)| !matches!(key.as_str(), "allow" | "ask" | "deny" | "additionalDirectories" | "defaultMode"))
+            .map(|(key, value)| (key.clone(), value.clone()))
+            .collect();
+        Self {
+            allow: strings("allow"),
+            ask: strings("ask"),
+            deny: strings("deny"),
+            additional_directories: strings("additionalDirectories"),
+            default_mode: object.get("defaultMode").and_then(Value::as_str).map(str::to_string),
+            extra,
+        }
+    }
+
+    // 空列表不写出；什么都没有时返回 None，写回配置时删除 permissions
+    pub fn to_value(&self) -> Option<Value> {
+        let mut object = Map::new();
+        for list in RuleList::ALL {
+            let rules: Vec<&str> = self.list(list).iter().map(|r| r.trim()).filter(|r| !r.is_empty()).collect();
+            if !rules.is_empty() {
+                object.insert(list.key().to_string(), rules.into());
+            }
+        }
+        let directories: Vec<&str> = self.additional_directories.iter().map(|d| d.trim()).filter(|d| !d.is_empty()).collect();
+        if !directories.is_empty() {
+            object.insert("additionalDirectories".to_string(), directories.into());
+        }
+        if let Some(mode) = &self.default_mode {
+            object.insert("defaultMode".to_string(), mode.clone().into());
+        }
+        object.extend(self.extra.clone());
+        (!object.is_empty()).then_some(Value::Object(object))
+    }
+
+    pub fn list(&self, list: RuleList) -> &Vec<String> {
+        match list {
+            RuleList::Allow => &self.allow,
+            RuleList::Ask => &self.ask,
+            RuleList::Deny => &self.deny,
+        }
+    }
+
+    pub fn list_mut(&mut self, list: RuleList) -> &mut Vec<String> {
+        match list {
+            RuleList::Allow => &mut self.allow,
+            RuleList::Ask => &mut self.ask,
+            RuleList::Deny => &mut self.deny,
+        }
+    }
+
+    // 忽略空白差异比较规则
+    pub fn conflicts(&self) -> Vec<Conflict> {
+        let mut seen: Vec<(String, RuleList)> = Vec::new();
+        let mut conflicts = Vec::new();
+        for list in RuleList::ALL {
+            for rule in self.list(list) {
+                let key: String = rule.split_whitespace().collect::<Vec<_>>().join(" ");
+                if key.is_empty() {
+                    continue;
+                }
+                if let Some((_, first)) = seen.iter().find(|(seen, _)| *seen == key) {
+                    conflicts.push(Conflict { rule: rule.trim().to_string(), first: *first, second: list });
+                } else {
+                    seen.push((key, list));
+                }
+            }
+        }
+        conflicts
+    }
+
+    // deny 优先，其次 ask，最后 allow；都不命中时返回 None，由默认模式决定。
+    // Bash 的组合命令（&&、||、;、|）逐段判断：任何一段被拒绝或需要询问则整体如此，所有段都被允许才算允许
+    pub fn evaluate(&self, call: &str, home: &Path, project_dir: Option<&Path>) -> Option<RuleMatch> {
+        let call = Rule::parse(call).ok()?;
+        let calls = match (&call.tool[..], &call.specifier) {
+            ("Bash", Some(command)) => split_commands(command)
+                .into_iter()
+                .map(|command| Rule { tool: call.tool.clone(), specifier: Some(command) })
+                .collect(),
+            _ => vec![call],
+        };
+        let find = |list: RuleList, call: &Rule| {
+            self.list(list).iter().enumerate().find_map(|(index, text)| {
+                let rule = Rule::parse(text).ok()?;
+                matches(&rule, call, home, project_dir).then(|| RuleMatch { list, index, rule: text.trim().to_string() })
+            })
+        };
+        for list in [RuleList::Deny, RuleList::Ask] {
+            if let Some(found) = calls.iter().find_map(|call| find(list, call)) {
+                return Some(found);
+            }
+        }
+        let allowed: Vec<RuleMatch> = calls.iter().map_while(|call| find(RuleList::Allow, call)).collect();
+        (allowed.len() == calls.len()).then(|| allowed.into_iter().next()).flatten()
+    }
+}
+
+// 按 &&、||、;、| 和后台运行的 & 拆分；>&、&>、<& 和 >| 是重定向，不拆分
+fn split_commands(command: &str) -> Vec<String> {
+    let mut commands = Vec::new();
+    let mut current = String::new();
+    let mut quote = None;
+    let mut chars = command.chars().peekable();
+    while let Some(c) = chars.next() {
+        match (quote, c) {
+            (Some(q), c) if c == q => {
+                quote = None;
+                current.push(c);
+            }
+            (None, '\'' | '"') => {
+                quote = Some(c);
+                current.push(c);
+            }
+            (None, '\\') => {
+                current.push(c);
+                current.extend(chars.next());
+            }
+            (None, '&') if current.ends_with(['>', '<']) || chars.peek() == Some(&'>') => current.push(c),
+            (None, '|') if current.ends_with('>') => current.push(c),
+            (None, '&' | '|' | ';') => {
+                // && 和 || 是一个分隔符；|& 是同时传递标准错误的管道
+                if chars.peek() == Some(&c) || (c == '|' && chars.peek() == Some(&'&')) {
+                    chars.next();
+                }
+                commands.push(std::mem::take(&mut current));
+            }
+            _ => current.push(c),
+        }
+    }
+    commands.push(current);
+    commands.into_iter().map(|c| c.trim().to_string()).filter(|c| !c.is_empty()).collect()
+}
+
+fn tool_matches(rule: &str, call: &str) -> bool {
+    if rule == call {
+        return true;
+    }
+    if let Some(server) = rule.strip_prefix("mcp__") {
+        // mcp__server 和 mcp__server__* 匹配该服务器的所有工具
+        let server = server.trim_end_matches("__*");
+        return call.strip_prefix("mcp__").and_then(|c| c.strip_prefix(server)).is_some_and(|rest| rest.starts_with("__"));
+    }
+    (rule == "Edit" && EDIT_TOOLS.contains(&call)) || (rule == "Read" && READ_TOOLS.contains(&call))
+}
+
+fn matches(rule: &Rule, call: &Rule, home: &Path, project_dir: Option<&Path>) -> bool {
+    if !tool_matches(&rule.tool, &call.tool) {
+        return false;
+    }
+    let (Some(pattern), Some(argument)) = (&rule.specifier, &call.specifier) else {
+        // 没有限定的规则匹配该工具的所有调用；有限定的规则不匹配没有参数的调用
+        return rule.specifier.is_none();
+    };
+    match rule.tool.as_str() {
+        "Bash" => match pattern.strip_suffix(":*") {
+            Some(prefix) => argument.starts_with(prefix),
+            None => pattern == "*" || argument.trim() == pattern.trim(),
+        },
+        "WebFetch" => {
+            let host = argument.strip_prefix("domain:").unwrap_or_else(|| {
+                let rest = argument.split_once("://").map_or(argument.as_str(), |(_, rest)| rest);
+                rest.split(['/', ':', '?']).next().unwrap_or_default()
+            });
+            pattern.strip_prefix("domain:").is_some_and(|domain| domain.eq_ignore_ascii_case(host))
+        }
+        tool if EDIT_TOOLS.contains(&tool) || READ_TOOLS.contains(&tool) => {
+            glob_match(&resolve_pattern(pattern, home, project_dir), &resolve_path(argument, home, project_dir))
+        }
+        _ => pattern == argument,
+    }
+}
+
+// 规则中的路径：//path 是绝对路径，~/path 相对于主目录，/path 相对于项目目录，
+// 其余相对于当前目录；不含 / 的模式与 gitignore 一样匹配任意层级
+fn resolve_pattern(pattern: &str, home: &Path, project_dir: Option<&Path>) -> String {
+    if let Some(absolute) = pattern.strip_prefix("//") {
+        return format!("/{}", absolute);
+    }
+    if let Some(rest) = pattern.strip_prefix("~/") {
+        return join(&home.to_string_lossy(), rest);
+    }
+    let relative = pattern.strip_prefix("./").unwrap_or(pattern);
+    if !relative.contains('/') {
+        return format!("**/{}", relative);
+    }
+    let relative = relative.strip_prefix('/').unwrap_or(relative);
+    match project_dir {
+        Some(dir) => join(&dir.to_string_lossy(), relative),
+        None => relative.to_string(),
+    }
+}
+
+fn resolve_path(path: &str, home: &Path, project_dir: Option<&Path>) -> String {
+    if let Some(rest) = path.strip_prefix("~/") {
+        return join(&home.to_string_lossy(), rest);
+    }
+    if path.starts_with('/') {
+        return path.to_string();
+    }
+    let relative = path.strip_prefix("./").unwrap_or(path);
+    match project_dir {
+        Some(dir) => join(&dir.to_string_lossy(), relative),
+        None => relative.to_string(),
+    }
+}
+
+fn join(dir: &str, rest: &str) -> String {
+    format!("{}/{}", dir.trim_end_matches('/'), rest)
+}
+
+// * 不跨越 /，** 匹配任意层级，? 匹配单个字符
+fn glob_match(pattern: &str, path: &str) -> bool {
+    fn go(pattern: &[u8], path: &[u8]) -> bool {
+        match pattern {
+            [] => path.is_empty(),
+            [b'*', b'*', b'/', rest @ ..] => go(rest, path) || (0..path.len()).any(|i| path[i] == b'/' && go(rest, &path[i + 1..])),
+            [b'*', b'*', rest @ ..] => (0..=path.len()).any(|i| go(rest, &path[i..])),
+            [b'*', rest @ ..] => (0..=path.len()).take_while(|&i| i == 0 || path[i - 1] != b'/').any(|i| go(rest, &path[i..])),
+            [b'?', rest @ ..] => path.first().is_some_and(|&c| c != b'/') && go(rest, &path[1..]),
+            [c, rest @ ..] => path.first() == Some(c) && go(rest, &path[1..]),
+        }
+    }
+    // 匹配目录时包含其下的所有文件
+    go(pattern.as_bytes(), path.as_bytes()) || go(format!("{}/**", pattern.trim_end_matches('/')).as_bytes(), path.as_bytes())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn splits_on_operators_but_not_redirections() {
+        let cases: &[(&str, &[&str])] = &[
+            ("git add . && git commit -m 'a && b'", &["git add .", "git commit -m 'a && b'"]),
+            ("a || b; c", &["a", "b", "c"]),
+            ("cat log | grep x |& tee out", &["cat log", "grep x", "tee out"]),
+            ("make 2>&1", &["make 2>&1"]),
+            ("cmd &> log", &["cmd &> log"]),
+            ("cmd &>> log", &["cmd &>> log"]),
+            ("read x <&3", &["read x <&3"]),
+            ("echo hi >| out", &["echo hi >| out"]),
+            ("make 2>&1 | tee build.log", &["make 2>&1", "tee build.log"]),
+            ("server & curl localhost", &["server", "curl localhost"]),
+            ("sleep 1 &", &["sleep 1"]),
+            (r"echo a\&b", &[r"echo a\&b"]),
+        ];
+        for (command, expected) in cases {
+            assert_eq!(split_commands(command), *expected, "{}", command);
+        }
+    }
+
+    #[test]
+    fn glob_matches_segments() {
+        assert!(glob_match("/p/src/*.rs", "/p/src/main.rs"));
+        assert!(!glob_match("/p/src/*.rs", "/p/src/a/main.rs"));
+        assert!(glob_match("/p/**/*.rs", "/p/src/a/main.rs"));
+        assert!(glob_match("**/.env", "/p/.env"));
+        assert!(glob_match("/p/secrets", "/p/secrets/key.pem"));
+        assert!(glob_match("/p/?.txt", "/p/a.txt") && !glob_match("/p/?.txt", "/p/ab.txt"));
+    }
+
+    fn permissions(allow: &[&str], ask: &[&str], deny: &[&str]) -> Permissions {
+        let strings = |rules: &[&str]| rules.iter().map(|r| r.to_string()).collect();
+        Permissions { allow: strings(allow), ask: strings(ask), deny: strings(deny), ..Default::default() }
+    }
+
+    #[test]
+    fn evaluates_by_priority_and_per_command() {
+        let home = Path::new("/home/u");
+        let project = Some(Path::new("/p"));
+        let rules = permissions(&["Bash(make:*)", "Bash(tee build.log)", "Read"], &["Bash(git push:*)"], &["Read(~/.ssh/**)", "Edit(.env)"]);
+        let list = |call: &str| rules.evaluate(call, home, project).map(|m| m.list);
+
+        assert_eq!(list("Bash(make 2>&1)"), Some(RuleList::Allow));
+        assert_eq!(list("Bash(make 2>&1 | tee build.log)"), Some(RuleList::Allow));
+        assert_eq!(list("Bash(make && git push origin main)"), Some(RuleList::Ask));
+        assert_eq!(list("Bash(make && rm -rf /)"), None);
+        assert_eq!(list("Read(~/.ssh/id_rsa)"), Some(RuleList::Deny));
+        assert_eq!(list("Read(src/main.rs)"), Some(RuleList::Allow));
+        assert_eq!(list("Write(config/.env)"), Some(RuleList::Deny));
+    }
+
+    #[test]
+    fn checks_rule_syntax() {
+        assert!(Rule::parse("Bash(ls").is_err());
+        assert_eq!(Rule::parse(" Bash(git diff:*) ").unwrap().to_string(), "Bash(git diff:*)");
+        assert_eq!(check_rule("Bash(git:* --force)")[0].0, Severity::Error);
+        assert_eq!(check_rule("WebFetch(example.com)")[0].0, Severity::Error);
+        assert!(check_rule("mcp__github").is_empty());
+    }
+}
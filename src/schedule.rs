@@ -0,0 +1,604 @@
+// 定时自动切换：基于 cron 表达式和日期范围的切换规则
+
+use crate::files;
+use crate::t;
+use chrono::{Datelike, Duration, NaiveDate, NaiveDateTime, Timelike};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+
+pub const SCHEDULE_FILE_NAME: &str = "schedule.json";
+
+const DATE_TIME_FORMAT: &str = "%Y-%m-%d %H:%M";
+const DATE_FORMAT: &str = "%Y-%m-%d";
+// 到期检查最多回溯的范围（8 天），长时间休眠后只补最近的切换
+const SEARCH_WINDOW_MINUTES: i64 = 8 * 24 * 60;
+// cron 逐日查找的最大天数（8 年），覆盖 2 月 29 日这类隔数年才出现的日期
+const CRON_SEARCH_DAYS: i64 = 8 * 366;
+
+#[derive(Serialize, Deserialize, Clone, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum RuleKind {
+    // 在 cron 表达式匹配的时刻切换
+    Cron { expression: String },
+    // 在 [start, end) 范围内保持指定配置，结束时回到 cron 规则决定的配置
+    DateRange { start: String, end: String },
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct ScheduleRule {
+    pub name: String,
+    pub enabled: bool,
+    pub profile: String,
+    pub kind: RuleKind,
+}
+
+impl ScheduleRule {
+    pub fn new_cron() -> Self {
+        Self {
+            name: t!("schedule.new_rule").to_string(),
+            enabled: true,
+            profile: String::new(),
+            kind: RuleKind::Cron { expression: "0 9 * * 1-5".to_string() },
+        }
+    }
+
+    pub fn new_date_range() -> Self {
+        let today = chrono::Local::now().date_naive();
+        Self {
+            name: t!("schedule.new_date_range").to_string(),
+            enabled: true,
+            profile: String::new(),
+            kind: RuleKind::DateRange {
+                start: today.format(DATE_FORMAT).to_string(),
+                end: (today + Duration::days(1)).format(DATE_FORMAT).to_string(),
+            },
+        }
+    }
+
+    // 检查规则是否有效，返回错误描述
+    pub fn validate(&self) -> Result<(), String> {
+        if self.profile.is_empty() {
+            return Err(t!("schedule.no_profile").to_string());
+        }
+        match &self.kind {
+            RuleKind::Cron { expression } => CronExpr::parse(expression).map(|_| ()),
+            RuleKind::DateRange { start, end } => {
+                let start = parse_date_time(start)?;
+                let end = parse_date_time(end)?;
+                if end <= start {
+                    return Err(t!("schedule.end_before_start").to_string());
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct Schedule {
+    pub enabled: bool,
+    // 启动时立即应用当前时刻应生效的配置
+    pub apply_on_start: bool,
+    pub rules: Vec<ScheduleRule>,
+}
+
+// 一次计划中的切换
+#[derive(Clone)]
+pub struct SwitchEvent {
+    pub at: NaiveDateTime,
+    pub rule: String,
+    pub profile: String,
+}
+
+// 编译后的规则，避免每分钟重复解析
+enum CompiledKind {
+    Cron(CronExpr),
+    DateRange(NaiveDateTime, NaiveDateTime),
+}
+
+struct CompiledRule<'a> {
+    rule: &'a ScheduleRule,
+    kind: CompiledKind,
+}
+
+impl Schedule {
+    pub fn load(path: &Path) -> Result<Self, String> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let content = fs::read_to_string(path).map_err(|e| e.to_string())?;
+        serde_json::from_str(&content).map_err(|e| e.to_string())
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        let content = serde_json::to_string_pretty(self).map_err(|e| e.to_string())?;
+        files::write_atomically(path, &content).map_err(|e| e.to_string())
+    }
+
+    // 重命名配置文件时同步更新规则中的引用
+    pub fn rename_profile(&mut self, old_name: &str, new_name: &str) -> bool {
+        let mut changed = false;
+        for rule in self.rules.iter_mut().filter(|r| r.profile == old_name) {
+            rule.profile = new_name.to_string();
+            changed = true;
+        }
+        changed
+    }
+
+    fn compiled_rules(&self) -> Vec<CompiledRule<'_>> {
+        self.rules
+            .iter()
+            .filter(|r| r.enabled && !r.profile.is_empty())
+            .filter_map(|rule| {
+                let kind = match &rule.kind {
+                    RuleKind::Cron { expression } => CompiledKind::Cron(CronExpr::parse(expression).ok()?),
+                    RuleKind::DateRange { start, end } => {
+                        CompiledKind::DateRange(parse_date_time(start).ok()?, parse_date_time(end).ok()?)
+                    }
+                };
+                Some(CompiledRule { rule, kind })
+            })
+            .collect()
+    }
+
+    // 返回 (after, until] 区间内最后一个到期的切换
+    pub fn due_switch(&self, after: NaiveDateTime, until: NaiveDateTime) -> Option<SwitchEvent> {
+        if !self.enabled {
+            return None;
+        }
+        let rules = self.compiled_rules();
+        let until = floor_minute(until);
+        let after = floor_minute(after).max(until - Duration::minutes(SEARCH_WINDOW_MINUTES));
+        let mut candidates: Vec<NaiveDateTime> = rules
+            .iter()
+            .flat_map(|r| match &r.kind {
+                CompiledKind::Cron(cron) => vec![previous_cron_outside_ranges(&rules, cron, until)],
+                CompiledKind::DateRange(start, end) => vec![Some(*start), Some(*end)],
+            })
+            .flatten()
+            .filter(|t| after < *t && *t <= until)
+            .collect();
+        candidates.sort_unstable_by(|a, b| b.cmp(a));
+        candidates.dedup();
+        candidates.into_iter().find_map(|minute| event_at(&rules, minute))
+    }
+
+    // 返回 from 之后的第一个计划切换
+    pub fn next_switch(&self, from: NaiveDateTime) -> Option<SwitchEvent> {
+        if !self.enabled {
+            return None;
+        }
+        let rules = self.compiled_rules();
+        let from = floor_minute(from);
+        // 每条规则各自给出下一个可能的切换时刻，按时间顺序取第一个确实会切换的
+        let mut candidates: Vec<NaiveDateTime> = rules
+            .iter()
+            .flat_map(|r| match &r.kind {
+                CompiledKind::Cron(cron) => vec![next_cron_outside_ranges(&rules, cron, from)],
+                CompiledKind::DateRange(start, end) => vec![Some(*start), Some(*end)],
+            })
+            .flatten()
+            .filter(|t| *t > from)
+            .collect();
+        candidates.sort_unstable();
+        candidates.dedup();
+        candidates.into_iter().find_map(|minute| event_at(&rules, minute))
+    }
+
+    // 返回当前时刻按规则应当生效的配置
+    pub fn scheduled_profile(&self, now: NaiveDateTime) -> Option<SwitchEvent> {
+        if !self.enabled {
+            return None;
+        }
+        let rules = self.compiled_rules();
+        let now = floor_minute(now);
+        active_range(&rules, now)
+            .map(|rule| SwitchEvent { at: now, rule: rule.name.clone(), profile: rule.profile.clone() })
+            .or_else(|| latest_cron_event(&rules, now))
+    }
+}
+
+fn active_range<'a>(rules: &[CompiledRule<'a>], minute: NaiveDateTime) -> Option<&'a ScheduleRule> {
+    rules.iter().find_map(|r| match r.kind {
+        CompiledKind::DateRange(start, end) if start <= minute && minute < end => Some(r.rule),
+        _ => None,
+    })
+}
+
+fn cron_match_at(rules: &[CompiledRule<'_>], minute: NaiveDateTime) -> Option<SwitchEvent> {
+    rules.iter().find_map(|r| match &r.kind {
+        CompiledKind::Cron(cron) if cron.matches(&minute) => Some(SwitchEvent {
+            at: minute,
+            rule: r.rule.name.clone(),
+            profile: r.rule.profile.clone(),
+        }),
+        _ => None,
+    })
+}
+
+// 最近一次 cron 触发（不考虑日期范围），用于日期范围结束后的回退
+fn latest_cron_event(rules: &[CompiledRule<'_>], minute: NaiveDateTime) -> Option<SwitchEvent> {
+    let latest = rules
+        .iter()
+        .filter_map(|r| match &r.kind {
+            CompiledKind::Cron(cron) => cron.previous_at_or_before(minute),
+            CompiledKind::DateRange(..) => None,
+        })
+        .max()?;
+    cron_match_at(rules, latest)
+}
+
+// 日期范围内 cron 规则不生效，落在范围内时从范围结束处继续查找
+fn next_cron_outside_ranges(rules: &[CompiledRule<'_>], cron: &CronExpr, from: NaiveDateTime) -> Option<NaiveDateTime> {
+    let mut next = cron.next_after(from)?;
+    while let Some(end) = containing_range_end(rules, next) {
+        next = cron.next_after(end - Duration::minutes(1))?;
+    }
+    Some(next)
+}
+
+fn previous_cron_outside_ranges(rules: &[CompiledRule<'_>], cron: &CronExpr, until: NaiveDateTime) -> Option<NaiveDateTime> {
+    let mut previous = cron.previous_at_or_before(until)?;
+    while let Some(start) = containing_range_start(rules, previous) {
+        previous = cron.previous_at_or_before(start - Duration::minutes(1))?;
+    }
+    Some(previous)
+}
+
+fn containing_range_end(rules: &[CompiledRule<'_>], minute: NaiveDateTime) -> Option<NaiveDateTime> {
+    rules.iter().find_map(|r| match r.kind {
+        CompiledKind::DateRange(start, end) if start <= minute && minute < end => Some(end),
+        _ => None,
+    })
+}
+
+fn containing_range_start(rules: &[CompiledRule<'_>], minute: NaiveDateTime) -> Option<NaiveDateTime> {
+    rules.iter().find_map(|r| match r.kind {
+        CompiledKind::DateRange(start, end) if start <= minute && minute < end => Some(start),
+        _ => None,
+    })
+}
+
+fn event_at(rules: &[CompiledRule<'_>], minute: NaiveDateTime) -> Option<SwitchEvent> {
+    // 日期范围开始
+    if let Some(r) = rules.iter().find(|r| matches!(r.kind, CompiledKind::DateRange(start, _) if start == minute)) {
+        return Some(SwitchEvent { at: minute, rule: r.rule.name.clone(), profile: r.rule.profile.clone() });
+    }
+    // 日期范围结束，回到 cron 规则决定的配置
+    if active_range(rules, minute).is_none() {
+        if let Some(r) = rules.iter().find(|r| matches!(r.kind, CompiledKind::DateRange(_, end) if end == minute)) {
+            return latest_cron_event(rules, minute).map(|event| SwitchEvent {
+                at: minute,
+                rule: t!("schedule.range_ended", name = r.rule.name),
+                profile: event.profile,
+            });
+        }
+    }
+    if active_range(rules, minute).is_some() {
+        // 日期范围内 cron 规则不生效
+        return None;
+    }
+    cron_match_at(rules, minute)
+}
+
+fn floor_minute(t: NaiveDateTime) -> NaiveDateTime {
+    t.with_second(0).and_then(|t| t.with_nanosecond(0)).unwrap_or(t)
+}
+
+// 支持 "YYYY-MM-DD HH:MM" 或 "YYYY-MM-DD"（当天 00:00）
+pub fn parse_date_time(text: &str) -> Result<NaiveDateTime, String> {
+    let text = text.trim();
+    NaiveDateTime::parse_from_str(text, DATE_TIME_FORMAT)
+        .or_else(|_| NaiveDate::parse_from_str(text, DATE_FORMAT).map(|d| d.and_hms_opt(0, 0, 0).unwrap_or_default()))
+        .map_err(|_| t!("schedule.invalid_time", text = text))
+}
+
+// --- 五段式 cron 表达式：分 时 日 月 周 ---
+pub struct CronExpr {
+    minutes: u64,
+    hours: u64,
+    days: u64,
+    months: u64,
+    weekdays: u64,
+    any_day: bool,
+    any_weekday: bool,
+}
+
+const MONTH_NAMES: [&str; 12] = ["jan", "feb", "mar", "apr", "may", "jun", "jul", "aug", "sep", "oct", "nov", "dec"];
+const WEEKDAY_NAMES: [&str; 7] = ["sun", "mon", "tue", "wed", "thu", "fri", "sat"];
+
+impl CronExpr {
+    pub fn parse(expression: &str) -> Result<Self, String> {
+        let fields: Vec<&str> = expression.split_whitespace().collect();
+        if fields.len() != 5 {
+            return Err(t!("cron.field_count", count = fields.len()));
+        }
+        let minutes = parse_field(fields[0], 0, 59, &[])?;
+        let hours = parse_field(fields[1], 0, 23, &[])?;
+        let days = parse_field(fields[2], 1, 31, &[])?;
+        let months = parse_field(fields[3], 1, 12, &MONTH_NAMES)?;
+        let mut weekdays = parse_field(fields[4], 0, 7, &WEEKDAY_NAMES)?;
+        // 7 和 0 都表示周日
+        if weekdays & (1 << 7) != 0 {
+            weekdays |= 1;
+        }
+        Ok(Self {
+            minutes,
+            hours,
+            days,
+            months,
+            weekdays,
+            any_day: fields[2] == "*",
+            any_weekday: fields[4] == "*",
+        })
+    }
+
+    pub fn matches(&self, t: &NaiveDateTime) -> bool {
+        has_bit(self.minutes, t.minute()) && has_bit(self.hours, t.hour()) && self.matches_date(t.date())
+    }
+
+    fn matches_date(&self, date: NaiveDate) -> bool {
+        if !has_bit(self.months, date.month()) {
+            return false;
+        }
+        let day_match = has_bit(self.days, date.day());
+        let weekday_match = has_bit(self.weekdays, date.weekday().num_days_from_sunday());
+        // 与标准 cron 一致：日和周同时受限时，满足其一即可
+        match (self.any_day, self.any_weekday) {
+            (true, true) => true,
+            (true, false) => weekday_match,
+            (false, true) => day_match,
+            (false, false) => day_match || weekday_match,
+        }
+    }
+
+    // t 之后第一个匹配的分钟：逐日找到匹配的日期，再在当天按小时、分钟的位掩码直接取值
+    pub fn next_after(&self, t: NaiveDateTime) -> Option<NaiveDateTime> {
+        let start = floor_minute(t) + Duration::minutes(1);
+        let last_date = start.date() + Duration::days(CRON_SEARCH_DAYS);
+        let mut date = start.date();
+        let mut from = (start.hour(), start.minute());
+        while date <= last_date {
+            if !has_bit(self.months, date.month()) {
+                // 整月不匹配时直接跳到下个月 1 日
+                date = first_of_next_month(date)?;
+                from = (0, 0);
+                continue;
+            }
+            if self.matches_date(date) {
+                if let Some((hour, minute)) = self.first_time_from(from) {
+                    return date.and_hms_opt(hour, minute, 0);
+                }
+            }
+            date = date.succ_opt()?;
+            from = (0, 0);
+        }
+        None
+    }
+
+    // t 及之前最后一个匹配的分钟
+    pub fn previous_at_or_before(&self, t: NaiveDateTime) -> Option<NaiveDateTime> {
+        let start = floor_minute(t);
+        let first_date = start.date() - Duration::days(CRON_SEARCH_DAYS);
+        let mut date = start.date();
+        let mut until = (start.hour(), start.minute());
+        while date >= first_date {
+            if !has_bit(self.months, date.month()) {
+                // 整月不匹配时直接跳到上个月最后一天
+                date = date.with_day(1)?.pred_opt()?;
+                until = (23, 59);
+                continue;
+            }
+            if self.matches_date(date) {
+                if let Some((hour, minute)) = self.last_time_until(until) {
+                    return date.and_hms_opt(hour, minute, 0);
+                }
+            }
+            date = date.pred_opt()?;
+            until = (23, 59);
+        }
+        None
+    }
+
+    // 当天不早于 (hour, minute) 的第一个匹配时刻
+    fn first_time_from(&self, (hour, minute): (u32, u32)) -> Option<(u32, u32)> {
+        let first_hour = next_bit(self.hours, hour)?;
+        if first_hour == hour {
+            if let Some(minute) = next_bit(self.minutes, minute) {
+                return Some((hour, minute));
+            }
+            return Some((next_bit(self.hours, hour + 1)?, next_bit(self.minutes, 0)?));
+        }
+        Some((first_hour, next_bit(self.minutes, 0)?))
+    }
+
+    // 当天不晚于 (hour, minute) 的最后一个匹配时刻
+    fn last_time_until(&self, (hour, minute): (u32, u32)) -> Option<(u32, u32)> {
+        let last_hour = previous_bit(self.hours, hour)?;
+        if last_hour == hour {
+            if let Some(minute) = previous_bit(self.minutes, minute) {
+                return Some((hour, minute));
+            }
+            return Some((previous_bit(self.hours, hour.checked_sub(1)?)?, previous_bit(self.minutes, 59)?));
+        }
+        Some((last_hour, previous_bit(self.minutes, 59)?))
+    }
+}
+
+fn has_bit(mask: u64, value: u32) -> bool {
+    mask & (1 << value) != 0
+}
+
+// 不小于 from 的最小置位
+fn next_bit(mask: u64, from: u32) -> Option<u32> {
+    let rest = mask.checked_shr(from)?.checked_shl(from)?;
+    (rest != 0).then(|| rest.trailing_zeros())
+}
+
+// 不大于 until 的最大置位
+fn previous_bit(mask: u64, until: u32) -> Option<u32> {
+    let rest = mask & (u64::MAX >> (63 - until.min(63)));
+    (rest != 0).then(|| 63 - rest.leading_zeros())
+}
+
+fn first_of_next_month(date: NaiveDate) -> Option<NaiveDate> {
+    match date.month() {
+        12 => NaiveDate::from_ymd_opt(date.year() + 1, 1, 1),
+        month => NaiveDate::from_ymd_opt(date.year(), month + 1, 1),
+    }
+}
+
+fn parse_value(text: &str, min: u32, max: u32, names: &[&str]) -> Result<u32, String> {
+    let lower = text.to_ascii_lowercase();
+    let value = match names.iter().position(|n| *n == lower) {
+        // 月份名称从 1 开始，星期名称从 0 开始，与各自字段的最小值一致
+        Some(index) => index as u32 + min,
+        None => text.parse::<u32>().map_err(|_| t!("cron.invalid_value", text = text))?,
+    };
+    if value < min || value > max {
+        return Err(t!("cron.out_of_range", value = value, min = min, max = max));
+    }
+    Ok(value)
+}
+
+fn parse_field(field: &str, min: u32, max: u32, names: &[&str]) -> Result<u64, String> {
+    let mut mask = 0u64;
+    for part in field.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((range, step)) => {
+                let step = step.parse::<u32>().map_err(|_| t!("cron.invalid_step", text = step))?;
+                if step == 0 {
+                    return Err(t!("cron.zero_step").to_string());
+                }
+                (range, step)
+            }
+            None => (part, 1),
+        };
+        let (start, end) = if range == "*" {
+            (min, max)
+        } else if let Some((a, b)) = range.split_once('-') {
+            (parse_value(a, min, max, names)?, parse_value(b, min, max, names)?)
+        } else {
+            let value = parse_value(range, min, max, names)?;
+            // "5/15" 表示从 5 开始每 15 个单位
+            (value, if step > 1 { max } else { value })
+        };
+        if start > end {
+            return Err(t!("cron.invalid_range", text = range));
+        }
+        for value in (start..=end).step_by(step as usize) {
+            mask |= 1 << value;
+        }
+    }
+    Ok(mask)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(text: &str) -> NaiveDateTime {
+        parse_date_time(text).unwrap()
+    }
+
+    fn rule(name: &str, profile: &str, kind: RuleKind) -> ScheduleRule {
+        ScheduleRule { name: name.to_string(), enabled: true, profile: profile.to_string(), kind }
+    }
+
+    fn cron(expression: &str) -> RuleKind {
+        RuleKind::Cron { expression: expression.to_string() }
+    }
+
+    #[test]
+    fn parse_field_handles_lists_ranges_steps_and_names() {
+        assert_eq!(parse_field("*", 0, 7, &[]).unwrap(), 0xff);
+        assert_eq!(parse_field("1,3-5", 0, 59, &[]).unwrap(), 0b11_1010);
+        assert_eq!(parse_field("*/20", 0, 59, &[]).unwrap(), 1 | 1 << 20 | 1 << 40);
+        assert_eq!(parse_field("5/20", 0, 59, &[]).unwrap(), 1 << 5 | 1 << 25 | 1 << 45);
+        assert_eq!(parse_field("jan-Mar", 1, 12, &MONTH_NAMES).unwrap(), 0b1110);
+        assert_eq!(parse_field("sun,sat", 0, 7, &WEEKDAY_NAMES).unwrap(), 1 | 1 << 6);
+    }
+
+    #[test]
+    fn parse_rejects_invalid_expressions() {
+        for expression in ["* * * *", "60 * * * *", "* * 0 * *", "*/0 * * * *", "5-1 * * * *", "x * * * *", "* * * foo *"] {
+            assert!(CronExpr::parse(expression).is_err(), "{}", expression);
+        }
+    }
+
+    #[test]
+    fn matches_days_or_weekdays_like_cron() {
+        // 周日可以写作 0 或 7
+        let sunday = CronExpr::parse("30 9 * * 7").unwrap();
+        assert!(sunday.matches(&at("2026-10-18 09:30")));
+        assert!(!sunday.matches(&at("2026-10-19 09:30")));
+        assert!(!sunday.matches(&at("2026-10-18 09:31")));
+        // 日和周同时受限时满足其一即可
+        let either = CronExpr::parse("0 0 1 * mon").unwrap();
+        assert!(either.matches(&at("2026-10-01")));
+        assert!(either.matches(&at("2026-10-19")));
+        assert!(!either.matches(&at("2026-10-18")));
+    }
+
+    #[test]
+    fn date_range_overrides_cron_and_falls_back_when_it_ends() {
+        let schedule = Schedule {
+            enabled: true,
+            apply_on_start: false,
+            rules: vec![
+                rule("work", "work.json", cron("0 9 * * 1-5")),
+                rule("trip", "trip.json", RuleKind::DateRange { start: "2026-10-19 12:00".into(), end: "2026-10-20".into() }),
+            ],
+        };
+        let next = schedule.next_switch(at("2026-10-18 10:00")).unwrap();
+        assert_eq!((next.at, next.profile.as_str()), (at("2026-10-19 09:00"), "work.json"));
+        let next = schedule.next_switch(at("2026-10-19 09:00")).unwrap();
+        assert_eq!((next.at, next.profile.as_str()), (at("2026-10-19 12:00"), "trip.json"));
+        // 范围结束时回到 cron 规则决定的配置，范围内 cron 不触发
+        let next = schedule.next_switch(at("2026-10-19 12:00")).unwrap();
+        assert_eq!((next.at, next.profile.as_str()), (at("2026-10-20"), "work.json"));
+        assert_eq!(schedule.scheduled_profile(at("2026-10-19 13:00")).unwrap().profile, "trip.json");
+        let due = schedule.due_switch(at("2026-10-19 08:00"), at("2026-10-19 12:30")).unwrap();
+        assert_eq!(due.profile, "trip.json");
+    }
+
+    #[test]
+    fn next_and_previous_match_skip_to_matching_fields() {
+        let cron = CronExpr::parse("15,45 9-17/4 * * 1-5").unwrap();
+        // 当天剩余的小时和分钟
+        assert_eq!(cron.next_after(at("2026-10-19 09:15")), Some(at("2026-10-19 09:45")));
+        assert_eq!(cron.next_after(at("2026-10-19 09:50")), Some(at("2026-10-19 13:15")));
+        // 周五最后一次之后跳到下周一
+        assert_eq!(cron.next_after(at("2026-10-23 17:45")), Some(at("2026-10-26 09:15")));
+        assert_eq!(cron.previous_at_or_before(at("2026-10-26 09:14")), Some(at("2026-10-23 17:45")));
+        assert_eq!(cron.previous_at_or_before(at("2026-10-19 13:15")), Some(at("2026-10-19 13:15")));
+        // 隔数年才出现的日期
+        let leap_day = CronExpr::parse("0 0 29 feb *").unwrap();
+        assert_eq!(leap_day.next_after(at("2026-10-18")), Some(at("2028-02-29")));
+        assert_eq!(leap_day.previous_at_or_before(at("2026-10-18")), Some(at("2024-02-29")));
+        assert_eq!(CronExpr::parse("0 0 31 apr *").unwrap().next_after(at("2026-10-18")), None);
+    }
+
+    #[test]
+    fn next_switch_skips_cron_matches_inside_date_ranges() {
+        let schedule = Schedule {
+            enabled: true,
+            apply_on_start: false,
+            rules: vec![
+                rule("hourly", "hourly.json", cron("30 * * * *")),
+                rule("trip", "trip.json", RuleKind::DateRange { start: "2026-10-18 10:00".into(), end: "2026-10-25".into() }),
+            ],
+        };
+        // 范围结束时回到范围开始前最近一次 cron 触发的配置，之后继续按 cron 切换
+        let next = schedule.next_switch(at("2026-10-18 11:00")).unwrap();
+        assert_eq!((next.at, next.profile.as_str()), (at("2026-10-25"), "hourly.json"));
+        let next = schedule.next_switch(at("2026-10-25")).unwrap();
+        assert_eq!(next.at, at("2026-10-25 00:30"));
+        // 长时间未检查时只取区间内最后一次切换
+        let due = schedule.due_switch(at("2026-10-18 09:00"), at("2026-10-24 12:00")).unwrap();
+        assert_eq!((due.at, due.profile.as_str()), (at("2026-10-18 10:00"), "trip.json"));
+        assert!(schedule.due_switch(at("2026-10-19"), at("2026-10-24 12:00")).is_none());
+    }
+}
@@ -0,0 +1,104 @@
+// 应用设置及其持久化
+
+use crate::error::{Error, Result};
+use crate::files;
+use crate::format::FormatSettings;
+use crate::i18n::Language;
+use crate::mcp::McpSettings;
+use crate::paths::Paths;
+use crate::target::{ClaudeRoot, ToolTarget};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct AppSettings {
+    pub config_directory: PathBuf,
+    pub theme: String,
+    pub default_config_file: String,
+    // 旧版本的设置文件没有该字段，按环境区域设置选择
+    #[serde(default)]
+    pub language: Language,
+    #[serde(default)]
+    pub autosave: AutosaveSettings,
+    #[serde(default)]
+    pub format: FormatSettings,
+    #[serde(default)]
+    pub mcp: McpSettings,
+    // Claude Code 之外同时切换的工具
+    #[serde(default)]
+    pub targets: Vec<ToolTarget>,
+    // Claude 配置目录，优先于 CLAUDE_CONFIG_DIR；未设置时自动确定
+    #[serde(default)]
+    pub claude_config_dir: Option<PathBuf>,
+    // 应用配置时与主目录一起写入的其他 Claude 配置目录
+    #[serde(default)]
+    pub claude_roots: Vec<ClaudeRoot>,
+}
+
+// 编辑器停止输入一段时间后自动保存，只保存有效的 JSON
+#[derive(Serialize, Deserialize, Clone, PartialEq)]
+#[serde(default)]
+pub struct AutosaveSettings {
+    pub enabled: bool,
+    pub delay_secs: u64,
+    // 自动保存默认配置时是否同时写入 Claude 活动配置
+    pub sync_default: bool,
+}
+
+impl Default for AutosaveSettings {
+    fn default() -> Self {
+        Self { enabled: false, delay_secs: 2, sync_default: false }
+    }
+}
+
+impl AppSettings {
+    pub fn defaults_for(paths: &Paths) -> Self {
+        Self {
+            config_directory: paths.app_dir(),
+            theme: "Dark".to_string(),
+            default_config_file: String::new(),
+            language: Language::detect(),
+            autosave: AutosaveSettings::default(),
+            format: FormatSettings::default(),
+            mcp: McpSettings::default(),
+            targets: Vec::new(),
+            claude_config_dir: None,
+            claude_roots: Vec::new(),
+        }
+    }
+}
+
+impl Default for AppSettings {
+    fn default() -> Self {
+        Self::defaults_for(&Paths::from_env())
+    }
+}
+
+pub struct AppSettingsRepository {
+    path: PathBuf,
+}
+
+impl AppSettingsRepository {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    // 文件不存在时返回 None
+    pub fn load(&self) -> Result<Option<AppSettings>> {
+        if !self.path.exists() {
+            return Ok(None);
+        }
+        let content = fs::read_to_string(&self.path).map_err(|source| Error::Read { path: self.path.clone(), source })?;
+        serde_json::from_str(&content).map(Some).map_err(Error::InvalidSettings)
+    }
+
+    pub fn save(&self, settings: &AppSettings) -> Result<()> {
+        let content = serde_json::to_string_pretty(settings).map_err(Error::InvalidSettings)?;
+        files::write_atomically(&self.path, &content)
+    }
+}
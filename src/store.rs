@@ -0,0 +1,202 @@
+// 配置文件仓库：<配置目录>/settings/*.json
+
+use crate::error::{Error, Result};
+use crate::files;
+use crate::jsonc;
+use crate::mcp;
+use crate::paths::{ACTIVE_CONFIG_NAME, SETTINGS_SUBDIR};
+use crate::target;
+use serde_json::Value;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+// 新建配置文件时使用的模板
+pub const DEFAULT_PROFILE_CONTENT: &str = "{\n\t\"env\": {\n\t\t\"ANTHROPIC_API_KEY\": \"sk-ant-XXX\",\n\t\t\"ANTHROPIC_BASE_URL\": \"https://api.anthropic.com\"\n\t}\n}";
+
+// 去掉首尾空白并补全 .json 扩展名
+pub fn normalize_profile_name(name: &str) -> Result<String> {
+    let mut name = name.trim().to_string();
+    if !name.ends_with(".json") {
+        name.push_str(".json");
+    }
+    if name == ".json" {
+        return Err(Error::EmptyProfileName);
+    }
+    Ok(name)
+}
+
+#[derive(Clone)]
+pub struct ProfileStore {
+    root: PathBuf,
+}
+
+impl ProfileStore {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    pub fn dir(&self) -> PathBuf {
+        self.root.join(SETTINGS_SUBDIR)
+    }
+
+    pub fn path(&self, name: &str) -> PathBuf {
+        self.dir().join(name)
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.path(name).is_file()
+    }
+
+    // 创建配置目录、settings 子目录以及其中的默认 settings.json
+    pub fn ensure(&self) -> Result<()> {
+        let dir = self.dir();
+        fs::create_dir_all(&dir).map_err(|source| Error::CreateDir { path: dir.clone(), source })?;
+        let settings_path = dir.join(ACTIVE_CONFIG_NAME);
+        if !settings_path.exists() {
+            fs::write(&settings_path, DEFAULT_PROFILE_CONTENT).map_err(|source| Error::Write { path: settings_path, source })?;
+        }
+        Ok(())
+    }
+
+    // 按文件名排序，不包含 settings 子目录中的 settings.json
+    pub fn list(&self) -> Result<Vec<PathBuf>> {
+        let dir = self.dir();
+        if !dir.exists() {
+            return Ok(Vec::new());
+        }
+        let entries = fs::read_dir(&dir).map_err(|source| Error::ReadDir { path: dir.clone(), source })?;
+        let mut files: Vec<PathBuf> = entries
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.path())
+            .filter(|p| {
+                p.is_file()
+                    && p.extension().is_some_and(|ext| ext == "json")
+                    && p.file_name().and_then(|s| s.to_str()).unwrap_or_default() != ACTIVE_CONFIG_NAME
+            })
+            .collect();
+        files.sort_by(|a, b| a.file_name().cmp(&b.file_name()));
+        Ok(files)
+    }
+
+    pub fn read(&self, name: &str) -> Result<String> {
+        let path = self.path(name);
+        if !path.is_file() {
+            return Err(Error::ProfileNotFound(name.to_string()));
+        }
+        fs::read_to_string(&path).map_err(|source| Error::Read { path, source })
+    }
+
+    pub fn write(&self, name: &str, content: &str) -> Result<()> {
+        files::write_atomically(&self.path(name), content)
+    }
+
+    // 校验 JSON 后原样写入，保留用户的键顺序、缩进、空白和注释
+    pub fn write_validated(&self, name: &str, content: &str) -> Result<()> {
+        jsonc::from_str::<Value>(content).map_err(Error::InvalidJson)?;
+        self.write(name, content)
+    }
+
+    // 返回不与现有文件冲突的文件名，例如 新配置.json、新配置_1.json
+    pub fn unique_name(&self, stem: &str) -> String {
+        let mut name = format!("{}.json", stem);
+        let mut i = 1;
+        while self.path(&name).exists() {
+            name = format!("{}_{}.json", stem, i);
+            i += 1;
+        }
+        name
+    }
+
+    // 重命名配置文件，返回规范化后的新文件名
+    pub fn rename(&self, old_name: &str, new_name: &str) -> Result<String> {
+        let new_name = normalize_profile_name(new_name)?;
+        let old_path = self.path(old_name);
+        let new_path = self.path(&new_name);
+        if new_path.exists() {
+            return Err(Error::ProfileExists(new_name));
+        }
+        fs::rename(&old_path, &new_path).map_err(|source| Error::Rename { path: old_path, source })?;
+        Ok(new_name)
+    }
+
+    pub fn delete(&self, name: &str) -> Result<()> {
+        let path = self.path(name);
+        fs::remove_file(&path).map_err(|source| Error::Remove { path, source })
+    }
+
+    // 查找内容与给定 JSON 相同的配置文件，忽略注释和格式差异
+    pub fn find_by_value(&self, value: &Value) -> Option<String> {
+        self.list().ok()?.into_iter().find_map(|path| {
+            let content = fs::read_to_string(&path).ok()?;
+            let mut json: Value = jsonc::from_str(&content).ok()?;
+            // MCP 服务器和其他工具的设置不写入活动配置，比较时忽略
+            if let Some(object) = json.as_object_mut() {
+                object.remove(mcp::PROFILE_KEY);
+                object.remove(target::PROFILE_KEY);
+            }
+            if &json == value {
+                path.file_name().and_then(|n| n.to_str()).map(str::to_string)
+            } else {
+                None
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn store() -> (tempfile::TempDir, ProfileStore) {
+        let root = tempfile::tempdir().unwrap();
+        let store = ProfileStore::new(root.path());
+        store.ensure().unwrap();
+        (root, store)
+    }
+
+    #[test]
+    fn list_skips_active_settings_and_sorts() {
+        let (_root, store) = store();
+        store.write("b.json", "{}").unwrap();
+        store.write("a.json", "{}").unwrap();
+        fs::write(store.dir().join("notes.txt"), "").unwrap();
+        let names: Vec<_> = store.list().unwrap().iter().map(|p| p.file_name().unwrap().to_string_lossy().to_string()).collect();
+        assert_eq!(names, ["a.json", "b.json"]);
+        assert!(store.contains(ACTIVE_CONFIG_NAME));
+    }
+
+    #[test]
+    fn write_validated_keeps_comments_and_rejects_invalid_json() {
+        let (_root, store) = store();
+        let content = "{\n  // 注释\n  \"env\": {},\n}";
+        store.write_validated("a.json", content).unwrap();
+        assert_eq!(store.read("a.json").unwrap(), content);
+        assert!(store.write_validated("a.json", "{").is_err());
+        assert_eq!(store.read("a.json").unwrap(), content);
+        assert!(matches!(store.read("missing.json"), Err(Error::ProfileNotFound(_))));
+    }
+
+    #[test]
+    fn rename_normalizes_and_refuses_to_overwrite() {
+        let (_root, store) = store();
+        store.write("a.json", "{}").unwrap();
+        store.write("b.json", "{}").unwrap();
+        assert_eq!(store.rename("a.json", " c ").unwrap(), "c.json");
+        assert!(matches!(store.rename("c.json", "b"), Err(Error::ProfileExists(_))));
+        assert!(matches!(store.rename("c.json", "  "), Err(Error::EmptyProfileName)));
+        assert_eq!(store.unique_name("b"), "b_1.json");
+    }
+
+    #[test]
+    fn find_by_value_ignores_comments_and_tool_keys() {
+        let (_root, store) = store();
+        store.write("a.json", r#"{"env": {"A": "1"}, "mcpServers": {}} // x"#).unwrap();
+        assert_eq!(store.find_by_value(&json!({"env": {"A": "1"}})).as_deref(), Some("a.json"));
+        assert_eq!(store.find_by_value(&json!({"env": {"A": "2"}})), None);
+    }
+}
@@ -0,0 +1,595 @@
+// 配置切换的核心流程：图形界面、无界面模式和控制接口都通过 Switcher 操作配置，
+// 行为（包括审计日志）保持一致
+
+use crate::audit::{self, AuditAction, AuditEntry};
+use crate::error::{Error, Result};
+use crate::jsonc;
+use crate::lint::{self, Severity};
+use crate::mcp::{self, McpServer, Scope};
+use crate::paths::Paths;
+use crate::settings::{AppSettings, AppSettingsRepository};
+use crate::store::{ProfileStore, DEFAULT_PROFILE_CONTENT};
+use crate::t;
+use crate::target::{self, ActiveSettingsTarget, RootReport, RootStatus, Snapshot, TargetReport};
+use serde_json::Value;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+// 启动时与 Claude 活动配置对比的结果
+pub enum SyncOutcome {
+    // 活动配置不存在或不是有效 JSON
+    Skipped,
+    Matched { name: String, changed: bool },
+    Created(String),
+}
+
+pub struct SaveOutcome {
+    // 实际写入的内容
+    pub content: String,
+    // 保存的是默认配置时，同步到活动配置的结果
+    pub synced: Option<Result<()>>,
+}
+
+pub struct Switcher {
+    paths: Paths,
+    repository: AppSettingsRepository,
+    settings: AppSettings,
+    target: ActiveSettingsTarget,
+    recorded: Vec<AuditEntry>,
+    warnings: Vec<String>,
+    // 最近一次写入时其他工具目标的结果
+    target_reports: Vec<TargetReport>,
+    // 最近一次写入时各 Claude 配置目录的结果
+    root_reports: Vec<RootReport>,
+}
+
+impl Switcher {
+    pub fn new(paths: Paths) -> Self {
+        Self {
+            repository: AppSettingsRepository::new(paths.app_settings_file()),
+            settings: AppSettings::defaults_for(&paths),
+            target: ActiveSettingsTarget::for_paths(&paths),
+            paths,
+            recorded: Vec::new(),
+            warnings: Vec::new(),
+            target_reports: Vec::new(),
+            root_reports: Vec::new(),
+        }
+    }
+
+    pub fn paths(&self) -> &Paths {
+        &self.paths
+    }
+
+    pub fn settings(&self) -> &AppSettings {
+        &self.settings
+    }
+
+    // 修改后需调用 save_settings；更换配置目录请使用 change_config_directory
+    pub fn settings_mut(&mut self) -> &mut AppSettings {
+        &mut self.settings
+    }
+
+    pub fn store(&self) -> ProfileStore {
+        ProfileStore::new(&self.settings.config_directory)
+    }
+
+    pub fn target(&self) -> &ActiveSettingsTarget {
+        &self.target
+    }
+
+    pub fn target_reports(&self) -> &[TargetReport] {
+        &self.target_reports
+    }
+
+    pub fn root_reports(&self) -> &[RootReport] {
+        &self.root_reports
+    }
+
+    pub fn default_profile(&self) -> &str {
+        &self.settings.default_config_file
+    }
+
+    pub fn is_default(&self, name: &str) -> bool {
+        self.settings.default_config_file == name
+    }
+
+    // --- 应用设置 ---
+    // 首次运行或设置文件无法读取时写入默认设置；设置文件格式错误时保留文件并返回错误
+    pub fn load_settings(&mut self) -> Result<()> {
+        match self.repository.load() {
+            Ok(Some(settings)) => {
+                self.settings = settings;
+                self.resolve_claude_dir();
+                Ok(())
+            }
+            Err(e @ Error::InvalidSettings(_)) => Err(e),
+            Ok(None) | Err(_) => self.repository.save(&self.settings),
+        }
+    }
+
+    pub fn save_settings(&self) -> Result<()> {
+        self.repository.save(&self.settings)
+    }
+
+    pub fn ensure_layout(&self) -> Result<()> {
+        let app_dir = self.paths.app_dir();
+        fs::create_dir_all(&app_dir).map_err(|source| Error::CreateDir { path: app_dir, source })?;
+        self.store().ensure()
+    }
+
+    // None 表示按 CLAUDE_CONFIG_DIR 或默认位置自动确定
+    // 设置保存失败时恢复原来的目录
+    pub fn set_claude_config_dir(&mut self, dir: Option<PathBuf>) -> Result<()> {
+        let previous = std::mem::replace(&mut self.settings.claude_config_dir, dir.filter(|dir| !dir.as_os_str().is_empty()));
+        if let Err(e) = self.save_settings() {
+            self.settings.claude_config_dir = previous;
+            return Err(e);
+        }
+        self.resolve_claude_dir();
+        Ok(())
+    }
+
+    // 活动配置的位置只在这里确定
+    fn resolve_claude_dir(&mut self) {
+        self.paths.set_claude_dir_override(self.settings.claude_config_dir.clone());
+        self.target = ActiveSettingsTarget::for_paths(&self.paths);
+    }
+
+    pub fn change_config_directory(&mut self, dir: PathBuf) -> Result<()> {
+        self.settings.config_directory = dir;
+        let layout = self.ensure_layout();
+        self.save_settings()?;
+        layout
+    }
+
+    // --- 配置文件 ---
+    pub fn list_profiles(&self) -> Result<Vec<PathBuf>> {
+        self.store().list()
+    }
+
+    pub fn create_profile(&self) -> Result<String> {
+        let store = self.store();
+        let name = store.unique_name(t!("profile.new_stem"));
+        store.write(&name, DEFAULT_PROFILE_CONTENT)?;
+        Ok(name)
+    }
+
+    // 设为默认并写入 Claude 活动配置，返回写入的内容
+    pub fn apply(&mut self, name: &str) -> Result<String> {
+        self.apply_checked(name, false, AuditAction::SetAsDefault, String::new())
+    }
+
+    // 用户确认后忽略错误级检查结果强制应用
+    pub fn force_apply(&mut self, name: &str) -> Result<String> {
+        self.apply_checked(name, true, AuditAction::SetAsDefault, String::new())
+    }
+
+    // 定时规则触发的切换，审计日志中记为自动同步并注明规则
+    pub fn apply_scheduled(&mut self, name: &str, rule: &str) -> Result<String> {
+        self.apply_checked(name, false, AuditAction::AutomaticSync, t!("audit.detail.scheduled", rule = rule))
+    }
+
+    fn apply_checked(&mut self, name: &str, ignore_lint: bool, action: AuditAction, detail: String) -> Result<String> {
+        // 没有写到活动配置时不应显示上一次写入各目录的结果
+        self.root_reports.clear();
+        let result = self.store().read(name).and_then(|content| {
+            if !ignore_lint {
+                check_lint(name, &content)?;
+            }
+            self.write_target(&content)
+        });
+        let entry = match &result {
+            Ok(content) => {
+                self.set_default(name);
+                AuditEntry::new(action, name, Some(content), Ok(()))
+            }
+            Err(e) => AuditEntry::new(action, name, None, Err(e.to_string())),
+        };
+        self.record(if detail.is_empty() { entry } else { entry.with_detail(detail) });
+        result
+    }
+
+    // Claude 不接受注释，写入活动配置前去掉；MCP 服务器另外合并到 Claude 读取的文件中，
+    // 其他工具的目标随后写入。返回实际写入的内容
+    fn write_target(&mut self, content: &str) -> Result<String> {
+        let active = jsonc::to_json(&target::strip_from_settings(&mcp::strip_from_settings(content))).map_err(Error::InvalidJson)?;
+        self.write_roots(&active)?;
+        self.apply_mcp(content);
+        self.apply_tool_targets(content);
+        Ok(active)
+    }
+
+    // 主目录和启用的其他目录，指向同一位置的只保留一个
+    fn roots(&self) -> Vec<(String, ActiveSettingsTarget)> {
+        let mut roots = vec![(t!("roots.primary").to_string(), ActiveSettingsTarget::new(self.target.dir()))];
+        for root in self.settings.claude_roots.iter().filter(|root| root.enabled && !root.dir.as_os_str().is_empty()) {
+            let dir = self.paths.expand_home(&root.dir);
+            if roots.iter().all(|(_, existing)| existing.dir() != dir) {
+                roots.push((root.name.clone(), ActiveSettingsTarget::new(dir)));
+            }
+        }
+        roots
+    }
+
+    // 依次写入所有目录；任何一个失败时把该目录和已写入的目录恢复原状并返回该错误
+    fn write_roots(&mut self, active: &str) -> Result<()> {
+        let roots = self.roots();
+        let mut snapshots: Vec<Snapshot> = Vec::new();
+        let mut failure = None;
+        for (index, (_, root)) in roots.iter().enumerate() {
+            // 写入失败时可能已经新建了目录，所以失败的目录也保留快照
+            let result = root.snapshot().and_then(|snapshot| {
+                snapshots.push(snapshot);
+                root.write(active)
+            });
+            if let Err(e) = result {
+                failure = Some((index, e));
+                break;
+            }
+        }
+        let mut statuses = vec![RootStatus::Written; roots.len()];
+        if let Some((failed, error)) = &failure {
+            statuses[*failed] = RootStatus::Failed(error.to_string());
+            statuses[failed + 1..].fill(RootStatus::Skipped);
+            for (index, snapshot) in snapshots.iter().enumerate().rev() {
+                let (name, root) = &roots[index];
+                match root.restore(snapshot) {
+                    Ok(()) if index < *failed => statuses[index] = RootStatus::RolledBack,
+                    Ok(()) => {}
+                    Err(e) => {
+                        self.warnings.push(t!("warning.root_rollback", name = name, error = e));
+                        statuses[index] = RootStatus::RollbackFailed(e.to_string());
+                    }
+                }
+            }
+        }
+        self.root_reports = roots
+            .into_iter()
+            .zip(statuses)
+            .map(|((name, root), status)| RootReport { name, path: root.path(), status })
+            .collect();
+        match failure {
+            Some((_, error)) => Err(error),
+            None => Ok(()),
+        }
+    }
+
+    pub fn set_claude_roots(&mut self, roots: Vec<target::ClaudeRoot>) -> Result<()> {
+        self.settings.claude_roots = roots;
+        self.save_settings()
+    }
+
+    // 与 MCP 一样，其他工具写入失败只产生警告
+    fn apply_tool_targets(&mut self, content: &str) {
+        self.target_reports.clear();
+        let Ok(profile) = jsonc::from_str::<Value>(content) else { return };
+        let mut changed = false;
+        for index in 0..self.settings.targets.len() {
+            let tool = &self.settings.targets[index];
+            if !tool.enabled {
+                continue;
+            }
+            let name = tool.name.clone();
+            let result = tool.apply(self.paths.home(), &profile);
+            let result = match result {
+                Ok(keys) => {
+                    let count = keys.len();
+                    if self.settings.targets[index].managed != keys {
+                        self.settings.targets[index].managed = keys;
+                        changed = true;
+                    }
+                    Ok(count)
+                }
+                Err(e) => {
+                    self.warnings.push(t!("warning.target_write", name = name, error = e));
+                    Err(e.to_string())
+                }
+            };
+            self.target_reports.push(TargetReport { name, result });
+        }
+        if changed {
+            if let Err(e) = self.save_settings() {
+                self.warnings.push(t!("warning.settings_save", error = e));
+            }
+        }
+    }
+
+    // 合并失败不影响活动配置的写入，只产生警告
+    fn apply_mcp(&mut self, content: &str) {
+        let Ok(profile) = jsonc::from_str::<Value>(content) else { return };
+        let Some(servers) = mcp::from_profile(&profile) else {
+            // 配置没有 mcpServers：MCP 服务器随配置切换，移除上一个配置写入的服务器
+            let files: Vec<PathBuf> = self.settings.mcp.managed.iter().map(|m| m.file.clone()).collect();
+            for file in files {
+                self.merge_mcp(file, &[]);
+            }
+            return;
+        };
+        let (project, user): (Vec<McpServer>, Vec<McpServer>) =
+            servers.into_iter().filter(|s| s.enabled).partition(|s| s.scope == Scope::Project);
+        self.merge_mcp(self.paths.claude_user_config_file(), &user);
+        match self.settings.mcp.project_dir.clone() {
+            Some(dir) => self.merge_mcp(dir.join(mcp::PROJECT_FILE_NAME), &project),
+            None if !project.is_empty() => self.warnings.push(t!("warning.mcp_no_project_dir").to_string()),
+            None => {}
+        }
+    }
+
+    fn merge_mcp(&mut self, file: PathBuf, servers: &[McpServer]) {
+        let previous = self.settings.mcp.managed_names(&file).to_vec();
+        match mcp::merge_into(&file, servers, &previous) {
+            Ok(merged) => {
+                if !merged.skipped.is_empty() {
+                    self.warnings.push(t!("warning.mcp_name_conflict", path = file.display(), names = merged.skipped.join(", ")));
+                }
+                if merged.names != previous {
+                    self.settings.mcp.set_managed_names(&file, merged.names);
+                    if let Err(e) = self.save_settings() {
+                        self.warnings.push(t!("warning.settings_save", error = e));
+                    }
+                }
+            }
+            Err(e) => self.warnings.push(t!("warning.mcp_merge", path = file.display(), error = e)),
+        }
+    }
+
+    // 保存配置；保存的是默认配置时同时更新 Claude 活动配置
+    pub fn save_profile(&mut self, name: &str, content: &str) -> Result<SaveOutcome> {
+        let content = match self.store().write_validated(name, content) {
+            Ok(()) => content.to_string(),
+            Err(e) => {
+                self.record(AuditEntry::new(AuditAction::SaveCurrentFile, name, None, Err(e.to_string())));
+                return Err(e);
+            }
+        };
+        if !self.is_default(name) {
+            self.record(AuditEntry::new(AuditAction::SaveCurrentFile, name, None, Ok(())));
+            return Ok(SaveOutcome { content, synced: None });
+        }
+        // 与应用一样，有错误级检查结果时不写入活动配置，由调用方确认后强制应用
+        self.root_reports.clear();
+        let synced = check_lint(name, &content).and_then(|()| self.write_target(&content));
+        match &synced {
+            Ok(active) => self.record(AuditEntry::new(AuditAction::SaveCurrentFile, name, Some(active), Ok(()))),
+            Err(e) => self.record(AuditEntry::new(AuditAction::SaveCurrentFile, name, None, Err(e.to_string()))),
+        }
+        Ok(SaveOutcome { content, synced: Some(synced.map(|_| ())) })
+    }
+
+    // 自动保存：与手动保存一样原样写入，但默认配置只有在设置允许时才同步到活动配置
+    pub fn autosave_profile(&mut self, name: &str, content: &str) -> Result<SaveOutcome> {
+        let detail = t!("audit.detail.autosave");
+        let written = self.store().write_validated(name, content);
+        if let Err(e) = written {
+            self.record(AuditEntry::new(AuditAction::SaveCurrentFile, name, None, Err(e.to_string())).with_detail(detail));
+            return Err(e);
+        }
+        if !self.is_default(name) || !self.settings.autosave.sync_default {
+            self.record(AuditEntry::new(AuditAction::SaveCurrentFile, name, None, Ok(())).with_detail(detail));
+            return Ok(SaveOutcome { content: content.to_string(), synced: None });
+        }
+        self.root_reports.clear();
+        let synced = check_lint(name, content).and_then(|()| self.write_target(content));
+        match &synced {
+            Ok(active) => self.record(AuditEntry::new(AuditAction::SaveCurrentFile, name, Some(active), Ok(())).with_detail(detail)),
+            Err(e) => self.record(AuditEntry::new(AuditAction::SaveCurrentFile, name, None, Err(e.to_string())).with_detail(detail)),
+        }
+        Ok(SaveOutcome { content: content.to_string(), synced: Some(synced.map(|_| ())) })
+    }
+
+    // 重命名配置，返回规范化后的新文件名
+    pub fn rename_profile(&mut self, old_name: &str, new_name: &str) -> Result<String> {
+        match self.store().rename(old_name, new_name) {
+            Ok(new_name) => {
+                if self.is_default(old_name) {
+                    self.set_default(&new_name);
+                }
+                self.record(AuditEntry::new(AuditAction::RenameSelectedFile, new_name.clone(), None, Ok(()))
+                    .with_detail(format!("{} → {}", old_name, new_name)));
+                Ok(new_name)
+            }
+            // 文件名校验失败时没有执行任何操作，不记录审计日志
+            Err(e @ (Error::EmptyProfileName | Error::ProfileExists(_))) => Err(e),
+            Err(e) => {
+                self.record(AuditEntry::new(AuditAction::RenameSelectedFile, old_name, None, Err(e.to_string())));
+                Err(e)
+            }
+        }
+    }
+
+    pub fn delete_profile(&mut self, name: &str) -> Result<()> {
+        let result = self.store().delete(name);
+        match &result {
+            Ok(()) => {
+                if self.is_default(name) {
+                    self.set_default("");
+                }
+                self.record(AuditEntry::new(AuditAction::DeleteSelectedFile, name, None, Ok(())));
+            }
+            Err(e) => self.record(AuditEntry::new(AuditAction::DeleteSelectedFile, name, None, Err(e.to_string()))),
+        }
+        result
+    }
+
+    // 找到与 Claude 活动配置内容相同的文件并设为默认，找不到时从活动配置创建一个
+    pub fn sync_with_active(&mut self) -> Result<SyncOutcome> {
+        let Ok(Some(content)) = self.target.read() else { return Ok(SyncOutcome::Skipped) };
+        let Ok(value) = jsonc::from_str::<Value>(&content) else { return Ok(SyncOutcome::Skipped) };
+
+        let store = self.store();
+        if let Some(name) = store.find_by_value(&value) {
+            let changed = !self.is_default(&name);
+            if changed {
+                self.record(AuditEntry::new(AuditAction::AutomaticSync, name.clone(), Some(&content), Ok(())));
+            }
+            self.set_default(&name);
+            return Ok(SyncOutcome::Matched { name, changed });
+        }
+
+        let name = store.unique_name(t!("profile.imported_stem"));
+        match store.write(&name, &content) {
+            Ok(()) => {
+                self.set_default(&name);
+                self.record(AuditEntry::new(AuditAction::AutomaticSync, name.clone(), Some(&content), Ok(()))
+                    .with_detail(t!("audit.detail.imported")));
+                Ok(SyncOutcome::Created(name))
+            }
+            Err(e) => {
+                self.record(AuditEntry::new(AuditAction::AutomaticSync, name, None, Err(e.to_string())));
+                Err(e)
+            }
+        }
+    }
+
+    // --- 审计日志与警告 ---
+    pub fn audit_log_path(&self) -> PathBuf {
+        self.paths.audit_log_file()
+    }
+
+    pub fn record(&mut self, entry: AuditEntry) {
+        if let Err(e) = append_audit(&self.paths.audit_log_file(), &entry) {
+            self.warnings.push(t!("warning.audit_write", error = e));
+        }
+        self.recorded.push(entry);
+    }
+
+    // 取出上次调用以来记录的审计条目
+    pub fn take_recorded(&mut self) -> Vec<AuditEntry> {
+        std::mem::take(&mut self.recorded)
+    }
+
+    // 取出操作本身成功但附带步骤失败时产生的警告
+    pub fn take_warnings(&mut self) -> Vec<String> {
+        std::mem::take(&mut self.warnings)
+    }
+
+    fn set_default(&mut self, name: &str) {
+        self.settings.default_config_file = name.to_string();
+        if let Err(e) = self.save_settings() {
+            self.warnings.push(t!("warning.settings_save", error = e));
+        }
+    }
+}
+
+fn check_lint(name: &str, content: &str) -> Result<()> {
+    let errors = lint::lint(content).iter().filter(|f| f.severity == Severity::Error).count();
+    if errors > 0 {
+        return Err(Error::LintFailed { name: name.to_string(), count: errors });
+    }
+    Ok(())
+}
+
+fn append_audit(path: &Path, entry: &AuditEntry) -> std::io::Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    audit::append_entry(path, entry)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn switcher() -> (TempDir, Switcher) {
+        let home = tempfile::tempdir().unwrap();
+        let mut switcher = Switcher::new(Paths::new(home.path()));
+        switcher.load_settings().unwrap();
+        switcher.ensure_layout().unwrap();
+        (home, switcher)
+    }
+
+    fn profile(switcher: &Switcher, name: &str, content: &str) -> String {
+        switcher.store().write(name, content).unwrap();
+        name.to_string()
+    }
+
+    fn read_json(path: &Path) -> Value {
+        serde_json::from_str(&fs::read_to_string(path).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn switching_to_profile_without_mcp_removes_managed_servers() {
+        let (_home, mut switcher) = switcher();
+        let with = profile(&switcher, "with.json", r#"{"mcpServers": {"docs": {"url": "https://x"}}}"#);
+        let without = profile(&switcher, "without.json", r#"{"env": {}}"#);
+        let user_file = switcher.paths().claude_user_config_file();
+
+        switcher.apply(&with).unwrap();
+        assert!(read_json(&user_file)[mcp::PROFILE_KEY].get("docs").is_some());
+        switcher.apply(&without).unwrap();
+        assert!(read_json(&user_file)[mcp::PROFILE_KEY].get("docs").is_none());
+        assert!(switcher.settings().mcp.managed.is_empty());
+    }
+
+    #[test]
+    fn failed_root_rolls_back_written_roots() {
+        let (home, mut switcher) = switcher();
+        let name = profile(&switcher, "p.json", r#"{"env": {"A": "1"}}"#);
+        let existing = home.path().join("existing");
+        fs::create_dir_all(&existing).unwrap();
+        fs::write(existing.join("settings.json"), "{\"old\": 1}").unwrap();
+        fs::write(home.path().join("file"), "").unwrap();
+        let root = |name: &str, dir: PathBuf| target::ClaudeRoot { name: name.to_string(), dir, enabled: true };
+        switcher.set_claude_roots(vec![root("existing", existing.clone()), root("broken", home.path().join("file/sub"))]).unwrap();
+
+        assert!(switcher.apply(&name).is_err());
+        assert!(!switcher.target().path().exists());
+        assert_eq!(fs::read_to_string(existing.join("settings.json")).unwrap(), "{\"old\": 1}");
+        let statuses: Vec<_> = switcher.root_reports().iter().map(|report| report.status.clone()).collect();
+        assert!(matches!(statuses.as_slice(), [RootStatus::RolledBack, RootStatus::RolledBack, RootStatus::Failed(_)]), "{:?}", statuses);
+    }
+
+    #[test]
+    fn apply_writes_plain_json_and_sets_default() {
+        let (_home, mut switcher) = switcher();
+        let name = profile(&switcher, "p.json", "{\n  // 注释\n  \"env\": {\"A\": \"1\"},\n  \"toolTargets\": {}\n}");
+        switcher.apply(&name).unwrap();
+        assert_eq!(read_json(&switcher.target().path()), serde_json::json!({"env": {"A": "1"}}));
+        assert!(switcher.is_default(&name));
+    }
+
+    #[test]
+    fn scheduled_apply_is_recorded_once_as_automatic_sync() {
+        let (_home, mut switcher) = switcher();
+        let name = profile(&switcher, "p.json", r#"{"env": {"A": "1"}}"#);
+        switcher.apply_scheduled(&name, "work").unwrap();
+        let entries = audit::read_entries(&switcher.audit_log_path());
+        assert_eq!(entries.len(), 1);
+        assert!(entries[0].action == AuditAction::AutomaticSync && entries[0].result == audit::AuditResult::Success);
+        assert_eq!(entries[0].detail, t!("audit.detail.scheduled", rule = "work"));
+        assert!(switcher.is_default(&name));
+    }
+
+    #[test]
+    fn apply_is_blocked_by_lint_errors_until_forced() {
+        let (_home, mut switcher) = switcher();
+        let name = profile(&switcher, "p.json", r#"{"env": {"ANTHROPIC_BASE_URL": "api.example.com"}}"#);
+        assert!(matches!(switcher.apply(&name), Err(Error::LintFailed { count: 1, .. })));
+        assert!(!switcher.target().path().exists());
+        assert!(!switcher.is_default(&name));
+        switcher.force_apply(&name).unwrap();
+        assert!(switcher.target().path().exists());
+        assert!(switcher.is_default(&name));
+    }
+
+    #[test]
+    fn saving_default_profile_with_lint_errors_saves_without_syncing() {
+        let (_home, mut switcher) = switcher();
+        let name = profile(&switcher, "p.json", r#"{"env": {"A": "1"}}"#);
+        switcher.apply(&name).unwrap();
+
+        let outcome = switcher.save_profile(&name, r#"{"env": {"A": null}}"#).unwrap();
+        assert!(matches!(outcome.synced, Some(Err(Error::LintFailed { .. }))));
+        assert_eq!(switcher.store().read(&name).unwrap(), r#"{"env": {"A": null}}"#);
+        assert_eq!(read_json(&switcher.target().path()), serde_json::json!({"env": {"A": "1"}}));
+
+        let outcome = switcher.save_profile(&name, r#"{"env": {"A": "2"}}"#).unwrap();
+        assert!(matches!(outcome.synced, Some(Ok(()))));
+        assert_eq!(read_json(&switcher.target().path()), serde_json::json!({"env": {"A": "2"}}));
+
+        let other = profile(&switcher, "other.json", "{}");
+        assert!(switcher.save_profile(&other, r#"{"env": {"A": null}}"#).unwrap().synced.is_none());
+    }
+}
@@ -0,0 +1,676 @@
+// 切换配置时写入的目标：Claude Code 的活动配置（默认为 ~/.claude/settings.json），
+// 以及其他 AI 命令行工具的配置文件（JSON、TOML、YAML 或 dotenv），由映射决定配置中的哪些值写到哪里
+
+use crate::error::{Error, Result};
+use crate::files;
+use crate::format::FormatSettings;
+use crate::jsonc;
+use crate::outline;
+use crate::paths::{Paths, ACTIVE_CONFIG_NAME};
+use crate::t;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+// 配置中按目标名称直接给出的键和值，不写入 Claude 的活动配置
+pub const PROFILE_KEY: &str = "toolTargets";
+
+pub struct ActiveSettingsTarget {
+    dir: PathBuf,
+}
+
+impl ActiveSettingsTarget {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    pub fn for_paths(paths: &Paths) -> Self {
+        Self::new(paths.claude_dir())
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    pub fn path(&self) -> PathBuf {
+        self.dir.join(ACTIVE_CONFIG_NAME)
+    }
+
+    // 活动配置不存在时返回 None
+    pub fn read(&self) -> Result<Option<String>> {
+        let path = self.path();
+        if !path.exists() {
+            return Ok(None);
+        }
+        fs::read_to_string(&path).map(Some).map_err(|source| Error::Read { path, source })
+    }
+
+    // 不存在的 Claude 配置目录会一并创建
+    pub fn write(&self, content: &str) -> Result<()> {
+        files::write_atomically(&self.path(), content)
+    }
+
+    // 记录写入前的状态，写入失败或需要撤销时交给 restore
+    pub fn snapshot(&self) -> Result<Snapshot> {
+        let previous = self.read()?;
+        let created_dir = self.dir.ancestors().take_while(|dir| !dir.as_os_str().is_empty() && !dir.exists()).last().map(Path::to_path_buf);
+        Ok(Snapshot { previous, created_dir })
+    }
+
+    // 恢复写入前读到的内容；原来没有活动配置时删除写入的文件，以及写入时新建的目录
+    pub fn restore(&self, snapshot: &Snapshot) -> Result<()> {
+        if let Some(content) = &snapshot.previous {
+            return self.write(content);
+        }
+        // 写入失败时文件和目录可能都没有创建
+        let path = self.path();
+        if path.exists() {
+            fs::remove_file(&path).map_err(|source| Error::Remove { path, source })?;
+        }
+        let Some(created_dir) = &snapshot.created_dir else { return Ok(()) };
+        for dir in self.dir.ancestors() {
+            if dir.is_dir() {
+                fs::remove_dir(dir).map_err(|source| Error::Remove { path: dir.to_path_buf(), source })?;
+            }
+            if dir == created_dir {
+                break;
+            }
+        }
+        Ok(())
+    }
+}
+
+// 写入活动配置之前的状态
+#[derive(Clone, Debug, Default)]
+pub struct Snapshot {
+    previous: Option<String>,
+    // 写入时需要新建的最上层目录
+    created_dir: Option<PathBuf>,
+}
+
+// 需要同时写入的其他 Claude 配置目录，例如开发容器挂载的主目录或沙箱用户的目录
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+#[serde(default)]
+pub struct ClaudeRoot {
+    pub name: String,
+    // 包含 settings.json 的目录，可以以 ~ 开头
+    pub dir: PathBuf,
+    pub enabled: bool,
+}
+
+impl Default for ClaudeRoot {
+    fn default() -> Self {
+        Self { name: String::new(), dir: PathBuf::new(), enabled: true }
+    }
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub enum RootStatus {
+    Written,
+    Failed(String),
+    // 其他目录失败后已恢复原内容
+    RolledBack,
+    RollbackFailed(String),
+    // 前面的目录失败，没有写入
+    Skipped,
+}
+
+impl RootStatus {
+    pub fn is_ok(&self) -> bool {
+        *self == RootStatus::Written
+    }
+
+    pub fn label(&self) -> String {
+        match self {
+            RootStatus::Written => t!("roots.status.written").to_string(),
+            RootStatus::Failed(error) => t!("roots.status.failed", error = error),
+            RootStatus::RolledBack => t!("roots.status.rolled_back").to_string(),
+            RootStatus::RollbackFailed(error) => t!("roots.status.rollback_failed", error = error),
+            RootStatus::Skipped => t!("roots.status.skipped").to_string(),
+        }
+    }
+}
+
+// 最近一次写入时各 Claude 配置目录的结果，第一个为主目录
+#[derive(Clone, Debug)]
+pub struct RootReport {
+    pub name: String,
+    pub path: PathBuf,
+    pub status: RootStatus,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash, Debug, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum TargetFormat {
+    #[default]
+    Json,
+    Toml,
+    Yaml,
+    Dotenv,
+}
+
+impl TargetFormat {
+    pub const ALL: [TargetFormat; 4] = [TargetFormat::Json, TargetFormat::Toml, TargetFormat::Yaml, TargetFormat::Dotenv];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            TargetFormat::Json => "JSON",
+            TargetFormat::Toml => "TOML",
+            TargetFormat::Yaml => "YAML",
+            TargetFormat::Dotenv => "dotenv",
+        }
+    }
+
+    // 按扩展名推断格式，.env 之类没有扩展名的文件视为 dotenv
+    pub fn from_path(path: &Path) -> Self {
+        match path.extension().and_then(|e| e.to_str()).map(str::to_ascii_lowercase).as_deref() {
+            Some("json") | Some("jsonc") => TargetFormat::Json,
+            Some("toml") => TargetFormat::Toml,
+            Some("yaml") | Some("yml") => TargetFormat::Yaml,
+            _ => TargetFormat::Dotenv,
+        }
+    }
+
+    // 解析为 JSON 值用于比较；dotenv 解析为字符串对象
+    fn parse(self, content: &str) -> std::result::Result<Value, String> {
+        match self {
+            TargetFormat::Json if content.trim().is_empty() => Ok(Value::Object(Map::new())),
+            TargetFormat::Json => jsonc::from_str(content).map_err(|e| e.to_string()),
+            TargetFormat::Toml => {
+                let document = content.parse::<toml_edit::DocumentMut>().map_err(|e| e.to_string())?;
+                Ok(toml_item_to_json(document.as_item()).unwrap_or_default())
+            }
+            TargetFormat::Yaml if content.trim().is_empty() => Ok(Value::Object(Map::new())),
+            TargetFormat::Yaml => serde_yaml::from_str(content).map_err(|e| e.to_string()),
+            TargetFormat::Dotenv => Ok(Value::Object(
+                content.lines().filter_map(dotenv_entry).map(|(key, value)| (key.to_string(), Value::String(value))).collect(),
+            )),
+        }
+    }
+
+    // 先删除 remove 中的键，再写入 set 中的键；尽量保留文件中的其他内容（JSON、TOML 和 dotenv 保留注释）
+    fn merge(self, content: &str, set: &Map<String, Value>, remove: &[String]) -> std::result::Result<String, String> {
+        match self {
+            TargetFormat::Json => {
+                let mut content = if content.trim().is_empty() { "{}".to_string() } else { content.to_string() };
+                let format = FormatSettings::default();
+                let removed = remove.iter().map(|key| (key, None));
+                for (key, value) in removed.chain(set.iter().map(|(key, value)| (key, Some(value)))) {
+                    let mut path: Vec<&str> = key.split('.').collect();
+                    let name = path.pop().unwrap_or_default();
+                    content = outline::replace_nested_member(&content, &path, name, value, &format).map_err(|e| e.to_string())?;
+                }
+                Ok(content)
+            }
+            TargetFormat::Toml => {
+                let mut document = content.parse::<toml_edit::DocumentMut>().map_err(|e| e.to_string())?;
+                for key in remove {
+                    toml_remove(document.as_table_mut(), key);
+                }
+                for (key, value) in set {
+                    let Some(value) = json_to_toml(value) else { continue };
+                    toml_set(document.as_table_mut(), key, value)?;
+                }
+                Ok(document.to_string())
+            }
+            TargetFormat::Yaml => {
+                let mut root: serde_yaml::Value = if content.trim().is_empty() {
+                    serde_yaml::Value::Mapping(serde_yaml::Mapping::new())
+                } else {
+                    serde_yaml::from_str(content).map_err(|e| e.to_string())?
+                };
+                for key in remove {
+                    yaml_remove(&mut root, key);
+                }
+                for (key, value) in set {
+                    let value = serde_yaml::to_value(value).map_err(|e| e.to_string())?;
+                    yaml_set(&mut root, key, value)?;
+                }
+                serde_yaml::to_string(&root).map_err(|e| e.to_string())
+            }
+            TargetFormat::Dotenv => Ok(dotenv_merge(content, set, remove)),
+        }
+    }
+}
+
+// 把配置中 source（JSON 指针，如 /env/ANTHROPIC_BASE_URL）处的值写到目标的 key（点号分隔的路径；dotenv 为变量名）
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug, Default)]
+#[serde(default)]
+pub struct Mapping {
+    pub source: String,
+    pub key: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+#[serde(default)]
+pub struct ToolTarget {
+    pub name: String,
+    pub enabled: bool,
+    // 可以以 ~ 开头
+    pub path: PathBuf,
+    pub format: TargetFormat,
+    pub mappings: Vec<Mapping>,
+    // 上次应用时写入的键，下次应用时先移除
+    pub managed: Vec<String>,
+}
+
+impl Default for ToolTarget {
+    fn default() -> Self {
+        Self { name: String::new(), enabled: true, path: PathBuf::new(), format: TargetFormat::Json, mappings: Vec::new(), managed: Vec::new() }
+    }
+}
+
+// 目标文件与配置的对比结果
+#[derive(Clone, PartialEq, Debug)]
+pub enum TargetStatus {
+    Disabled,
+    // 配置没有为该目标提供任何值
+    Unmapped,
+    Missing,
+    InSync,
+    // 与配置不一致的键
+    Differs(Vec<String>),
+    Unreadable(String),
+}
+
+impl TargetStatus {
+    pub fn label(&self) -> String {
+        match self {
+            TargetStatus::Disabled => t!("targets.status.disabled").to_string(),
+            TargetStatus::Unmapped => t!("targets.status.unmapped").to_string(),
+            TargetStatus::Missing => t!("targets.status.missing").to_string(),
+            TargetStatus::InSync => t!("targets.status.in_sync").to_string(),
+            TargetStatus::Differs(keys) => t!("targets.status.differs", keys = keys.join(", ")),
+            TargetStatus::Unreadable(error) => t!("targets.status.unreadable", error = error),
+        }
+    }
+}
+
+impl ToolTarget {
+    pub fn resolved_path(&self, home: &Path) -> PathBuf {
+        Paths::new(home).expand_home(&self.path)
+    }
+
+    // 返回第一个问题
+    pub fn validate(&self) -> Option<String> {
+        if self.name.trim().is_empty() {
+            return Some(t!("targets.invalid.name").to_string());
+        }
+        if self.path.as_os_str().is_empty() {
+            return Some(t!("targets.invalid.path", name = self.name));
+        }
+        self.mappings.iter().find_map(|mapping| {
+            if !mapping.source.starts_with('/') {
+                Some(t!("targets.invalid.source", name = self.name, source = mapping.source))
+            } else if mapping.key.trim().is_empty() {
+                Some(t!("targets.invalid.key", name = self.name, source = mapping.source))
+            } else {
+                None
+            }
+        })
+    }
+
+    // 该目标要写入的键和值：先按映射取值，再合并配置中 toolTargets.<名称> 的内容
+    pub fn values(&self, profile: &Value) -> Map<String, Value> {
+        let mut values = Map::new();
+        for mapping in &self.mappings {
+            if let Some(value) = profile.pointer(&mapping.source).filter(|v| !v.is_null()) {
+                values.insert(mapping.key.trim().to_string(), value.clone());
+            }
+        }
+        if let Some(section) = profile.get(PROFILE_KEY).and_then(|s| s.get(&self.name)).and_then(Value::as_object) {
+            values.extend(section.iter().filter(|(_, v)| !v.is_null()).map(|(k, v)| (k.clone(), v.clone())));
+        }
+        values
+    }
+
+    // 写入配置对应的值并移除上次写入、这次没有的键，返回这次写入的键
+    pub fn apply(&self, home: &Path, profile: &Value) -> Result<Vec<String>> {
+        let values = self.values(profile);
+        let keys: Vec<String> = values.keys().cloned().collect();
+        let removed: Vec<String> = self.managed.iter().filter(|key| !values.contains_key(*key)).cloned().collect();
+        let path = self.resolved_path(home);
+        let content = match fs::read_to_string(&path) {
+            Ok(content) => content,
+            // 没有要写的内容时不创建文件
+            Err(e) if e.kind() == io::ErrorKind::NotFound && values.is_empty() => return Ok(keys),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => String::new(),
+            Err(source) => return Err(Error::Read { path, source }),
+        };
+        let merged = self.format.merge(&content, &values, &removed).map_err(|message| Error::InvalidTarget { path: path.clone(), message })?;
+        if merged != content {
+            files::write_atomically(&path, &merged)?;
+        }
+        Ok(keys)
+    }
+
+    pub fn status(&self, home: &Path, profile: &Value) -> TargetStatus {
+        if !self.enabled {
+            return TargetStatus::Disabled;
+        }
+        let values = self.values(profile);
+        if values.is_empty() {
+            return TargetStatus::Unmapped;
+        }
+        let content = match fs::read_to_string(self.resolved_path(home)) {
+            Ok(content) => content,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return TargetStatus::Missing,
+            Err(e) => return TargetStatus::Unreadable(e.to_string()),
+        };
+        let current = match self.format.parse(&content) {
+            Ok(current) => current,
+            Err(e) => return TargetStatus::Unreadable(e),
+        };
+        let differs: Vec<String> = values
+            .iter()
+            .filter(|(key, expected)| {
+                let actual = match self.format {
+                    TargetFormat::Dotenv => current.get(key.as_str()),
+                    _ => key.split('.').try_fold(&current, |value, segment| value.get(segment)),
+                };
+                match (self.format, actual) {
+                    (TargetFormat::Dotenv, Some(actual)) => actual.as_str() != Some(dotenv_string(expected).as_str()),
+                    (_, Some(actual)) => actual != *expected,
+                    (_, None) => true,
+                }
+            })
+            .map(|(key, _)| key.clone())
+            .collect();
+        if differs.is_empty() {
+            TargetStatus::InSync
+        } else {
+            TargetStatus::Differs(differs)
+        }
+    }
+}
+
+// 切换后各目标的写入结果，用于界面显示
+#[derive(Clone, Debug)]
+pub struct TargetReport {
+    pub name: String,
+    // 成功时为写入的键数
+    pub result: std::result::Result<usize, String>,
+}
+
+// Claude 不认识 toolTargets，写入活动配置前去掉
+pub fn strip_from_settings(content: &str) -> String {
+    outline::replace_member(content, PROFILE_KEY, None, &FormatSettings::default()).unwrap_or_else(|_| content.to_string())
+}
+
+fn toml_item_to_json(item: &toml_edit::Item) -> Option<Value> {
+    match item {
+        toml_edit::Item::None => None,
+        toml_edit::Item::Value(value) => Some(toml_value_to_json(value)),
+        toml_edit::Item::Table(table) => Some(Value::Object(table.iter().filter_map(|(k, v)| Some((k.to_string(), toml_item_to_json(v)?))).collect())),
+        toml_edit::Item::ArrayOfTables(tables) => {
+            Some(Value::Array(tables.iter().map(|table| Value::Object(table.iter().filter_map(|(k, v)| Some((k.to_string(), toml_item_to_json(v)?))).collect())).collect()))
+        }
+    }
+}
+
+fn toml_value_to_json(value: &toml_edit::Value) -> Value {
+    match value {
+        toml_edit::Value::String(s) => Value::String(s.value().clone()),
+        toml_edit::Value::Integer(i) => Value::from(*i.value()),
+        toml_edit::Value::Float(f) => Value::from(*f.value()),
+        toml_edit::Value::Boolean(b) => Value::Bool(*b.value()),
+        toml_edit::Value::Datetime(d) => Value::String(d.value().to_string()),
+        toml_edit::Value::Array(array) => Value::Array(array.iter().map(toml_value_to_json).collect()),
+        toml_edit::Value::InlineTable(table) => Value::Object(table.iter().map(|(k, v)| (k.to_string(), toml_value_to_json(v))).collect()),
+    }
+}
+
+// TOML 没有 null，null 的值和数组元素跳过
+fn json_to_toml(value: &Value) -> Option<toml_edit::Value> {
+    Some(match value {
+        Value::Null => return None,
+        Value::Bool(b) => (*b).into(),
+        Value::Number(n) => match n.as_i64() {
+            Some(i) => i.into(),
+            None => n.as_f64().unwrap_or_default().into(),
+        },
+        Value::String(s) => s.as_str().into(),
+        Value::Array(items) => toml_edit::Value::Array(items.iter().filter_map(json_to_toml).collect()),
+        Value::Object(object) => toml_edit::Value::InlineTable(object.iter().filter_map(|(k, v)| Some((k.as_str(), json_to_toml(v)?))).collect()),
+    })
+}
+
+fn toml_set(root: &mut toml_edit::Table, key: &str, value: toml_edit::Value) -> std::result::Result<(), String> {
+    let mut segments: Vec<&str> = key.split('.').collect();
+    let name = segments.pop().unwrap_or_default();
+    let mut table: &mut dyn toml_edit::TableLike = root;
+    for segment in segments {
+        table = table
+            .entry(segment)
+            .or_insert_with(toml_edit::table)
+            .as_table_like_mut()
+            .ok_or_else(|| t!("targets.not_a_table", key = segment))?;
+    }
+    match table.get_mut(name).and_then(toml_edit::Item::as_value_mut) {
+        // 保留原值两侧的空白和行尾注释
+        Some(existing) => {
+            let decor = existing.decor().clone();
+            *existing = value;
+            *existing.decor_mut() = decor;
+        }
+        None => {
+            table.insert(name, toml_edit::Item::Value(value));
+        }
+    }
+    Ok(())
+}
+
+fn toml_remove(root: &mut toml_edit::Table, key: &str) {
+    let mut segments: Vec<&str> = key.split('.').collect();
+    let name = segments.pop().unwrap_or_default();
+    let mut table: &mut dyn toml_edit::TableLike = root;
+    for segment in segments {
+        match table.get_mut(segment).and_then(toml_edit::Item::as_table_like_mut) {
+            Some(child) => table = child,
+            None => return,
+        }
+    }
+    table.remove(name);
+}
+
+fn yaml_set(root: &mut serde_yaml::Value, key: &str, value: serde_yaml::Value) -> std::result::Result<(), String> {
+    let mut segments: Vec<&str> = key.split('.').collect();
+    let name = segments.pop().unwrap_or_default();
+    let mut node = root;
+    for segment in segments {
+        let mapping = node.as_mapping_mut().ok_or_else(|| t!("targets.not_a_table", key = segment))?;
+        node = mapping.entry(serde_yaml::Value::from(segment)).or_insert_with(|| serde_yaml::Value::Mapping(serde_yaml::Mapping::new()));
+    }
+    let mapping = node.as_mapping_mut().ok_or_else(|| t!("targets.not_a_table", key = key))?;
+    mapping.insert(serde_yaml::Value::from(name), value);
+    Ok(())
+}
+
+fn yaml_remove(root: &mut serde_yaml::Value, key: &str) {
+    let mut segments: Vec<&str> = key.split('.').collect();
+    let name = segments.pop().unwrap_or_default();
+    let mut node = root;
+    for segment in segments {
+        match node.get_mut(segment) {
+            Some(child) => node = child,
+            None => return,
+        }
+    }
+    if let Some(mapping) = node.as_mapping_mut() {
+        mapping.shift_remove(name);
+    }
+}
+
+// 解析 KEY=value 或 export KEY=value，去掉值两侧的引号
+fn dotenv_entry(line: &str) -> Option<(&str, String)> {
+    let line = line.trim();
+    if line.starts_with('#') {
+        return None;
+    }
+    let line = line.strip_prefix("export ").unwrap_or(line);
+    let (key, value) = line.split_once('=')?;
+    let value = value.trim();
+    let value = match value.chars().next() {
+        Some(quote @ ('"' | '\'')) if value.len() >= 2 && value.ends_with(quote) => {
+            let inner = &value[1..value.len() - 1];
+            if quote == '"' { unescape_double_quoted(inner) } else { inner.to_string() }
+        }
+        // 未加引号的值中 # 之后是注释
+        _ => value.split(" #").next().unwrap_or_default().trim().to_string(),
+    };
+    Some((key.trim(), value))
+}
+
+fn dotenv_string(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
+
+// 需要引号时优先用单引号，其中的内容不做任何展开；值本身含单引号时用双引号，
+// 并转义 shell 和 dotenv 加载器在双引号中仍会处理的字符
+fn dotenv_line(key: &str, value: &Value) -> String {
+    let value = dotenv_string(value);
+    if !value.chars().any(|c| c.is_whitespace() || matches!(c, '#' | '"' | '\'' | '\\' | '$' | '`')) {
+        format!("{}={}", key, value)
+    } else if !value.contains('\'') {
+        format!("{}='{}'", key, value)
+    } else {
+        let escaped: String = value
+            .chars()
+            .flat_map(|c| matches!(c, '\\' | '"' | '$' | '`').then_some('\\').into_iter().chain([c]))
+            .collect();
+        format!("{}=\"{}\"", key, escaped)
+    }
+}
+
+// 双引号中反斜杠只转义 \ " $ `，其余保持原样
+fn unescape_double_quoted(inner: &str) -> String {
+    let mut value = String::new();
+    let mut chars = inner.chars().peekable();
+    while let Some(c) = chars.next() {
+        match chars.peek() {
+            Some(&next) if c == '\\' && matches!(next, '\\' | '"' | '$' | '`') => {
+                value.push(next);
+                chars.next();
+            }
+            _ => value.push(c),
+        }
+    }
+    value
+}
+
+// 原地替换已有的变量，删除要移除的变量，新变量追加到末尾；注释和其他行保持不变
+fn dotenv_merge(content: &str, set: &Map<String, Value>, remove: &[String]) -> String {
+    let mut written = Vec::new();
+    let mut lines: Vec<String> = Vec::new();
+    for line in content.lines() {
+        let key = dotenv_entry(line).map(|(key, _)| key.to_string());
+        match key {
+            Some(key) if set.contains_key(&key) => {
+                if !written.contains(&key) {
+                    let export = if line.trim_start().starts_with("export ") { "export " } else { "" };
+                    lines.push(format!("{}{}", export, dotenv_line(&key, &set[&key])));
+                    written.push(key);
+                }
+            }
+            Some(key) if remove.contains(&key) => {}
+            _ => lines.push(line.to_string()),
+        }
+    }
+    for (key, value) in set.iter().filter(|(key, _)| !written.contains(key)) {
+        lines.push(dotenv_line(key, value));
+    }
+    let mut merged = lines.join("\n");
+    if !merged.is_empty() {
+        merged.push('\n');
+    }
+    merged
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn values(value: Value) -> Map<String, Value> {
+        value.as_object().unwrap().clone()
+    }
+
+    #[test]
+    fn dotenv_values_are_not_expanded() {
+        assert_eq!(dotenv_line("A", &json!("plain")), "A=plain");
+        assert_eq!(dotenv_line("A", &json!("p$ss word")), "A='p$ss word'");
+        assert_eq!(dotenv_line("A", &json!("it's $HOME")), r#"A="it's \$HOME""#);
+        assert_eq!(dotenv_line("A", &json!(3)), "A=3");
+        for value in ["p$ss word", "it's $HOME \"q\" \\ `x`", "a # b"] {
+            let line = dotenv_line("A", &json!(value));
+            assert_eq!(dotenv_entry(&line), Some(("A", value.to_string())), "{}", line);
+        }
+    }
+
+    #[test]
+    fn dotenv_merge_keeps_comments_and_export() {
+        let content = "# keys\nexport API_KEY=old # note\nKEEP=1\nOLD=x\n";
+        let merged = dotenv_merge(content, &values(json!({"API_KEY": "new", "NEW": "y"})), &["OLD".to_string()]);
+        assert_eq!(merged, "# keys\nexport API_KEY=new\nKEEP=1\nNEW=y\n");
+    }
+
+    #[test]
+    fn toml_set_creates_tables_and_keeps_comments() {
+        let mut document: toml_edit::DocumentMut = "# top\n[model]\nname = \"old\" # current\n".parse().unwrap();
+        toml_set(document.as_table_mut(), "model.name", "new".into()).unwrap();
+        toml_set(document.as_table_mut(), "provider.base_url", "https://x".into()).unwrap();
+        let text = document.to_string();
+        assert!(text.contains("name = \"new\" # current"), "{}", text);
+        assert_eq!(toml_item_to_json(&document["provider"]["base_url"]), Some(json!("https://x")));
+        assert!(toml_set(document.as_table_mut(), "model.name.inner", "x".into()).is_err());
+    }
+
+    #[test]
+    fn restore_removes_created_directories() {
+        let home = tempfile::tempdir().unwrap();
+        let target = ActiveSettingsTarget::new(home.path().join("a/b/.claude"));
+        let snapshot = target.snapshot().unwrap();
+        target.write("{}").unwrap();
+        target.restore(&snapshot).unwrap();
+        assert!(!home.path().join("a").exists());
+        assert!(home.path().exists());
+    }
+
+    #[test]
+    fn restore_writes_previous_content() {
+        let home = tempfile::tempdir().unwrap();
+        let target = ActiveSettingsTarget::new(home.path());
+        target.write("{\"old\": true}").unwrap();
+        let snapshot = target.snapshot().unwrap();
+        target.write("{}").unwrap();
+        target.restore(&snapshot).unwrap();
+        assert_eq!(target.read().unwrap().as_deref(), Some("{\"old\": true}"));
+        assert!(!home.path().join("settings.json.tmp").exists());
+    }
+
+    #[test]
+    fn apply_writes_mapped_values_and_removes_stale_keys() {
+        let home = tempfile::tempdir().unwrap();
+        let mut tool = ToolTarget {
+            name: "tool".to_string(),
+            path: PathBuf::from("~/tool/config.yaml"),
+            format: TargetFormat::Yaml,
+            mappings: vec![Mapping { source: "/env/ANTHROPIC_MODEL".to_string(), key: "model.name".to_string() }],
+            ..Default::default()
+        };
+        let profile = json!({"env": {"ANTHROPIC_MODEL": "m1"}, "toolTargets": {"tool": {"extra": true}}});
+        tool.managed = tool.apply(home.path(), &profile).unwrap();
+        assert_eq!(tool.managed, ["model.name", "extra"]);
+        assert_eq!(tool.status(home.path(), &profile), TargetStatus::InSync);
+
+        tool.managed = tool.apply(home.path(), &json!({"env": {"ANTHROPIC_MODEL": "m2"}})).unwrap();
+        let written: serde_yaml::Value = serde_yaml::from_str(&fs::read_to_string(home.path().join("tool/config.yaml")).unwrap()).unwrap();
+        assert_eq!(written["model"]["name"], serde_yaml::Value::from("m2"));
+        assert!(written.get("extra").is_none());
+    }
+}
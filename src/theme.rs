@@ -0,0 +1,369 @@
+// 主题：内置深色/浅色主题，以及应用目录 themes/*.json 中用户自定义的主题
+
+use claude_code_switcher::t;
+use eframe::egui::Color32;
+use serde::de::{self, Deserializer};
+use serde::{Deserialize, Serialize, Serializer};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime};
+
+pub const THEMES_DIR_NAME: &str = "themes";
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+// --- 主题颜色定义 ---
+#[derive(Clone, Copy, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ThemeColors {
+    #[serde(with = "hex")] pub base: Color32,
+    #[serde(with = "hex")] pub mantle: Color32,
+    #[serde(with = "hex")] pub crust: Color32,
+    #[serde(with = "hex")] pub surface0: Color32,
+    #[serde(with = "hex")] pub surface1: Color32,
+    #[serde(with = "hex")] pub text: Color32,
+    #[serde(with = "hex")] pub lavender: Color32,
+    #[serde(with = "hex")] pub green: Color32,
+    #[serde(with = "hex")] pub red: Color32,
+    #[serde(with = "hex")] pub yellow: Color32,
+    // 新增颜色用于更精致的UI
+    #[serde(with = "hex")] pub accent: Color32,
+    #[serde(with = "hex")] pub muted: Color32,
+    #[serde(with = "hex")] pub border: Color32,
+    #[serde(with = "hex")] pub highlight: Color32,
+}
+
+// 编辑器中 JSON 各类记号的颜色
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SyntaxColors {
+    #[serde(with = "hex")] pub key: Color32,
+    #[serde(with = "hex")] pub string: Color32,
+    #[serde(with = "hex")] pub number: Color32,
+    #[serde(with = "hex")] pub keyword: Color32,
+    #[serde(with = "hex")] pub punctuation: Color32,
+    // 旧的主题文件没有这一项
+    #[serde(with = "hex", default = "default_comment_color")] pub comment: Color32,
+}
+
+fn default_comment_color() -> Color32 {
+    Color32::from_rgb(128, 128, 128)
+}
+
+pub const CLAUDE_DARK: ThemeColors = ThemeColors {
+    base: Color32::from_rgb(18, 18, 23),       // 更亮的深色背景
+    crust: Color32::from_rgb(28, 28, 35),      // 侧边栏背景 - 更亮
+    mantle: Color32::from_rgb(22, 22, 28),     // 编辑器背景 - 更亮
+    surface0: Color32::from_rgb(40, 40, 48),   // 非交互元素 - 更亮
+    surface1: Color32::from_rgb(50, 50, 60),   // 悬停状态 - 更亮
+    text: Color32::from_rgb(248, 250, 252),    // 更亮的白色文字
+    lavender: Color32::from_rgb(167, 139, 250), // 更亮的紫色主色调
+    green: Color32::from_rgb(52, 211, 153),    // 更亮的绿色
+    red: Color32::from_rgb(248, 113, 113),     // 更亮的红色
+    yellow: Color32::from_rgb(251, 191, 36),   // 更亮的黄色
+    accent: Color32::from_rgb(124, 126, 251),  // 更亮的强调色
+    muted: Color32::from_rgb(156, 163, 175),   // 次要文字色
+    border: Color32::from_rgb(75, 85, 99),     // 更亮的边框色
+    highlight: Color32::from_rgb(45, 55, 72),  // 更亮的高亮背景
+};
+
+pub const CLAUDE_LIGHT: ThemeColors = ThemeColors {
+    base: Color32::from_rgb(255, 255, 255),    // 纯白背景
+    crust: Color32::from_rgb(248, 250, 252),   // 侧边栏背景 - 更亮的浅灰
+    mantle: Color32::from_rgb(255, 255, 255),  // 编辑器背景 - 纯白
+    surface0: Color32::from_rgb(241, 245, 249), // 非交互元素 - 更亮
+    surface1: Color32::from_rgb(226, 232, 240), // 悬停状态 - 更明显
+    text: Color32::from_rgb(15, 23, 42),       // 更深的文字色
+    lavender: Color32::from_rgb(139, 92, 246), // 鲜艳紫色
+    green: Color32::from_rgb(34, 197, 94),     // 鲜艳绿色
+    red: Color32::from_rgb(239, 68, 68),       // 鲜艳红色
+    yellow: Color32::from_rgb(245, 158, 11),   // 鲜艳黄色
+    accent: Color32::from_rgb(99, 102, 241),   // 鲜艳强调色
+    muted: Color32::from_rgb(100, 116, 139),   // 更深的次要文字色
+    border: Color32::from_rgb(203, 213, 225),  // 更明显的边框色
+    highlight: Color32::from_rgb(219, 234, 254), // 更明显的高亮背景
+};
+
+const DARK_SYNTAX: SyntaxColors = SyntaxColors {
+    key: Color32::from_rgb(156, 220, 254),         // 浅蓝色 - 键名
+    string: Color32::from_rgb(206, 145, 120),      // 橙色 - 字符串值
+    number: Color32::from_rgb(181, 206, 168),      // 浅绿色 - 数字
+    keyword: Color32::from_rgb(197, 134, 192),     // 紫色 - 关键字
+    punctuation: Color32::from_rgb(212, 212, 212), // 浅灰色 - 标点
+    comment: Color32::from_rgb(106, 153, 85),      // 灰绿色 - 注释
+};
+
+const LIGHT_SYNTAX: SyntaxColors = SyntaxColors {
+    key: Color32::from_rgb(0, 92, 197),        // 深蓝色 - 键名
+    string: Color32::from_rgb(163, 21, 21),    // 深红色 - 字符串值
+    number: Color32::from_rgb(9, 134, 88),     // 深绿色 - 数字
+    keyword: Color32::from_rgb(111, 66, 193),  // 深紫色 - 关键字
+    punctuation: Color32::from_rgb(80, 80, 80), // 深灰色 - 标点
+    comment: Color32::from_rgb(0, 128, 0),      // 绿色 - 注释
+};
+
+// 界面绘制所需的全部颜色
+#[derive(Clone, Copy)]
+pub struct Palette {
+    // 决定 egui 的基础样式和阴影深浅
+    pub dark: bool,
+    pub colors: ThemeColors,
+    pub syntax: SyntaxColors,
+    // 文件列表隔行背景
+    pub alt_row: Color32,
+    // 控件悬停背景
+    pub hover: Color32,
+}
+
+impl Palette {
+    pub const DARK: Palette = Palette {
+        dark: true,
+        colors: CLAUDE_DARK,
+        syntax: DARK_SYNTAX,
+        alt_row: Color32::from_rgb(32, 35, 42),  // 更深的对比色
+        hover: Color32::from_rgb(60, 60, 70),    // 更亮的悬停背景
+    };
+
+    pub const LIGHT: Palette = Palette {
+        dark: false,
+        colors: CLAUDE_LIGHT,
+        syntax: LIGHT_SYNTAX,
+        alt_row: Color32::from_rgb(241, 245, 249), // 更明显的浅色
+        hover: Color32::from_rgb(219, 234, 254),   // 更明显的浅色悬停
+    };
+}
+
+#[derive(Clone, PartialEq)]
+pub enum Theme {
+    Dark,
+    Light,
+    // themes/ 目录中的主题，名称为文件名（不含扩展名）
+    Custom(String),
+}
+
+impl Theme {
+    // AppSettings.theme 中保存的名称
+    pub fn from_setting(name: &str) -> Self {
+        match name {
+            "Dark" | "" => Theme::Dark,
+            "Light" => Theme::Light,
+            name => Theme::Custom(name.to_string()),
+        }
+    }
+
+    pub fn setting_name(&self) -> String {
+        match self {
+            Theme::Dark => "Dark".to_string(),
+            Theme::Light => "Light".to_string(),
+            Theme::Custom(name) => name.clone(),
+        }
+    }
+}
+
+// --- 主题文件 ---
+#[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ThemeFile {
+    // 省略时按背景色亮度判断
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    dark: Option<bool>,
+    colors: ThemeColors,
+    syntax: SyntaxColors,
+}
+
+impl ThemeFile {
+    fn into_palette(self) -> Palette {
+        let colors = self.colors;
+        let dark = self.dark.unwrap_or_else(|| {
+            let [r, g, b, _] = colors.base.to_array();
+            (r as u32 * 299 + g as u32 * 587 + b as u32 * 114) / 1000 < 128
+        });
+        Palette { dark, colors, syntax: self.syntax, alt_row: colors.surface0, hover: colors.surface1 }
+    }
+}
+
+// 自定义主题目录，按文件修改时间轮询实现热重载
+pub struct ThemeLibrary {
+    dir: PathBuf,
+    themes: BTreeMap<String, Palette>,
+    signature: Vec<(PathBuf, Option<SystemTime>, u64)>,
+    last_check: Option<Instant>,
+}
+
+impl ThemeLibrary {
+    pub fn new(dir: PathBuf) -> Self {
+        Self { dir, themes: BTreeMap::new(), signature: Vec::new(), last_check: None }
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    pub fn names(&self) -> impl Iterator<Item = &String> {
+        self.themes.keys()
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Palette> {
+        self.themes.get(name)
+    }
+
+    // 距上次检查超过一秒且目录内容有变化时重新加载，返回 Some(加载错误)
+    pub fn poll(&mut self) -> Option<Vec<String>> {
+        if self.last_check.is_some_and(|t| t.elapsed() < POLL_INTERVAL) {
+            return None;
+        }
+        self.last_check = Some(Instant::now());
+        let signature = self.scan_signature();
+        if signature == self.signature {
+            return None;
+        }
+        self.signature = signature;
+        Some(self.load_all())
+    }
+
+    fn scan_signature(&self) -> Vec<(PathBuf, Option<SystemTime>, u64)> {
+        let Ok(entries) = fs::read_dir(&self.dir) else { return Vec::new() };
+        let mut signature: Vec<_> = entries
+            .filter_map(Result::ok)
+            .map(|entry| entry.path())
+            .filter(|p| p.extension().is_some_and(|ext| ext == "json"))
+            .map(|p| {
+                let meta = fs::metadata(&p).ok();
+                let modified = meta.as_ref().and_then(|m| m.modified().ok());
+                let len = meta.map_or(0, |m| m.len());
+                (p, modified, len)
+            })
+            .collect();
+        signature.sort_by(|a, b| a.0.cmp(&b.0));
+        signature
+    }
+
+    fn load_all(&mut self) -> Vec<String> {
+        self.themes.clear();
+        let mut errors = Vec::new();
+        for (path, _, _) in &self.signature {
+            let Some(name) = path.file_stem().and_then(|s| s.to_str()) else { continue };
+            // 与内置主题同名的文件会被忽略
+            if matches!(Theme::from_setting(name), Theme::Dark | Theme::Light) {
+                continue;
+            }
+            let parsed = fs::read_to_string(path)
+                .map_err(|e| e.to_string())
+                .and_then(|content| serde_json::from_str::<ThemeFile>(&content).map_err(|e| e.to_string()));
+            match parsed {
+                Ok(file) => {
+                    self.themes.insert(name.to_string(), file.into_palette());
+                }
+                Err(e) => errors.push(format!("{}: {}", path.file_name().unwrap_or_default().to_string_lossy(), e)),
+            }
+        }
+        errors
+    }
+
+    // 把调色板写成主题文件，作为自定义主题的起点；已有同名文件时改用 <名称>_1.json 等新名称，不覆盖
+    pub fn export(&self, name: &str, palette: &Palette) -> Result<PathBuf, String> {
+        fs::create_dir_all(&self.dir).map_err(|e| e.to_string())?;
+        let mut path = self.dir.join(format!("{}.json", name));
+        let mut i = 1;
+        while path.exists() {
+            path = self.dir.join(format!("{}_{}.json", name, i));
+            i += 1;
+        }
+        let file = ThemeFile { dark: Some(palette.dark), colors: palette.colors, syntax: palette.syntax };
+        let content = serde_json::to_string_pretty(&file).map_err(|e| e.to_string())?;
+        fs::write(&path, content).map_err(|e| e.to_string())?;
+        Ok(path)
+    }
+}
+
+// 颜色以 "#rgb"、"#rrggbb" 或 "#rrggbbaa" 表示
+mod hex {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(color: &Color32, serializer: S) -> Result<S::Ok, S::Error> {
+        let [r, g, b, a] = color.to_srgba_unmultiplied();
+        let text = if a == 255 {
+            format!("#{:02x}{:02x}{:02x}", r, g, b)
+        } else {
+            format!("#{:02x}{:02x}{:02x}{:02x}", r, g, b, a)
+        };
+        serializer.serialize_str(&text)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Color32, D::Error> {
+        let text = String::deserialize(deserializer)?;
+        parse(&text).ok_or_else(|| de::Error::custom(t!("theme.invalid_color", text = text)))
+    }
+
+    pub(super) fn parse(text: &str) -> Option<Color32> {
+        let hex = text.trim().strip_prefix('#')?;
+        if !hex.is_ascii() {
+            return None;
+        }
+        // "#rgb" 的每一位重复一次，例如 #f80 即 #ff8800
+        if hex.len() == 3 {
+            let digit = |i: usize| u8::from_str_radix(&hex[i..i + 1], 16).ok().map(|d| d * 17);
+            return Some(Color32::from_rgb(digit(0)?, digit(1)?, digit(2)?));
+        }
+        if hex.len() != 6 && hex.len() != 8 {
+            return None;
+        }
+        let byte = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).ok();
+        let (r, g, b) = (byte(0)?, byte(2)?, byte(4)?);
+        let a = if hex.len() == 8 { byte(6)? } else { 255 };
+        Some(Color32::from_rgba_unmultiplied(r, g, b, a))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hex_parses_short_long_and_alpha_forms() {
+        assert_eq!(hex::parse("#f80"), Some(Color32::from_rgb(0xff, 0x88, 0x00)));
+        assert_eq!(hex::parse(" #1E1e1e "), Some(Color32::from_rgb(0x1e, 0x1e, 0x1e)));
+        assert_eq!(hex::parse("#d9775733"), Some(Color32::from_rgba_unmultiplied(0xd9, 0x77, 0x57, 0x33)));
+        for text in ["1e1e1e", "#1e1e1", "#gg0000", "#ffff", "#", "#ééé"] {
+            assert_eq!(hex::parse(text), None, "{}", text);
+        }
+    }
+
+    #[test]
+    fn library_loads_theme_files_and_reports_errors() {
+        let dir = tempfile::tempdir().unwrap();
+        let colors = r##""base": "#fff", "mantle": "#fff", "crust": "#fff", "surface0": "#eee", "surface1": "#ddd",
+            "text": "#000", "lavender": "#00f", "green": "#0f0", "red": "#f00", "yellow": "#ff0",
+            "accent": "#00f", "muted": "#888", "border": "#ccc", "highlight": "#0000ff33""##;
+        let syntax = r##""key": "#00f", "string": "#a00", "number": "#080", "keyword": "#00f", "punctuation": "#333""##;
+        fs::write(dir.path().join("paper.json"), format!(r#"{{"colors": {{{}}}, "syntax": {{{}}}}}"#, colors, syntax)).unwrap();
+        fs::write(dir.path().join("broken.json"), format!(r#"{{"colors": {{{}}}, "syntax": {{{}}}}}"#, colors.replace("#888", "grey"), syntax)).unwrap();
+        // 与内置主题同名的文件被忽略
+        fs::write(dir.path().join("Dark.json"), "{}").unwrap();
+
+        let mut library = ThemeLibrary::new(dir.path().to_path_buf());
+        let errors = library.poll().unwrap();
+        assert_eq!(errors.len(), 1);
+        assert!(errors[0].starts_with("broken.json: ") && errors[0].contains(&t!("theme.invalid_color", text = "grey")), "{}", errors[0]);
+        assert_eq!(library.names().collect::<Vec<_>>(), ["paper"]);
+        // 省略 dark 时按背景亮度判断，省略注释颜色时使用默认值
+        let paper = library.get("paper").unwrap();
+        assert!(!paper.dark);
+        assert_eq!(paper.syntax.comment, default_comment_color());
+        assert_eq!(paper.colors.highlight, Color32::from_rgba_unmultiplied(0, 0, 0xff, 0x33));
+    }
+
+    #[test]
+    fn export_does_not_overwrite_existing_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let library = ThemeLibrary::new(dir.path().join("themes"));
+        let first = library.export("dark", &Palette::DARK).unwrap();
+        fs::write(&first, "edited").unwrap();
+        let second = library.export("dark", &Palette::DARK).unwrap();
+        assert_eq!(second.file_name().unwrap(), "dark_1.json");
+        assert_eq!(fs::read_to_string(&first).unwrap(), "edited");
+        let exported: ThemeFile = serde_json::from_str(&fs::read_to_string(&second).unwrap()).unwrap();
+        assert_eq!(exported.colors.base, Palette::DARK.colors.base);
+        assert_eq!(exported.dark, Some(true));
+    }
+}
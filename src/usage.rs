@@ -0,0 +1,336 @@
+// 用量统计：解析 Claude Code 会话记录 (~/.claude/projects/**/*.jsonl)，按配置/日期/项目汇总 Token 与费用
+
+use crate::audit::SwitchHistory;
+use crate::csv;
+use crate::t;
+use chrono::{DateTime, Local, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fs;
+use std::path::Path;
+
+pub const PRICING_FILE_NAME: &str = "pricing.json";
+
+// 单条助手消息的用量
+#[derive(Clone)]
+pub struct UsageRecord {
+    pub session_id: String,
+    pub project: String,
+    pub model: String,
+    pub timestamp: DateTime<Utc>,
+    pub input: u64,
+    pub output: u64,
+    pub cache_write: u64,
+    pub cache_read: u64,
+}
+
+// 扫描所有项目目录下的会话记录
+pub fn scan_transcripts(projects_dir: &Path) -> Vec<UsageRecord> {
+    let mut records = Vec::new();
+    let mut seen = HashSet::new();
+    let Ok(projects) = fs::read_dir(projects_dir) else { return records };
+    for project in projects.filter_map(Result::ok).map(|e| e.path()).filter(|p| p.is_dir()) {
+        let project_name = project.file_name().and_then(|n| n.to_str()).unwrap_or_default().to_string();
+        let Ok(files) = fs::read_dir(&project) else { continue };
+        for file in files.filter_map(Result::ok).map(|e| e.path()) {
+            if file.extension().is_some_and(|ext| ext == "jsonl") {
+                parse_transcript(&file, &project_name, &mut seen, &mut records);
+            }
+        }
+    }
+    records.sort_by_key(|r| r.timestamp);
+    records
+}
+
+fn parse_transcript(path: &Path, project_name: &str, seen: &mut HashSet<String>, records: &mut Vec<UsageRecord>) {
+    let Ok(content) = fs::read_to_string(path) else { return };
+    for line in content.lines() {
+        let Ok(entry) = serde_json::from_str::<Value>(line) else { continue };
+        let Some(usage) = entry.pointer("/message/usage") else { continue };
+        let Some(timestamp) = entry.get("timestamp").and_then(Value::as_str)
+            .and_then(|t| DateTime::parse_from_rfc3339(t).ok())
+        else { continue };
+
+        // 流式输出时同一条消息会被写入多次，按消息 ID 和请求 ID 去重
+        let message_id = entry.pointer("/message/id").and_then(Value::as_str).unwrap_or_default();
+        let request_id = entry.get("requestId").and_then(Value::as_str).unwrap_or_default();
+        if !message_id.is_empty() && !seen.insert(format!("{}:{}", message_id, request_id)) {
+            continue;
+        }
+
+        let tokens = |key: &str| usage.get(key).and_then(Value::as_u64).unwrap_or(0);
+        records.push(UsageRecord {
+            session_id: entry.get("sessionId").and_then(Value::as_str).unwrap_or_default().to_string(),
+            // 优先使用记录中的工作目录，否则使用编码后的项目目录名
+            project: entry.get("cwd").and_then(Value::as_str).unwrap_or(project_name).to_string(),
+            model: entry.pointer("/message/model").and_then(Value::as_str).unwrap_or_default().to_string(),
+            timestamp: timestamp.with_timezone(&Utc),
+            input: tokens("input_tokens"),
+            output: tokens("output_tokens"),
+            cache_write: tokens("cache_creation_input_tokens"),
+            cache_read: tokens("cache_read_input_tokens"),
+        });
+    }
+}
+
+// --- 价格表（美元 / 百万 Token） ---
+#[derive(Serialize, Deserialize, Clone)]
+pub struct ModelPrice {
+    // 模型 ID 中包含该字符串即匹配，按顺序取第一个
+    pub pattern: String,
+    pub input: f64,
+    pub output: f64,
+    pub cache_write: f64,
+    pub cache_read: f64,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct PriceTable {
+    pub models: Vec<ModelPrice>,
+}
+
+impl Default for PriceTable {
+    fn default() -> Self {
+        let price = |pattern: &str, input: f64, output: f64| ModelPrice {
+            pattern: pattern.to_string(),
+            input,
+            output,
+            cache_write: input * 1.25,
+            cache_read: input * 0.1,
+        };
+        Self {
+            models: vec![
+                price("opus", 15.0, 75.0),
+                price("sonnet", 3.0, 15.0),
+                price("haiku", 0.8, 4.0),
+            ],
+        }
+    }
+}
+
+impl PriceTable {
+    pub fn load(path: &Path) -> Self {
+        fs::read_to_string(path)
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        let content = serde_json::to_string_pretty(self).map_err(|e| e.to_string())?;
+        fs::write(path, content).map_err(|e| e.to_string())
+    }
+
+    pub fn cost(&self, record: &UsageRecord) -> f64 {
+        let model = record.model.to_lowercase();
+        self.models
+            .iter()
+            .find(|p| !p.pattern.is_empty() && model.contains(&p.pattern.to_lowercase()))
+            .map_or(0.0, |p| {
+                (record.input as f64 * p.input
+                    + record.output as f64 * p.output
+                    + record.cache_write as f64 * p.cache_write
+                    + record.cache_read as f64 * p.cache_read)
+                    / 1_000_000.0
+            })
+    }
+}
+
+// --- 汇总 ---
+#[derive(Clone, Copy, PartialEq)]
+pub enum GroupBy {
+    Profile,
+    Day,
+    Project,
+}
+
+impl GroupBy {
+    pub fn label(self) -> &'static str {
+        match self {
+            GroupBy::Profile => t!("usage.group.profile"),
+            GroupBy::Day => t!("usage.group.day"),
+            GroupBy::Project => t!("usage.group.project"),
+        }
+    }
+}
+
+#[derive(Clone, Default)]
+pub struct UsageRow {
+    pub key: String,
+    pub sessions: usize,
+    pub input: u64,
+    pub output: u64,
+    pub cache_write: u64,
+    pub cache_read: u64,
+    pub cost: f64,
+}
+
+impl UsageRow {
+    pub fn total_tokens(&self) -> u64 {
+        self.input + self.output + self.cache_write + self.cache_read
+    }
+}
+
+// 按会话开始时生效的配置归属，再按指定维度汇总
+pub fn aggregate(
+    records: &[UsageRecord],
+    history: &SwitchHistory,
+    prices: &PriceTable,
+    group_by: GroupBy,
+    since: Option<DateTime<Utc>>,
+) -> Vec<UsageRow> {
+    let mut session_profiles: HashMap<&str, String> = HashMap::new();
+    for record in records {
+        // records 已按时间排序，第一条即会话开始
+        session_profiles.entry(record.session_id.as_str()).or_insert_with(|| {
+            history.profile_at(record.timestamp).unwrap_or(t!("usage.unknown_profile")).to_string()
+        });
+    }
+
+    let mut rows: BTreeMap<String, (UsageRow, HashSet<&str>)> = BTreeMap::new();
+    for record in records.iter().filter(|r| since.is_none_or(|s| r.timestamp >= s)) {
+        let key = match group_by {
+            GroupBy::Profile => session_profiles[record.session_id.as_str()].clone(),
+            GroupBy::Day => record.timestamp.with_timezone(&Local).format("%Y-%m-%d").to_string(),
+            GroupBy::Project => record.project.clone(),
+        };
+        let (row, sessions) = rows.entry(key.clone()).or_insert_with(|| {
+            (UsageRow { key, ..Default::default() }, HashSet::new())
+        });
+        sessions.insert(record.session_id.as_str());
+        row.input += record.input;
+        row.output += record.output;
+        row.cache_write += record.cache_write;
+        row.cache_read += record.cache_read;
+        row.cost += prices.cost(record);
+    }
+
+    let mut rows: Vec<UsageRow> = rows
+        .into_values()
+        .map(|(mut row, sessions)| {
+            row.sessions = sessions.len();
+            row
+        })
+        .collect();
+    // 日期按时间顺序，其余按费用降序
+    if group_by != GroupBy::Day {
+        rows.sort_by(|a, b| b.cost.total_cmp(&a.cost).then_with(|| b.total_tokens().cmp(&a.total_tokens())));
+    }
+    rows
+}
+
+pub fn to_csv(rows: &[UsageRow], group_by: GroupBy) -> String {
+    let mut csv = format!(
+        "{},sessions,input_tokens,output_tokens,cache_write_tokens,cache_read_tokens,cost_usd\n",
+        match group_by {
+            GroupBy::Profile => "profile",
+            GroupBy::Day => "day",
+            GroupBy::Project => "project",
+        }
+    );
+    for row in rows {
+        csv.push_str(&format!(
+            "{},{},{},{},{},{},{:.4}\n",
+            csv::field(&row.key), row.sessions, row.input, row.output, row.cache_write, row.cache_read, row.cost
+        ));
+    }
+    csv
+}
+
+// 千分位格式化 Token 数量
+pub fn format_tokens(value: u64) -> String {
+    let digits = value.to_string();
+    let mut result = String::new();
+    for (i, ch) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i).is_multiple_of(3) {
+            result.push(',');
+        }
+        result.push(ch);
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audit::{AuditAction, AuditEntry};
+
+    fn line(session: &str, time: &str, message_id: &str, request_id: &str, output: u64) -> String {
+        serde_json::json!({
+            "sessionId": session,
+            "timestamp": time,
+            "requestId": request_id,
+            "cwd": "/work/app",
+            "message": {"id": message_id, "model": "claude-sonnet-4", "usage": {"input_tokens": 10, "output_tokens": output}},
+        })
+        .to_string()
+    }
+
+    fn parse(lines: &[String]) -> Vec<UsageRecord> {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("session.jsonl");
+        fs::write(&path, lines.join("\n")).unwrap();
+        let mut records = Vec::new();
+        parse_transcript(&path, "-work-app", &mut HashSet::new(), &mut records);
+        records
+    }
+
+    #[test]
+    fn parse_transcript_dedupes_streamed_messages() {
+        let records = parse(&[
+            line("s", "2026-01-01T10:00:00Z", "m1", "r1", 5),
+            line("s", "2026-01-01T10:00:01Z", "m1", "r1", 5),
+            line("s", "2026-01-01T10:00:02Z", "m1", "r2", 7),
+            line("s", "2026-01-01T10:00:03Z", "", "r3", 1),
+            line("s", "2026-01-01T10:00:04Z", "", "r3", 1),
+            "{\"message\": {\"usage\": {}}}".to_string(),
+            "not json".to_string(),
+        ]);
+        assert_eq!(records.iter().map(|r| r.output).collect::<Vec<_>>(), [5, 7, 1, 1]);
+        assert_eq!(records[0].project, "/work/app");
+        assert_eq!(records[0].model, "claude-sonnet-4");
+    }
+
+    #[test]
+    fn aggregate_attributes_sessions_to_profile_at_start() {
+        let switch = |time: &str, profile: &str| AuditEntry { timestamp: time.to_string(), ..AuditEntry::new(AuditAction::SetAsDefault, profile, None, Ok(())) };
+        let history = SwitchHistory::from_entries(&[switch("2026-01-01T09:00:00Z", "a.json"), switch("2026-01-01T12:00:00Z", "b.json")]);
+        let records = parse(&[
+            line("before", "2026-01-01T08:00:00Z", "m1", "r1", 1),
+            // 会话跨越切换时按开始时的配置归属
+            line("first", "2026-01-01T11:00:00Z", "m2", "r2", 100),
+            line("first", "2026-01-01T13:00:00Z", "m3", "r3", 100),
+            line("second", "2026-01-01T13:00:00Z", "m4", "r4", 2),
+        ]);
+        let rows = aggregate(&records, &history, &PriceTable::default(), GroupBy::Profile, None);
+        let summary: Vec<_> = rows.iter().map(|row| (row.key.as_str(), row.sessions, row.output)).collect();
+        assert_eq!(summary, [("a.json", 1, 200), ("b.json", 1, 2), (t!("usage.unknown_profile"), 1, 1)]);
+        assert!((rows[0].cost - (20.0 * 3.0 + 200.0 * 15.0) / 1_000_000.0).abs() < 1e-12);
+
+        let since = DateTime::parse_from_rfc3339("2026-01-01T12:30:00Z").unwrap().with_timezone(&Utc);
+        let rows = aggregate(&records, &history, &PriceTable::default(), GroupBy::Profile, Some(since));
+        assert_eq!(rows.iter().map(|row| (row.key.as_str(), row.output)).collect::<Vec<_>>(), [("a.json", 100), ("b.json", 2)]);
+    }
+
+    #[test]
+    fn to_csv_escapes_fields() {
+        let row = |key: &str| UsageRow { key: key.to_string(), sessions: 1, cost: 0.5, ..Default::default() };
+        let csv = to_csv(&[row("plain"), row("a,b"), row("say \"hi\""), row("two\nlines")], GroupBy::Project);
+        assert_eq!(
+            csv,
+            "project,sessions,input_tokens,output_tokens,cache_write_tokens,cache_read_tokens,cost_usd\n\
+             plain,1,0,0,0,0,0.5000\n\
+             \"a,b\",1,0,0,0,0,0.5000\n\
+             \"say \"\"hi\"\"\",1,0,0,0,0,0.5000\n\
+             \"two\nlines\",1,0,0,0,0,0.5000\n"
+        );
+    }
+
+    #[test]
+    fn format_tokens_groups_thousands() {
+        for (value, text) in [(0, "0"), (999, "999"), (1_000, "1,000"), (999_999, "999,999"), (1_000_000, "1,000,000"), (u64::MAX, "18,446,744,073,709,551,615")] {
+            assert_eq!(format_tokens(value), text);
+        }
+    }
+}
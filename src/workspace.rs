@@ -0,0 +1,323 @@
+// 编辑器中打开的配置：每个标签页有独立的缓冲区和修改状态，
+// 撤销记录由 egui 按编辑器 Id 保存，因此同样按文档区分
+
+use crate::highlight::Highlighter;
+use chrono::{DateTime, Local};
+use claude_code_switcher::lint::{self, Finding};
+use claude_code_switcher::outline::{Outline, Segment};
+use eframe::egui;
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+
+pub struct Document {
+    pub id: u64,
+    pub path: PathBuf,
+    pub content: String,
+    // 最近一次从磁盘读取或保存的内容
+    pub original: String,
+    // 最近一次编辑的时间，自动保存据此判断是否已停止输入
+    pub last_edit: Option<Instant>,
+    pub autosave: AutosaveState,
+    // 打开和保存时的语义检查结果
+    pub findings: Vec<Finding>,
+    pub highlighter: Highlighter,
+    // 最近一次有效解析的大纲；内容无效时保留旧的结果并标记为过期
+    pub outline: Option<Outline>,
+    pub outline_stale: bool,
+    outline_revision: Option<u64>,
+    // 编辑器中的光标位置（字符偏移）
+    pub cursor: Option<usize>,
+    // 下一帧把光标移到这个字节偏移并滚动到可见
+    pub reveal: Option<usize>,
+}
+
+#[derive(Clone, PartialEq)]
+pub enum AutosaveState {
+    Idle,
+    Pending,
+    Saved(DateTime<Local>),
+    // 内容不是有效的 JSON，等下次编辑后再尝试
+    InvalidJson,
+    Failed,
+}
+
+impl Document {
+    pub fn name(&self) -> String {
+        self.path.file_name().unwrap_or_default().to_string_lossy().to_string()
+    }
+
+    pub fn is_modified(&self) -> bool {
+        self.content != self.original
+    }
+
+    pub fn relint(&mut self) {
+        self.findings = lint::lint(&self.content);
+    }
+
+    // 内容变化后重新解析大纲，失败时保留上一次的结果
+    pub fn refresh_outline(&mut self) {
+        self.highlighter.update(&self.content);
+        if self.outline_revision == Some(self.highlighter.revision()) {
+            return;
+        }
+        self.outline_revision = Some(self.highlighter.revision());
+        match Outline::parse(&self.content) {
+            Ok(outline) => {
+                self.outline = Some(outline);
+                self.outline_stale = false;
+            }
+            Err(_) => self.outline_stale = true,
+        }
+    }
+
+    // 光标所在节点的 JSON 路径
+    pub fn cursor_path(&self) -> Vec<Segment> {
+        let offset = self.cursor.and_then(|c| self.highlighter.char_to_byte(c));
+        match (&self.outline, offset) {
+            (Some(outline), Some(offset)) => outline.path_at(offset),
+            _ => Vec::new(),
+        }
+    }
+
+    pub fn mark_edited(&mut self) {
+        self.last_edit = Some(Instant::now());
+        self.autosave = AutosaveState::Pending;
+    }
+
+    // 同一文档在两个窗格中使用不同的编辑器，各自保留撤销记录
+    pub fn editor_id(&self, pane: Pane) -> egui::Id {
+        egui::Id::new(("editor", self.id, pane))
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum Pane {
+    Primary,
+    Secondary,
+}
+
+#[derive(Default)]
+pub struct Workspace {
+    documents: Vec<Document>,
+    primary: Option<u64>,
+    // 分屏时右侧窗格显示的文档
+    secondary: Option<u64>,
+    split: bool,
+    focus: Option<Pane>,
+    next_id: u64,
+}
+
+impl Workspace {
+    pub fn documents(&self) -> &[Document] {
+        &self.documents
+    }
+
+    pub fn is_split(&self) -> bool {
+        self.split
+    }
+
+    pub fn focus(&self) -> Pane {
+        self.focus.filter(|_| self.split).unwrap_or(Pane::Primary)
+    }
+
+    pub fn set_focus(&mut self, pane: Pane) {
+        self.focus = Some(pane);
+    }
+
+    pub fn find(&self, path: &Path) -> Option<&Document> {
+        self.documents.iter().find(|d| d.path == path)
+    }
+
+    pub fn find_mut(&mut self, path: &Path) -> Option<&mut Document> {
+        self.documents.iter_mut().find(|d| d.path == path)
+    }
+
+    // 有未保存修改的文档
+    pub fn modified_paths(&self) -> Vec<PathBuf> {
+        self.documents.iter().filter(|d| d.is_modified()).map(|d| d.path.clone()).collect()
+    }
+
+    // 放弃修改，恢复到最近一次读取或保存的内容
+    pub fn revert(&mut self, path: &Path) {
+        if let Some(document) = self.find_mut(path) {
+            document.content = document.original.clone();
+            document.last_edit = None;
+            document.autosave = AutosaveState::Idle;
+            document.relint();
+        }
+    }
+
+    pub fn get(&self, pane: Pane) -> Option<&Document> {
+        let id = self.pane_document(pane)?;
+        self.documents.iter().find(|d| d.id == id)
+    }
+
+    pub fn get_mut(&mut self, pane: Pane) -> Option<&mut Document> {
+        let id = self.pane_document(pane)?;
+        self.documents.iter_mut().find(|d| d.id == id)
+    }
+
+    // 获得焦点的窗格中的文档，保存、格式化等操作都作用于它
+    pub fn active(&self) -> Option<&Document> {
+        self.get(self.focus())
+    }
+
+    pub fn active_mut(&mut self) -> Option<&mut Document> {
+        self.get_mut(self.focus())
+    }
+
+    pub fn active_path(&self) -> Option<&Path> {
+        self.active().map(|d| d.path.as_path())
+    }
+
+    fn pane_document(&self, pane: Pane) -> Option<u64> {
+        match pane {
+            Pane::Primary => self.primary,
+            Pane::Secondary if self.split => self.secondary,
+            Pane::Secondary => None,
+        }
+    }
+
+    fn show_in(&mut self, pane: Pane, id: u64) {
+        match pane {
+            Pane::Primary => self.primary = Some(id),
+            Pane::Secondary => self.secondary = Some(id),
+        }
+        self.focus = Some(pane);
+    }
+
+    // 在获得焦点的窗格中显示已打开的文档
+    pub fn activate(&mut self, path: &Path) -> bool {
+        let Some(id) = self.find(path).map(|d| d.id) else { return false };
+        self.show_in(self.focus(), id);
+        true
+    }
+
+    // 新建标签页并在获得焦点的窗格中显示
+    pub fn open(&mut self, path: PathBuf, content: String) {
+        let id = self.next_id;
+        self.next_id += 1;
+        let findings = lint::lint(&content);
+        self.documents.push(Document {
+            id,
+            path,
+            original: content.clone(),
+            content,
+            last_edit: None,
+            autosave: AutosaveState::Idle,
+            findings,
+            highlighter: Highlighter::default(),
+            outline: None,
+            outline_stale: false,
+            outline_revision: None,
+            cursor: None,
+            reveal: None,
+        });
+        self.show_in(self.focus(), id);
+    }
+
+    pub fn close(&mut self, path: &Path) {
+        let Some(index) = self.documents.iter().position(|d| d.path == path) else { return };
+        let id = self.documents.remove(index).id;
+        // 关闭后显示相邻的标签页
+        let neighbour = self.documents.get(index.min(self.documents.len().saturating_sub(1))).map(|d| d.id);
+        if self.primary == Some(id) {
+            self.primary = neighbour;
+        }
+        if self.secondary == Some(id) {
+            self.secondary = neighbour;
+        }
+        // 最后一个标签页关闭后退出分屏，下次打开的文档不会落在空的右侧窗格
+        if self.documents.is_empty() {
+            self.split = false;
+            self.focus = Some(Pane::Primary);
+        }
+    }
+
+    pub fn close_all(&mut self) {
+        self.documents.clear();
+        self.primary = None;
+        self.secondary = None;
+        self.split = false;
+        self.focus = Some(Pane::Primary);
+    }
+
+    pub fn rename(&mut self, old: &Path, new: PathBuf) {
+        if let Some(document) = self.documents.iter_mut().find(|d| d.path == old) {
+            document.path = new;
+        }
+    }
+
+    // 打开分屏时右侧默认显示另一个标签页
+    pub fn toggle_split(&mut self) {
+        self.split = !self.split;
+        if self.split {
+            let other = self.documents.iter().map(|d| d.id).find(|&id| Some(id) != self.primary);
+            self.secondary = self.secondary.filter(|id| self.documents.iter().any(|d| d.id == *id)).or(other).or(self.primary);
+            self.focus = Some(Pane::Secondary);
+        } else {
+            self.focus = Some(Pane::Primary);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn workspace(names: &[&str]) -> Workspace {
+        let mut workspace = Workspace::default();
+        for name in names {
+            workspace.open(PathBuf::from(name), "{}".to_string());
+        }
+        workspace
+    }
+
+    fn shown(workspace: &Workspace, pane: Pane) -> Option<&str> {
+        workspace.get(pane).and_then(|d| d.path.to_str())
+    }
+
+    #[test]
+    fn closing_active_tab_shows_its_neighbour() {
+        let mut workspace = workspace(&["a.json", "b.json", "c.json"]);
+        assert!(workspace.activate(Path::new("b.json")));
+        // 关闭中间的标签页后显示右侧相邻的标签页
+        workspace.close(Path::new("b.json"));
+        assert_eq!(workspace.active_path(), Some(Path::new("c.json")));
+        // 关闭最后一个标签页后显示左侧相邻的标签页
+        workspace.close(Path::new("c.json"));
+        assert_eq!(workspace.active_path(), Some(Path::new("a.json")));
+        // 关闭未显示的标签页不影响当前文档
+        workspace.open(PathBuf::from("d.json"), "{}".to_string());
+        workspace.close(Path::new("a.json"));
+        assert_eq!(workspace.active_path(), Some(Path::new("d.json")));
+        workspace.close(Path::new("d.json"));
+        assert!(workspace.active().is_none());
+    }
+
+    #[test]
+    fn split_shows_another_tab_and_collapses_when_last_tab_closes() {
+        let mut workspace = workspace(&["a.json", "b.json"]);
+        workspace.toggle_split();
+        assert!(workspace.is_split());
+        assert_eq!(workspace.focus(), Pane::Secondary);
+        assert_eq!((shown(&workspace, Pane::Primary), shown(&workspace, Pane::Secondary)), (Some("b.json"), Some("a.json")));
+
+        // 右侧窗格的文档关闭后两侧显示剩下的同一个文档
+        workspace.close(Path::new("a.json"));
+        assert!(workspace.is_split());
+        assert_eq!((shown(&workspace, Pane::Primary), shown(&workspace, Pane::Secondary)), (Some("b.json"), Some("b.json")));
+
+        workspace.close(Path::new("b.json"));
+        assert!(!workspace.is_split());
+        assert_eq!(workspace.focus(), Pane::Primary);
+        workspace.open(PathBuf::from("c.json"), "{}".to_string());
+        assert_eq!(shown(&workspace, Pane::Primary), Some("c.json"));
+        assert_eq!(shown(&workspace, Pane::Secondary), None);
+
+        // 关闭分屏后焦点回到左侧窗格
+        workspace.toggle_split();
+        workspace.toggle_split();
+        assert_eq!(workspace.active_path(), Some(Path::new("c.json")));
+    }
+}